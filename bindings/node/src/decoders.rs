@@ -43,6 +43,12 @@ impl tk::Decoder for Decoder {
       .unwrap()
       .decode_chain(tokens)
   }
+
+  fn from_trainer_decoder(decoder: DecoderWrapper) -> Option<Self> {
+    Some(Decoder {
+      decoder: Some(Arc::new(RwLock::new(decoder))),
+    })
+  }
 }
 
 #[napi]
@@ -135,6 +135,26 @@ impl tk::Model for Model {
       .get_trainer()
       .into()
   }
+
+  fn model_type(&self) -> &'static str {
+    self
+      .model
+      .as_ref()
+      .expect("Uninitialized Model")
+      .read()
+      .unwrap()
+      .model_type()
+  }
+
+  fn get_unk_id(&self) -> Option<u32> {
+    self
+      .model
+      .as_ref()
+      .expect("Uninitialized Model")
+      .read()
+      .unwrap()
+      .get_unk_id()
+  }
 }
 
 #[derive(Default)]
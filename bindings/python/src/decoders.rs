@@ -67,6 +67,10 @@ impl Decoder for PyDecoder {
     fn decode_chain(&self, tokens: Vec<String>) -> tk::Result<Vec<String>> {
         self.decoder.decode_chain(tokens)
     }
+
+    fn from_trainer_decoder(decoder: DecoderWrapper) -> Option<Self> {
+        Some(decoder.into())
+    }
 }
 
 #[pymethods]
@@ -73,6 +73,14 @@ impl Model for PyModel {
     fn get_trainer(&self) -> Self::Trainer {
         self.model.read().unwrap().get_trainer().into()
     }
+
+    fn model_type(&self) -> &'static str {
+        self.model.read().unwrap().model_type()
+    }
+
+    fn get_unk_id(&self) -> Option<u32> {
+        self.model.read().unwrap().get_unk_id()
+    }
 }
 
 impl<I> From<I> for PyModel
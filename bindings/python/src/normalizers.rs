@@ -88,6 +88,12 @@ impl PyNormalizer {
                     }
                     NormalizerWrapper::Replace(_) => Py::new(py, (PyReplace {}, base))?.into_py(py),
                     NormalizerWrapper::Nmt(_) => Py::new(py, (PyNmt {}, base))?.into_py(py),
+                    NormalizerWrapper::RegexReplace(_)
+                    | NormalizerWrapper::SentencePieceNormalizer(_)
+                    | NormalizerWrapper::UnicodeNormalizer(_)
+                    | NormalizerWrapper::ConditionalNormalizer(_) => {
+                        Py::new(py, base)?.into_py(py)
+                    }
                 },
             },
         })
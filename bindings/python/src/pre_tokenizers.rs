@@ -77,6 +77,7 @@ impl PyPreTokenizer {
                         PreTokenizerWrapper::WhitespaceSplit(_) => {
                             Py::new(py, (PyWhitespaceSplit {}, base))?.into_py(py)
                         }
+                        PreTokenizerWrapper::KeepWhitespaceSplit(_) => Py::new(py, base)?.into_py(py),
                         PreTokenizerWrapper::ByteLevel(_) => {
                             Py::new(py, (PyByteLevel {}, base))?.into_py(py)
                         }
@@ -4,15 +4,15 @@ extern crate criterion;
 mod common;
 
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Write};
 use std::path::Path;
 
-use criterion::Criterion;
+use criterion::{black_box, Criterion};
 use tokenizers::models::bpe::{BpeTrainerBuilder, BPE};
 use tokenizers::models::TrainerWrapper;
 use tokenizers::pre_tokenizers::byte_level::ByteLevel;
 use tokenizers::pre_tokenizers::whitespace::Whitespace;
-use tokenizers::tokenizer::{AddedToken, EncodeInput};
+use tokenizers::tokenizer::{AddedToken, EncodeInput, EncodeOptions};
 use tokenizers::Tokenizer;
 
 use common::{iter_bench_encode, iter_bench_encode_batch, iter_bench_train};
@@ -34,10 +34,13 @@ fn bench_gpt2(c: &mut Criterion) {
         .build()
         .unwrap();
     let tokenizer = create_gpt2_tokenizer(bpe);
+    let mut texts: Vec<String> = vec![];
     let mut lines: Vec<EncodeInput> = vec![];
     let mut batches: Vec<Vec<EncodeInput>> = vec![vec![]];
     for line in BufReader::new(File::open(Path::new("data/big.txt")).unwrap()).lines() {
-        let line: EncodeInput = line.unwrap().into();
+        let line = line.unwrap();
+        texts.push(line.clone());
+        let line: EncodeInput = line.into();
         lines.push(line.clone());
         if batches.last().unwrap().len() >= BATCH_SIZE {
             batches.push(vec![]);
@@ -53,6 +56,30 @@ fn bench_gpt2(c: &mut Criterion) {
         b.iter_custom(|iters| iter_bench_encode_batch(iters, tokenizer.deref(), &batches))
     });
 
+    // A batch with a high length variance, where a few inputs are a lot longer than the others
+    let mut texts = texts.iter().cycle();
+    let varied: Vec<EncodeInput> = (0..BATCH_SIZE)
+        .map(|i| {
+            let n_lines = if i % 100 == 0 { 200 } else { 1 };
+            let text: Vec<&str> = texts.by_ref().take(n_lines).map(|t| t.as_str()).collect();
+            text.join(" ").into()
+        })
+        .collect();
+    for (name, sort_for_parallel) in [
+        ("BPE GPT2 encode batch, length variance", false),
+        ("BPE GPT2 encode batch, length variance, sorted", true),
+    ] {
+        let options = EncodeOptions {
+            sort_for_parallel,
+            ..Default::default()
+        };
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                black_box(tokenizer.encode_batch_with_options(varied.clone(), false, &options))
+            })
+        });
+    }
+
     let bpe = BPE::from_file("data/gpt2-vocab.json", "data/gpt2-merges.txt")
         .cache_capacity(0)
         .build()
@@ -100,6 +127,38 @@ fn bench_train(c: &mut Criterion) {
     });
 }
 
+fn bench_train_from_files_parallel(c: &mut Criterion) {
+    let trainer = BpeTrainerBuilder::default().show_progress(false).build();
+
+    // A toy corpus of 1000 files, made of lines of data/small.txt
+    let dir = tempfile::tempdir().unwrap();
+    let lines: Vec<String> = BufReader::new(File::open("data/small.txt").unwrap())
+        .lines()
+        .map(|line| line.unwrap())
+        .collect();
+    let paths: Vec<_> = (0..1000)
+        .map(|i| {
+            let path = dir.path().join(format!("{}.txt", i));
+            let mut file = File::create(&path).unwrap();
+            for line in lines.iter().cycle().skip(i * 10).take(100) {
+                writeln!(file, "{}", line).unwrap();
+            }
+            path
+        })
+        .collect();
+    let files: Vec<&Path> = paths.iter().map(|p| p.as_path()).collect();
+
+    for num_threads in [1, 0] {
+        let name = match num_threads {
+            1 => "BPE Train from 1000 files, 1 thread".to_string(),
+            _ => "BPE Train from 1000 files, all threads".to_string(),
+        };
+        c.bench_function(&name, |b| {
+            b.iter(|| black_box(trainer.train_from_files_parallel(&files, num_threads)))
+        });
+    }
+}
+
 criterion_group! {
     name = benches;
     config = Criterion::default().sample_size(20);
@@ -108,6 +167,6 @@ criterion_group! {
 criterion_group! {
     name = benches_train;
     config = Criterion::default().sample_size(10);
-    targets = bench_train
+    targets = bench_train, bench_train_from_files_parallel
 }
 criterion_main!(benches, benches_train);
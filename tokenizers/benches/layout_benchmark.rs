@@ -2,7 +2,7 @@
 extern crate criterion;
 
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Write};
 use std::path::Path;
 use std::time::{Duration, Instant};
 
@@ -65,6 +65,78 @@ pub fn bench_layout(c: &mut Criterion) {
             duration
         })
     });
+
+    let batch: Vec<Encoding> = encodeds.iter().take(1_000).cloned().collect();
+    c.bench_function("Encoding to_json_string batch", |b| {
+        b.iter_custom(|iters| {
+            let mut duration = Duration::new(0, 0);
+            for _ in 0..iters {
+                let mut sink = std::io::sink();
+                let start = Instant::now();
+                for encoding in &batch {
+                    let json = encoding.to_json_string().unwrap();
+                    sink.write_all(json.as_bytes()).unwrap();
+                }
+                duration = duration.checked_add(start.elapsed()).unwrap();
+            }
+            duration
+        })
+    });
+    c.bench_function("Encoding to_json_writer batch", |b| {
+        b.iter_custom(|iters| {
+            let mut duration = Duration::new(0, 0);
+            for _ in 0..iters {
+                let mut sink = std::io::sink();
+                let start = Instant::now();
+                for encoding in &batch {
+                    encoding.to_json_writer(&mut sink).unwrap();
+                }
+                duration = duration.checked_add(start.elapsed()).unwrap();
+            }
+            duration
+        })
+    });
+
+    // Around 1MB of decoded output
+    let mut total = 0;
+    let sequences: Vec<Vec<u32>> = encodeds
+        .iter()
+        .take_while(|encoding| {
+            total += tokenizer.decode(encoding.get_ids(), false).unwrap().len() + 1;
+            total <= 1_000_000
+        })
+        .map(|encoding| encoding.get_ids().to_vec())
+        .collect();
+    c.bench_function("decode_batch then write", |b| {
+        b.iter_custom(|iters| {
+            let mut duration = Duration::new(0, 0);
+            for _ in 0..iters {
+                let mut sink = std::io::sink();
+                let start = Instant::now();
+                let slices: Vec<&[u32]> = sequences.iter().map(|ids| ids.as_slice()).collect();
+                let decoded = tokenizer.decode_batch(&slices, false).unwrap();
+                for line in decoded {
+                    sink.write_all(line.as_bytes()).unwrap();
+                    sink.write_all(b"\n").unwrap();
+                }
+                duration = duration.checked_add(start.elapsed()).unwrap();
+            }
+            duration
+        })
+    });
+    c.bench_function("batch_decode_to_writer", |b| {
+        b.iter_custom(|iters| {
+            let mut duration = Duration::new(0, 0);
+            for _ in 0..iters {
+                let start = Instant::now();
+                tokenizer
+                    .batch_decode_to_writer(&sequences, false, "\n", std::io::sink())
+                    .unwrap();
+                duration = duration.checked_add(start.elapsed()).unwrap();
+            }
+            duration
+        })
+    });
 }
 
 criterion_group! {
@@ -1,19 +1,33 @@
+use crate::decoders::DecoderWrapper;
 use crate::tokenizer::{Decoder, Result};
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Clone, Debug, Serialize)]
 /// Allows decoding Original BPE by joining all the tokens and then replacing
-/// the suffix used to identify end-of-words by whitespaces
+/// the suffix used to identify end-of-words by whitespaces. When a `continuing_subword_prefix`
+/// is given, it is stripped from the subwords that continue a word, and the other subwords
+/// start a new word.
 #[serde(tag = "type")]
 #[non_exhaustive]
 pub struct BPEDecoder {
     pub suffix: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continuing_subword_prefix: Option<String>,
 }
 
 impl BPEDecoder {
     pub fn new(suffix: String) -> Self {
-        Self { suffix }
+        Self {
+            suffix,
+            continuing_subword_prefix: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_continuing_subword_prefix(mut self, prefix: String) -> Self {
+        self.continuing_subword_prefix = Some(prefix);
+        self
     }
 }
 
@@ -25,14 +39,81 @@ impl Default for BPEDecoder {
 
 impl Decoder for BPEDecoder {
     fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
-        let n = tokens.len() - 1;
+        let n = tokens.len().saturating_sub(1);
+        let prefix = self
+            .continuing_subword_prefix
+            .as_deref()
+            .filter(|prefix| !prefix.is_empty());
+        let mut after_suffix = false;
         Ok(tokens
             .into_iter()
             .enumerate()
             .map(|(i, token)| {
-                let replacement = if i == n { "" } else { " " };
-                token.replace(&self.suffix, replacement)
+                let mut decoded = match prefix {
+                    Some(prefix) => match token.strip_prefix(prefix) {
+                        Some(rest) => rest.to_owned(),
+                        None if i > 0 && !after_suffix => format!(" {}", token),
+                        None => token.clone(),
+                    },
+                    None => token.clone(),
+                };
+                if !self.suffix.is_empty() {
+                    let replacement = if i == n { "" } else { " " };
+                    after_suffix = token.ends_with(&self.suffix);
+                    decoded = decoded.replace(&self.suffix, replacement);
+                }
+                decoded
             })
             .collect())
     }
+
+    fn from_trainer_decoder(decoder: DecoderWrapper) -> Option<Self> {
+        match decoder {
+            DecoderWrapper::BPE(decoder) => Some(decoder),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_suffix() {
+        let decoder = BPEDecoder::default();
+        let tokens = ["hel", "lo</w>", "wor", "ld</w>"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(decoder.decode(tokens).unwrap(), "hello world");
+    }
+
+    #[test]
+    fn decode_continuing_subword_prefix() {
+        let decoder = BPEDecoder::new("".into()).with_continuing_subword_prefix("##".into());
+        let tokens = ["hel", "##lo", "wor", "##ld", "!"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(decoder.decode(tokens).unwrap(), "hello world !");
+
+        // With both a prefix and a suffix, the suffix already ends the word
+        let decoder = BPEDecoder::default().with_continuing_subword_prefix("##".into());
+        let tokens = ["hel", "##lo</w>", "wor", "##ld</w>"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(decoder.decode(tokens).unwrap(), "hello world");
+        assert!(decoder.decode(vec![]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn serialization() {
+        let decoder = BPEDecoder::default();
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(serialized, r#"{"type":"BPEDecoder","suffix":"</w>"}"#);
+
+        let decoder = decoder.with_continuing_subword_prefix("##".into());
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        let deserialized: BPEDecoder = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.continuing_subword_prefix, Some("##".into()));
+    }
 }
@@ -11,19 +11,63 @@ use serde::{Deserialize, Serialize};
 pub struct Fuse {
     #[serde(rename = "type")]
     type_: MustBe!("Fuse"),
+    /// Insert a space between two tokens, unless they already join naturally: when one of
+    /// them has a whitespace on this side, or when the second one starts with the
+    /// `continuing_subword_prefix`. Useful for models trained without any word boundary marker.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub space_between_tokens: bool,
+    /// The prefix of the tokens that continue the previous one, removed when fusing them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continuing_subword_prefix: Option<String>,
 }
 
 impl Fuse {
     pub fn new() -> Self {
         Self {
             type_: MustBe!("Fuse"),
+            space_between_tokens: false,
+            continuing_subword_prefix: None,
         }
     }
+
+    #[must_use]
+    pub fn with_space_between_tokens(mut self, enabled: bool) -> Self {
+        self.space_between_tokens = enabled;
+        self
+    }
+
+    #[must_use]
+    pub fn with_continuing_subword_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.continuing_subword_prefix = Some(prefix.into());
+        self
+    }
 }
 
 impl Decoder for Fuse {
     fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>> {
-        let new_string = tokens.join("");
+        if !self.space_between_tokens {
+            return Ok(vec![tokens.join("")]);
+        }
+
+        let mut new_string = String::new();
+        for token in &tokens {
+            let continuation = self
+                .continuing_subword_prefix
+                .as_deref()
+                .and_then(|prefix| token.strip_prefix(prefix));
+            match continuation {
+                Some(rest) => new_string.push_str(rest),
+                None => {
+                    let joins = new_string.is_empty()
+                        || new_string.ends_with(char::is_whitespace)
+                        || token.starts_with(char::is_whitespace);
+                    if !joins {
+                        new_string.push(' ');
+                    }
+                    new_string.push_str(token);
+                }
+            }
+        }
         Ok(vec![new_string])
     }
 }
@@ -40,4 +84,46 @@ mod tests {
             .unwrap();
         assert_eq!(res, vec!["Hey friend!"]);
     }
+
+    #[test]
+    fn decode_with_space_between_tokens() {
+        let tokens: Vec<String> = ["Hey", "my", " good", "fri", "##end", "!"]
+            .iter()
+            .map(|token| token.to_string())
+            .collect();
+
+        let decoder = Fuse::new();
+        let res = decoder.decode_chain(tokens.clone()).unwrap();
+        assert_eq!(res, vec!["Heymy goodfri##end!"]);
+
+        let decoder = Fuse::new().with_space_between_tokens(true);
+        let res = decoder.decode_chain(tokens.clone()).unwrap();
+        assert_eq!(res, vec!["Hey my good fri ##end !"]);
+
+        let decoder = decoder.with_continuing_subword_prefix("##");
+        let res = decoder.decode_chain(tokens).unwrap();
+        assert_eq!(res, vec!["Hey my good friend !"]);
+    }
+
+    #[test]
+    fn serialization() {
+        let decoder = Fuse::new();
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(serialized, r#"{"type":"Fuse"}"#);
+
+        let decoder = Fuse::new()
+            .with_space_between_tokens(true)
+            .with_continuing_subword_prefix("##");
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r###"{"type":"Fuse","space_between_tokens":true,"continuing_subword_prefix":"##"}"###
+        );
+        let deserialized: Fuse = serde_json::from_str(&serialized).unwrap();
+        assert!(deserialized.space_between_tokens);
+        assert_eq!(
+            deserialized.continuing_subword_prefix.as_deref(),
+            Some("##")
+        );
+    }
 }
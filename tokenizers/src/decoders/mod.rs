@@ -54,6 +54,10 @@ impl Decoder for DecoderWrapper {
             Self::Fuse(bf) => bf.decode_chain(tokens),
         }
     }
+
+    fn from_trainer_decoder(decoder: DecoderWrapper) -> Option<Self> {
+        Some(decoder)
+    }
 }
 
 impl_enum_from!(BPEDecoder, DecoderWrapper, BPE);
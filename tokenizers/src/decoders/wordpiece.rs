@@ -1,6 +1,7 @@
 use crate::tokenizer::{Decoder, Result};
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 #[derive(Deserialize, Clone, Debug, Serialize)]
 /// The WordPiece decoder takes care of decoding a list of wordpiece tokens
@@ -12,20 +13,31 @@ pub struct WordPiece {
     pub prefix: String,
     /// Whether to cleanup some tokenization artifacts (spaces before punctuation, ...)
     pub cleanup: bool,
+    /// Tokens of the vocabulary that start with the prefix while being whole words (like
+    /// `##hashtag`). They are never considered as continuing subwords.
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    pub whole_word_tokens: HashSet<String>,
 }
 
 impl WordPiece {
     pub fn new(prefix: String, cleanup: bool) -> Self {
-        Self { prefix, cleanup }
+        Self {
+            prefix,
+            cleanup,
+            whole_word_tokens: HashSet::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_whole_word_tokens<I: IntoIterator<Item = String>>(mut self, tokens: I) -> Self {
+        self.whole_word_tokens = tokens.into_iter().collect();
+        self
     }
 }
 
 impl Default for WordPiece {
     fn default() -> Self {
-        Self {
-            prefix: "##".to_owned(),
-            cleanup: true,
-        }
+        Self::new("##".to_owned(), true)
     }
 }
 pub fn cleanup(dirty_input: &str) -> String {
@@ -50,7 +62,7 @@ impl Decoder for WordPiece {
             .enumerate()
             .map(|(i, token)| {
                 if i != 0 {
-                    if token.starts_with(&self.prefix) {
+                    if token.starts_with(&self.prefix) && !self.whole_word_tokens.contains(token) {
                         *token = token.replacen(&self.prefix, "", 1);
                     } else {
                         *token = format!(" {}", token);
@@ -87,4 +99,41 @@ mod tests {
             "##uelo Araújo Noguera"
         );
     }
+
+    #[test]
+    fn whole_word_tokens_with_prefix() {
+        let decoder = WordPiece::new("##".to_string(), false)
+            .with_whole_word_tokens(vec!["##hashtag".to_string(), "##1".to_string()]);
+
+        let tokens = ["see", "##hashtag", "and", "note", "##1", "un", "##til"];
+        assert_eq!(
+            decoder
+                .decode(tokens.iter().map(|t| t.to_string()).collect())
+                .unwrap(),
+            "see ##hashtag and note ##1 until"
+        );
+
+        // Without them, the prefix is always stripped
+        assert_eq!(
+            WordPiece::new("##".to_string(), false)
+                .decode(tokens.iter().map(|t| t.to_string()).collect())
+                .unwrap(),
+            "seehashtag and note1 until"
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let decoder = WordPiece::default();
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r###"{"type":"WordPiece","prefix":"##","cleanup":true}"###
+        );
+
+        let decoder = decoder.with_whole_word_tokens(vec!["##1".to_string()]);
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        let deserialized: WordPiece = serde_json::from_str(&serialized).unwrap();
+        assert!(deserialized.whole_word_tokens.contains("##1"));
+    }
 }
@@ -24,15 +24,32 @@ pub enum Error {
     /// number of the line that caused the error.
     #[error("Merges text file invalid at line {0}")]
     BadMerges(usize),
+    /// When the fairseq dictionary file is in the wrong format. This error holds the line
+    /// number of the line that caused the error.
+    #[error("Fairseq dictionary invalid at line {0}")]
+    BadFairseqDict(usize),
     /// If a token found in merges, is not in the vocab
     #[error("Token `{0}` out of vocabulary")]
     MergeTokenOutOfVocabulary(String),
+    /// If a token referenced by a merge, as one of its parts or as its result, is not in the
+    /// vocab
+    #[error("Token `{token}` referenced by the merge `{} {}` is not in the vocabulary", .referenced_by_merge.0, .referenced_by_merge.1)]
+    MissingVocabEntry {
+        token: String,
+        referenced_by_merge: (String, String),
+    },
     /// If the provided unk token is out of vocabulary
     #[error("Unk token `{0}` not found in the vocabulary")]
     UnkTokenOutOfVocabulary(String),
     /// Dropout not between 0 and 1.
     #[error("Dropout should be between 0 and 1, inclusive")]
     InvalidDropout,
+    /// When the merges and the vocabulary don't match. This error holds all the issues found.
+    #[error("Inconsistent vocabulary: {}", .0.join("; "))]
+    InconsistentVocabulary(Vec<String>),
+    /// Sequence weight negative or not a number
+    #[error("Weight should be a non-negative number, got {0}")]
+    InvalidCorpusWeight(f64),
 }
 
 /// Provides access to the `FirstLastIterator` to any Iterator
@@ -5,7 +5,7 @@ use crate::utils::iter::ResultShunt;
 use serde_json::Value;
 use std::borrow::Cow;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::File,
     io::prelude::*,
     io::{BufRead, BufReader},
@@ -16,6 +16,8 @@ pub type Vocab = HashMap<String, u32>;
 type VocabR = HashMap<u32, String>;
 pub type MergeMap = HashMap<Pair, (u32, u32)>;
 pub type Merges = Vec<(String, String)>;
+/// The merges applied while tokenizing, as `(left_symbol_pos, right_symbol_pos, merge_rank)`
+pub type MergeTrace = Vec<(usize, usize, usize)>;
 
 struct Config {
     files: Option<(String, String)>,
@@ -177,7 +179,7 @@ impl BpeBuilder {
                 let b_id = vocab
                     .get(&b)
                     .ok_or_else(|| Error::MergeTokenOutOfVocabulary(b.to_owned()))?;
-                let new_token = format!("{}{}", a, &b[prefix_len..]);
+                let new_token = merged_token(&a, &b, prefix_len);
                 let new_id = vocab
                     .get(&new_token)
                     .ok_or(Error::MergeTokenOutOfVocabulary(new_token))?;
@@ -296,6 +298,12 @@ pub(crate) fn convert_merges_to_hashmap<I: Iterator<Item = String>>(
     Ok(merges)
 }
 
+/// The token produced by merging `a` and `b`, where `b` starts with the continuing subword
+/// prefix of length `prefix_len`
+fn merged_token(a: &str, b: &str, prefix_len: usize) -> String {
+    format!("{}{}", a, &b[prefix_len..])
+}
+
 impl BPE {
     /// Initialize a `BpeBuilder`.
     pub fn builder() -> BpeBuilder {
@@ -304,10 +312,91 @@ impl BPE {
 
     /// Create a new BPE model with the given vocab and merges.
     pub fn new(vocab: Vocab, merges: Merges) -> Self {
-        Self::builder()
+        let bpe = Self::builder()
             .vocab_and_merges(vocab, merges)
             .build()
-            .unwrap()
+            .unwrap();
+        #[cfg(debug_assertions)]
+        bpe.verify_vocabulary_consistency().unwrap();
+        bpe
+    }
+
+    /// Check that the vocabulary and the merges are consistent with each other: each merge
+    /// must reference tokens of the vocabulary, produce the concatenation of its two parts,
+    /// and have a unique rank. Returns an error listing all the issues found. `BPE::new` runs
+    /// this check in debug builds.
+    pub fn verify_vocabulary_consistency(&self) -> Result<()> {
+        let mut issues = vec![];
+        let mut vocab: Vec<_> = self.vocab.iter().collect();
+        vocab.sort_by_key(|(_, id)| **id);
+        for (token, id) in vocab {
+            if self.vocab_r.get(id) != Some(token) {
+                issues.push(format!(
+                    "token `{}` with id {} missing from vocab_r",
+                    token, id
+                ));
+            }
+        }
+
+        let prefix_len = self
+            .continuing_subword_prefix
+            .as_ref()
+            .map_or(0, String::len);
+        let mut merges: Vec<_> = self.merges.iter().collect();
+        merges.sort_by_key(|(_, (rank, _))| *rank);
+        let mut ranks = HashSet::with_capacity(merges.len());
+        for ((a_id, b_id), (rank, new_id)) in merges {
+            if !ranks.insert(*rank) {
+                issues.push(format!("rank {} is used by multiple merges", rank));
+            }
+            let mut token = |id: &u32| {
+                let token = self.vocab_r.get(id);
+                if token.is_none() {
+                    issues.push(format!("merge {} references unknown id {}", rank, id));
+                }
+                token
+            };
+            if let (Some(a), Some(b), Some(new_token)) = (token(a_id), token(b_id), token(new_id)) {
+                if !b.is_char_boundary(prefix_len) || *new_token != merged_token(a, b, prefix_len) {
+                    issues.push(format!(
+                        "merge {} of `{}` and `{}` produces `{}`",
+                        rank, a, b, new_token
+                    ));
+                }
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InconsistentVocabulary(issues).into())
+        }
+    }
+
+    /// Check that all the tokens referenced by `merges` are in `vocab`: both parts of each
+    /// merge, and the token it produces, computed like when building the model by dropping the
+    /// length of `continuing_subword_prefix` from its second part. Building a `BPE` stops at the
+    /// first missing token, while this returns a `MissingVocabEntry` error for each of them, so
+    /// that they can all be fixed at once.
+    pub fn find_missing_vocab_entries(
+        vocab: &Vocab,
+        merges: &[(String, String)],
+        continuing_subword_prefix: Option<&str>,
+    ) -> Vec<Error> {
+        let prefix_len = continuing_subword_prefix.map_or(0, str::len);
+        let mut missing = vec![];
+        for (a, b) in merges {
+            let merged = merged_token(a, b, prefix_len);
+            for token in [a, b, &merged].iter() {
+                if !vocab.contains_key(token.as_str()) {
+                    missing.push(Error::MissingVocabEntry {
+                        token: token.to_string(),
+                        referenced_by_merge: (a.clone(), b.clone()),
+                    });
+                }
+            }
+        }
+        missing
     }
 
     /// Initialize a BpeBuilder model from vocab and merges files
@@ -347,6 +436,72 @@ impl BPE {
         Ok((vocab, merges))
     }
 
+    /// Write the vocabulary as a fairseq dictionary, with a `token count` line per token, by
+    /// decreasing count and then by increasing id. The counts are taken from `merge_counts`
+    /// when given, `0` being used for the missing tokens. Otherwise, the count of a token is
+    /// the inverse of the rank of the merge producing it, the tokens produced by no merge
+    /// coming first.
+    ///
+    /// The merges are not part of the dictionary, and the special tokens fairseq adds on its
+    /// own (like `<s>` or `<pad>`) are not written unless they are in the vocabulary.
+    pub fn to_fairseq_dict(
+        &self,
+        path: &Path,
+        merge_counts: Option<&HashMap<String, usize>>,
+    ) -> Result<()> {
+        let num_merges = self.merges.len();
+        let merge_ranks: HashMap<u32, u32> = self
+            .merges
+            .values()
+            .map(|(rank, new_id)| (*new_id, *rank))
+            .collect();
+        let mut entries: Vec<(&String, u32, usize)> = self
+            .vocab
+            .iter()
+            .map(|(token, id)| {
+                let count = match merge_counts {
+                    Some(counts) => counts.get(token).copied().unwrap_or(0),
+                    None => merge_ranks
+                        .get(id)
+                        .map_or(num_merges + 1, |rank| num_merges - *rank as usize),
+                };
+                (token, *id, count)
+            })
+            .collect();
+        entries.sort_unstable_by_key(|(_, id, count)| (std::cmp::Reverse(*count), *id));
+
+        let mut dict_file = std::io::BufWriter::new(File::create(path)?);
+        for (token, _, count) in entries {
+            writeln!(dict_file, "{} {}", token, count)?;
+        }
+        dict_file.flush()?;
+        Ok(())
+    }
+
+    /// Read a fairseq dictionary, as written by `to_fairseq_dict`, into a `BPE` without any
+    /// merges. The tokens get their ids in the order of the file, starting at `0`, and their
+    /// counts are dropped. Each line is split on its last space, and the `#fairseq:overwrite`
+    /// flag that may end a line is ignored.
+    pub fn from_fairseq_dict(path: &Path) -> Result<BPE> {
+        let dict_file = BufReader::new(File::open(path)?);
+        let mut vocab = Vocab::new();
+        for (i, line) in dict_file.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end();
+            let line = line.strip_suffix(" #fairseq:overwrite").unwrap_or(line);
+            if line.is_empty() {
+                continue;
+            }
+            let token = match line.rsplit_once(' ') {
+                Some((token, count)) if count.parse::<u64>().is_ok() => token,
+                _ => return Err(Error::BadFairseqDict(i + 1).into()),
+            };
+            let id = vocab.len() as u32;
+            vocab.entry(token.to_owned()).or_insert(id);
+        }
+        BPE::builder().vocab_and_merges(vocab, vec![]).build()
+    }
+
     /// Reset the cache.
     pub fn clear_cache(&self) {
         if let Some(ref cache) = self.cache {
@@ -366,7 +521,121 @@ impl BPE {
         &self.continuing_subword_prefix
     }
 
-    fn merge_word(&self, w: &str) -> Result<Word> {
+    /// Get the rank of the merge of `a` with `b`, if it exists. The ranks follow the order
+    /// of the merges, `0` being the merge with the highest priority. Both tokens must be given
+    /// as they appear in the vocabulary, including any `continuing_subword_prefix`.
+    pub fn get_merge_rank(&self, a: &str, b: &str) -> Option<u32> {
+        let pair = (*self.vocab.get(a)?, *self.vocab.get(b)?);
+        self.merges.get(&pair).map(|(rank, _)| *rank)
+    }
+
+    /// Get the number of merges
+    pub fn num_merges(&self) -> usize {
+        self.merges.len()
+    }
+
+    /// Get up to `n` of the merges with the highest priority, by increasing rank. The tokens
+    /// are given as they appear in the vocabulary, like for `get_merge_rank`.
+    pub fn top_n_merges(&self, n: usize) -> Vec<((String, String), u32)> {
+        let mut merges = self.merges_by_rank();
+        merges.truncate(n);
+        merges
+    }
+
+    /// Get up to `n` of the merges with the lowest priority, by decreasing rank
+    pub fn bottom_n_merges(&self, n: usize) -> Vec<((String, String), u32)> {
+        let mut merges = self.merges_by_rank();
+        merges.reverse();
+        merges.truncate(n);
+        merges
+    }
+
+    pub(crate) fn merges_by_rank(&self) -> Vec<((String, String), u32)> {
+        let mut merges: Vec<_> = self
+            .merges
+            .iter()
+            .map(|((a, b), (rank, _))| {
+                let pair = (self.vocab_r[a].clone(), self.vocab_r[b].clone());
+                (pair, *rank)
+            })
+            .collect();
+        merges.sort_unstable_by_key(|(_, rank)| *rank);
+        merges
+    }
+
+    /// The rank of the merge producing `token_id`, or `0` for the tokens produced by no merge.
+    /// This is `None` for the ids that are not in the vocabulary.
+    pub(crate) fn merge_rank(&self, token_id: u32) -> Option<usize> {
+        self.id_to_token(token_id)?;
+        let rank = self
+            .merges
+            .values()
+            .filter(|(_, new_id)| *new_id == token_id)
+            .map(|(rank, _)| *rank as usize)
+            .min();
+        Some(rank.unwrap_or(0))
+    }
+
+    /// Append the given merges after the existing ones, adding the tokens they produce to the
+    /// vocabulary. Both parts of each merge must be in the vocabulary, or be produced by one of
+    /// the previous merges. The merges that already exist are skipped, and nothing is added if
+    /// any of them is invalid. Returns the number of merges added.
+    pub fn add_merges(&mut self, merges: &[(String, String)]) -> Result<usize> {
+        let mut next_id = self.vocab_r.keys().max().map_or(0, |id| id + 1);
+        let mut next_rank = self
+            .merges
+            .values()
+            .map(|(rank, _)| rank + 1)
+            .max()
+            .unwrap_or(0);
+        let mut new_tokens: Vocab = HashMap::new();
+        let mut new_merges: MergeMap = HashMap::new();
+
+        for (a, b) in merges {
+            let id_of = |token: &str, new_tokens: &Vocab| {
+                self.vocab
+                    .get(token)
+                    .or_else(|| new_tokens.get(token))
+                    .copied()
+                    .ok_or_else(|| Error::MergeTokenOutOfVocabulary(token.to_owned()))
+            };
+            let pair = (id_of(a, &new_tokens)?, id_of(b, &new_tokens)?);
+            if self.merges.contains_key(&pair) || new_merges.contains_key(&pair) {
+                continue;
+            }
+
+            let b = match &self.continuing_subword_prefix {
+                Some(prefix) => b.strip_prefix(prefix.as_str()).unwrap_or(b),
+                None => b,
+            };
+            let new_token = format!("{}{}", a, b);
+            let new_id = match id_of(&new_token, &new_tokens) {
+                Ok(id) => id,
+                Err(_) => {
+                    new_tokens.insert(new_token, next_id);
+                    next_id += 1;
+                    next_id - 1
+                }
+            };
+            new_merges.insert(pair, (next_rank, new_id));
+            next_rank += 1;
+        }
+
+        for (token, id) in new_tokens {
+            self.vocab.insert(token.clone(), id);
+            self.vocab_r.insert(id, token);
+        }
+        let added = new_merges.len();
+        self.merges.extend(new_merges);
+        self.clear_cache();
+        Ok(added)
+    }
+
+    pub(super) fn merge_word(&self, w: &str) -> Result<Word> {
+        self.merge_word_traced(w, None)
+    }
+
+    fn merge_word_traced(&self, w: &str, trace: Option<&mut MergeTrace>) -> Result<Word> {
         let mut indices = w.char_indices().map(|(idx, _)| idx).peekable();
         let mut word = Word::with_capacity(w.len());
         let mut unk: Option<(u32, usize)> = None;
@@ -448,11 +717,29 @@ impl BPE {
             word.add(unk_id, unk_len);
         }
 
-        word.merge_all(&self.merges, self.dropout);
+        word.merge_all(&self.merges, self.dropout, trace);
 
         Ok(word)
     }
 
+    /// Tokenize the given sequence like `tokenize`, also returning the trace of the merges
+    /// applied, in order, as `(left_symbol_pos, right_symbol_pos, merge_rank)`. The positions
+    /// refer to the initial symbols of the sequence (its chars, or the fused unknown ones),
+    /// the merged symbol taking the position of its left part. The cache is not used.
+    pub fn tokenize_with_merges(&self, sequence: &str) -> Result<(Vec<Token>, MergeTrace)> {
+        let mut trace = vec![];
+        if sequence.is_empty() {
+            return Ok((vec![], trace));
+        }
+        if self.ignore_merges {
+            if let Some(id) = self.vocab.get(sequence) {
+                return Ok((vec![Token::new(*id, sequence.to_owned(), (0, 0))], trace));
+            }
+        }
+        let word = self.merge_word_traced(sequence, Some(&mut trace))?;
+        Ok((self.word_to_tokens(&word).collect(), trace))
+    }
+
     fn word_to_tokens<'a, 'b: 'a>(&'a self, word: &'b Word) -> impl Iterator<Item = Token> + 'a {
         word.get_chars_iter()
             .zip(word.get_offsets_iter())
@@ -556,6 +843,10 @@ impl Model for BPE {
     fn get_trainer(&self) -> BpeTrainer {
         BpeTrainer::default()
     }
+
+    fn model_type(&self) -> &'static str {
+        "BPE"
+    }
 }
 
 #[cfg(test)]
@@ -796,6 +1087,277 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bpe_merge_rank() {
+        let vocab: Vocab = vec![
+            ("h".to_string(), 0),
+            ("e".to_string(), 1),
+            ("l".to_string(), 2),
+            ("o".to_string(), 3),
+            ("he".to_string(), 4),
+            ("ll".to_string(), 5),
+            ("llo".to_string(), 6),
+            ("hello".to_string(), 7),
+        ]
+        .into_iter()
+        .collect();
+        let merges = vec![
+            ("h".to_string(), "e".to_string()),
+            ("l".to_string(), "l".to_string()),
+            ("ll".to_string(), "o".to_string()),
+            ("he".to_string(), "llo".to_string()),
+        ];
+        let bpe = BPE::new(vocab, merges.clone());
+
+        assert_eq!(bpe.num_merges(), merges.len());
+        for (rank, (a, b)) in merges.iter().enumerate() {
+            assert_eq!(bpe.get_merge_rank(a, b), Some(rank as u32));
+        }
+        assert_eq!(bpe.get_merge_rank("he", "llo"), Some(3));
+        assert_eq!(bpe.get_merge_rank("e", "h"), None);
+        assert_eq!(bpe.get_merge_rank("he", "unknown"), None);
+        assert_eq!(BPE::default().num_merges(), 0);
+    }
+
+    #[test]
+    fn test_bpe_top_and_bottom_merges() {
+        let vocab: Vocab = [
+            ("a", 0),
+            ("b", 1),
+            ("c", 2),
+            ("ab", 3),
+            ("bc", 4),
+            ("abc", 5),
+        ]
+        .iter()
+        .map(|(token, id)| (token.to_string(), *id))
+        .collect();
+        let merges: Merges = [("a", "b"), ("b", "c"), ("ab", "c")]
+            .iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect();
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .build()
+            .unwrap();
+
+        let pair = |a: &str, b: &str, rank| ((a.to_string(), b.to_string()), rank);
+        assert_eq!(
+            bpe.top_n_merges(2),
+            vec![pair("a", "b", 0), pair("b", "c", 1)]
+        );
+        assert_eq!(
+            bpe.bottom_n_merges(2),
+            vec![pair("ab", "c", 2), pair("b", "c", 1)]
+        );
+        assert_eq!(bpe.top_n_merges(10).len(), 3);
+        assert!(bpe.bottom_n_merges(0).is_empty());
+    }
+
+    #[test]
+    fn test_bpe_add_merges() {
+        let vocab: Vocab = vec![
+            ("a".to_string(), 0),
+            ("##b".to_string(), 1),
+            ("##c".to_string(), 2),
+            ("ab".to_string(), 3),
+        ]
+        .into_iter()
+        .collect();
+        let merges = vec![("a".to_string(), "##b".to_string())];
+        let mut bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .continuing_subword_prefix("##".to_string())
+            .build()
+            .unwrap();
+        assert_eq!(bpe.tokenize("abc").unwrap().len(), 2);
+
+        // The second merge uses the token produced by the first one, and the known merge is
+        // skipped
+        let new_merges = vec![
+            ("##b".to_string(), "##c".to_string()),
+            ("a".to_string(), "##bc".to_string()),
+            ("a".to_string(), "##b".to_string()),
+        ];
+        assert_eq!(bpe.add_merges(&new_merges).unwrap(), 2);
+        assert_eq!(bpe.token_to_id("##bc"), Some(4));
+        assert_eq!(bpe.token_to_id("abc"), Some(5));
+        assert_eq!(bpe.get_merge_rank("##b", "##c"), Some(1));
+        assert_eq!(bpe.get_merge_rank("a", "##bc"), Some(2));
+        assert!(bpe.verify_vocabulary_consistency().is_ok());
+        // `ab` is merged first
+        let tokens: Vec<_> = bpe
+            .tokenize("abc")
+            .unwrap()
+            .into_iter()
+            .map(|t| t.value)
+            .collect();
+        assert_eq!(tokens, vec!["ab", "##c"]);
+        assert_eq!(bpe.add_merges(&new_merges).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_bpe_add_merges_unknown_token() {
+        let mut bpe = BPE::new(
+            vec![("a".to_string(), 0), ("b".to_string(), 1)]
+                .into_iter()
+                .collect(),
+            vec![],
+        );
+        let merges = vec![
+            ("a".to_string(), "b".to_string()),
+            ("ab".to_string(), "c".to_string()),
+        ];
+        let err = bpe.add_merges(&merges).unwrap_err();
+        assert_eq!(err.to_string(), "Token `c` out of vocabulary");
+        // Nothing was added
+        assert_eq!(bpe.num_merges(), 0);
+        assert_eq!(bpe.get_vocab_size(), 2);
+    }
+
+    #[test]
+    fn test_bpe_verify_vocabulary_consistency() {
+        let vocab: Vocab = vec![
+            ("a".to_string(), 0),
+            ("##b".to_string(), 1),
+            ("##c".to_string(), 2),
+            ("ab".to_string(), 3),
+            ("abc".to_string(), 4),
+        ]
+        .into_iter()
+        .collect();
+        let merges = vec![
+            ("a".to_string(), "##b".to_string()),
+            ("ab".to_string(), "##c".to_string()),
+        ];
+        let mut bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .continuing_subword_prefix("##".to_string())
+            .build()
+            .unwrap();
+        assert!(bpe.verify_vocabulary_consistency().is_ok());
+
+        // Dangling id, wrong merged token and duplicated rank
+        bpe.merges.insert((0, 7), (2, 3));
+        bpe.merges.insert((0, 2), (5, 4));
+        let err = bpe.verify_vocabulary_consistency().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Inconsistent vocabulary: merge 2 references unknown id 7; \
+             merge 5 of `a` and `##c` produces `abc`"
+        );
+
+        bpe.merges.insert((0, 1), (1, 3));
+        let err = bpe.verify_vocabulary_consistency().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("rank 1 is used by multiple merges"));
+    }
+
+    #[test]
+    fn missing_vocab_entries() {
+        let vocab: Vocab = [("a", 0), ("##b", 1), ("c", 2), ("ab", 3)]
+            .iter()
+            .map(|(token, id)| (token.to_string(), *id))
+            .collect();
+        let merge = |a: &str, b: &str| (a.to_string(), b.to_string());
+        let merges = vec![merge("a", "##b"), merge("ab", "##c"), merge("d", "##b")];
+
+        assert!(BPE::find_missing_vocab_entries(&vocab, &merges[..1], Some("##")).is_empty());
+
+        let missing: Vec<_> = BPE::find_missing_vocab_entries(&vocab, &merges, Some("##"))
+            .into_iter()
+            .map(|error| match error {
+                Error::MissingVocabEntry {
+                    token,
+                    referenced_by_merge,
+                } => (token, referenced_by_merge),
+                error => panic!("Unexpected error {:?}", error),
+            })
+            .collect();
+        assert_eq!(
+            missing,
+            vec![
+                ("##c".to_string(), merge("ab", "##c")),
+                ("abc".to_string(), merge("ab", "##c")),
+                ("d".to_string(), merge("d", "##b")),
+                ("db".to_string(), merge("d", "##b")),
+            ]
+        );
+
+        // Without the prefix, `a` and `##b` would produce `a##b`
+        let missing = BPE::find_missing_vocab_entries(&vocab, &merges[..1], None);
+        assert_eq!(
+            missing[0].to_string(),
+            "Token `a##b` referenced by the merge `a ##b` is not in the vocabulary"
+        );
+
+        // The merged token is the one the model builds, even when the second part lacks the
+        // prefix
+        let vocab: Vocab = [("a", 0), ("bc", 1), ("ac", 2)]
+            .iter()
+            .map(|(token, id)| (token.to_string(), *id))
+            .collect();
+        let merges = vec![merge("a", "bc")];
+        assert!(BPE::find_missing_vocab_entries(&vocab, &merges, Some("#")).is_empty());
+        assert!(BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .continuing_subword_prefix("#".to_string())
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn fairseq_dict() {
+        let vocab: Vocab = [("a", 0), ("b", 1), ("c", 2), ("ab", 3), ("abc", 4)]
+            .iter()
+            .map(|(token, id)| (token.to_string(), *id))
+            .collect();
+        let merge = |a: &str, b: &str| (a.to_string(), b.to_string());
+        let bpe = BPE::new(vocab.clone(), vec![merge("a", "b"), merge("ab", "c")]);
+
+        // The tokens produced by no merge come first, then by increasing merge rank
+        let dict_file = NamedTempFile::new().unwrap();
+        bpe.to_fairseq_dict(dict_file.path(), None).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dict_file.path()).unwrap(),
+            "a 3\nb 3\nc 3\nab 2\nabc 1\n"
+        );
+        let read = BPE::from_fairseq_dict(dict_file.path()).unwrap();
+        assert_eq!(read.get_vocab(), vocab);
+        assert_eq!(read.num_merges(), 0);
+
+        let counts: HashMap<String, usize> = [("ab", 10), ("c", 7), ("b", 7)]
+            .iter()
+            .map(|(token, count)| (token.to_string(), *count))
+            .collect();
+        bpe.to_fairseq_dict(dict_file.path(), Some(&counts))
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dict_file.path()).unwrap(),
+            "ab 10\nb 7\nc 7\na 0\nabc 0\n"
+        );
+        let read = BPE::from_fairseq_dict(dict_file.path()).unwrap();
+        assert_eq!(read.token_to_id("ab"), Some(0));
+        assert_eq!(read.token_to_id("abc"), Some(4));
+
+        let mut dict_file = NamedTempFile::new().unwrap();
+        dict_file
+            .write_all(b"hello world 12\n<mask> 0 #fairseq:overwrite\n")
+            .unwrap();
+        let read = BPE::from_fairseq_dict(dict_file.path()).unwrap();
+        assert_eq!(read.token_to_id("hello world"), Some(0));
+        assert_eq!(read.token_to_id("<mask>"), Some(1));
+
+        let mut dict_file = NamedTempFile::new().unwrap();
+        dict_file.write_all(b"a 1\nb\n").unwrap();
+        let err = BPE::from_fairseq_dict(dict_file.path()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::BadFairseqDict(2))
+        ));
+    }
+
     #[test]
     // Ensure `MergeTokenOutOfVocabulary` error is returned when it should be.
     fn test_bpe_from_file_merge_token_oov() {
@@ -984,4 +1546,75 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn merge_rank() {
+        let vocab: Vocab = [("a", 0), ("b", 1), ("c", 2), ("ab", 3), ("abc", 4)]
+            .iter()
+            .map(|(token, id)| (token.to_string(), *id))
+            .collect();
+        let merges = vec![("a".into(), "b".into()), ("ab".into(), "c".into())];
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .build()
+            .unwrap();
+        assert_eq!(bpe.merge_rank(0), Some(0));
+        assert_eq!(bpe.merge_rank(3), Some(0));
+        assert_eq!(bpe.merge_rank(4), Some(1));
+        assert_eq!(bpe.merge_rank(5), None);
+    }
+
+    #[test]
+    fn tokenize_with_merges() {
+        let merges: Vec<(String, String)> = [
+            ("u", "n"),
+            ("b", "e"),
+            ("l", "i"),
+            ("e", "v"),
+            ("a", "b"),
+            ("l", "e"),
+            ("un", "be"),
+            ("li", "ev"),
+            ("ab", "le"),
+            ("unbe", "liev"),
+        ]
+        .iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect();
+        let vocab: HashMap<String, u32> = ["u", "n", "b", "e", "l", "i", "v", "a"]
+            .iter()
+            .map(|c| c.to_string())
+            .chain(merges.iter().map(|(a, b)| format!("{}{}", a, b)))
+            .enumerate()
+            .map(|(id, token)| (token, id as u32))
+            .collect();
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, merges.clone())
+            .build()
+            .unwrap();
+
+        let (tokens, trace) = bpe.tokenize_with_merges("unbelievable").unwrap();
+        let tokens: Vec<_> = tokens.into_iter().map(|token| token.value).collect();
+        assert_eq!(tokens, ["unbeliev", "able"]);
+        assert_eq!(trace.len(), 10);
+        assert_eq!(&trace[..3], &[(0, 1, 0), (2, 3, 1), (4, 5, 2)]);
+
+        // Re-applying the trace on the chars gives the same tokens
+        let mut symbols: Vec<Option<String>> = "unbelievable"
+            .chars()
+            .map(|c| Some(c.to_string()))
+            .collect();
+        for (left, right, rank) in trace {
+            let (a, b) = (
+                symbols[left].take().unwrap(),
+                symbols[right].take().unwrap(),
+            );
+            assert_eq!(merges[rank], (a.clone(), b.clone()));
+            symbols[left] = Some(a + &b);
+        }
+        let merged: Vec<String> = symbols.into_iter().flatten().collect();
+        assert_eq!(merged, tokens);
+
+        assert!(bpe.tokenize_with_merges("").unwrap().1.is_empty());
+    }
 }
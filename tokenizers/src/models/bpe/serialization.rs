@@ -121,7 +121,7 @@ impl<'de> Visitor<'de> for BPEVisitor {
                 }
                 "vocab" => vocab = Some(map.next_value()?),
                 "merges" => merges = Some(map.next_value()?),
-                "type" => match map.next_value()? {
+                "type" => match map.next_value::<String>()?.as_ref() {
                     "BPE" => {}
                     u => {
                         return Err(serde::de::Error::invalid_value(
@@ -1,12 +1,16 @@
 #![allow(clippy::map_entry)]
 
-use super::{Pair, WithFirstLastIterator, Word, BPE};
+use super::{Error, Pair, WithFirstLastIterator, Word, BPE};
+use crate::decoders::bpe::BPEDecoder;
+use crate::decoders::DecoderWrapper;
 use crate::parallelism::*;
 use crate::tokenizer::{AddedToken, Result, Trainer};
 use crate::utils::progress::{ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::Path;
 
 #[derive(Debug, Eq)]
 struct Merge {
@@ -45,6 +49,8 @@ struct Config {
     continuing_subword_prefix: Option<String>,
     end_of_word_suffix: Option<String>,
     max_token_length: Option<usize>,
+    preserve_tokens: HashSet<String>,
+    corpora: Vec<(Vec<String>, u64)>,
 }
 
 /// A `BpeTrainerBuilder` can be used to create a `BpeTrainer` with a custom
@@ -66,6 +72,8 @@ impl Default for BpeTrainerBuilder {
                 continuing_subword_prefix: None,
                 end_of_word_suffix: None,
                 max_token_length: None,
+                preserve_tokens: HashSet::new(),
+                corpora: vec![],
             },
         }
     }
@@ -119,6 +127,13 @@ impl BpeTrainerBuilder {
         self
     }
 
+    /// Add the given chars to the initial alphabet
+    #[must_use]
+    pub fn add_initial_alphabet<I: IntoIterator<Item = char>>(mut self, chars: I) -> Self {
+        self.config.initial_alphabet.extend(chars);
+        self
+    }
+
     /// Set the continuing_subword_prefix
     #[must_use]
     pub fn continuing_subword_prefix(mut self, prefix: String) -> Self {
@@ -139,6 +154,24 @@ impl BpeTrainerBuilder {
         self
     }
 
+    /// Set the tokens to keep as atomic units. They are never merged into larger tokens.
+    #[must_use]
+    pub fn preserve_tokens(mut self, tokens: HashSet<String>) -> Self {
+        self.config.preserve_tokens = tokens;
+        self
+    }
+
+    /// Add an additional corpus, whose word counts get multiplied by `weight` before
+    /// computing the merges. The sequences fed through `Trainer::feed` have a weight of 1, and
+    /// each added corpus goes through the same pre-processing as them. When training without
+    /// feeding any sequence, its lines are split on whitespace instead. The weight is a whole
+    /// number so that the word counts stay exact.
+    #[must_use]
+    pub fn add_corpus<I: Iterator<Item = String>>(mut self, iterator: I, weight: u64) -> Self {
+        self.config.corpora.push((iterator.collect(), weight));
+        self
+    }
+
     /// Constructs the final BpeTrainer
     pub fn build(self) -> BpeTrainer {
         BpeTrainer {
@@ -151,6 +184,8 @@ impl BpeTrainerBuilder {
             continuing_subword_prefix: self.config.continuing_subword_prefix,
             end_of_word_suffix: self.config.end_of_word_suffix,
             max_token_length: self.config.max_token_length,
+            preserve_tokens: self.config.preserve_tokens,
+            corpora: self.config.corpora,
             words: HashMap::new(),
         }
     }
@@ -194,7 +229,16 @@ pub struct BpeTrainer {
     pub end_of_word_suffix: Option<String>,
     /// An optional parameter to limit the max length of any single token
     pub max_token_length: Option<usize>,
-
+    /// Tokens kept as atomic units: they are part of the initial vocabulary, each of their
+    /// occurrences in the words is a single symbol, and they are never merged with anything.
+    /// They are returned by `train` along with the special tokens, as regular added tokens,
+    /// so that the tokenizer also keeps them whole.
+    #[serde(default)]
+    pub preserve_tokens: HashSet<String>,
+
+    /// Additional weighted corpora, processed along with the sequences given to `feed`
+    #[serde(default)]
+    corpora: Vec<(Vec<String>, u64)>,
     words: HashMap<String, u64>,
 }
 
@@ -260,12 +304,25 @@ impl BpeTrainer {
         }
     }
 
+    /// Add the preserved tokens to the initial vocabulary
+    fn add_preserved_tokens(&self, w2id: &mut HashMap<String, u32>, id2w: &mut Vec<String>) {
+        let mut tokens: Vec<_> = self.preserve_tokens.iter().collect();
+        tokens.sort();
+        for token in tokens {
+            if !token.is_empty() && !w2id.contains_key(token) {
+                id2w.push(token.to_owned());
+                w2id.insert(token.to_owned(), (id2w.len() - 1) as u32);
+            }
+        }
+    }
+
     /// Compute the initial alphabet and limit it if relevant
     fn compute_alphabet(
         &self,
         wc: &HashMap<String, u64>,
         w2id: &mut HashMap<String, u32>,
         id2w: &mut Vec<String>,
+        prefixed_initial_alphabet: bool,
     ) {
         // Compute the alphabet from seen words
         let mut alphabet: HashMap<char, usize> = HashMap::new();
@@ -310,13 +367,24 @@ impl BpeTrainer {
 
         // Keep the initial alphabet (sorted for determinism)
         kept.sort_unstable_by_key(|k| (*k.0) as u32);
-        kept.into_iter().for_each(|(c, _)| {
-            let s = c.to_string();
+        let mut add_token = |s: String| {
             if !w2id.contains_key(&s) {
                 id2w.push(s.clone());
                 w2id.insert(s, (id2w.len() - 1) as u32);
             }
-        });
+        };
+        kept.iter().for_each(|(c, _)| add_token(c.to_string()));
+
+        // The chars of the initial alphabet must also be usable inside of a word
+        if let Some(prefix) = self
+            .continuing_subword_prefix
+            .as_ref()
+            .filter(|_| prefixed_initial_alphabet)
+        {
+            kept.iter()
+                .filter(|(c, _)| self.initial_alphabet.contains(c))
+                .for_each(|(c, _)| add_token(format!("{}{}", prefix, c)));
+        }
     }
 
     /// Tokenize words and add subwords to the vocabulary when relevant
@@ -325,16 +393,56 @@ impl BpeTrainer {
         wc: &HashMap<String, u64>,
         w2id: &mut HashMap<String, u32>,
         id2w: &mut Vec<String>,
+        preserved_ids: &mut HashSet<u32>,
         p: &Option<ProgressBar>,
     ) -> (Vec<Word>, Vec<u64>) {
         let mut words: Vec<Word> = Vec::with_capacity(wc.len());
         let mut counts: Vec<u64> = Vec::with_capacity(wc.len());
+        let mut preserved: Vec<&String> = self
+            .preserve_tokens
+            .iter()
+            .filter(|token| !token.is_empty())
+            .collect();
+        // Longest first, so that the longest preserved token wins
+        preserved.sort_by_key(|token| std::cmp::Reverse(token.len()));
 
         for (word, count) in wc {
             let mut current_word = Word::new();
             counts.push(*count);
 
-            for (is_first, is_last, c) in word.chars().with_first_and_last() {
+            let mut chars = word.char_indices().with_first_and_last();
+            while let Some((is_first, is_last, (i, c))) = chars.next() {
+                if let Some(token) = preserved
+                    .iter()
+                    .find(|token| word[i..].starts_with(**token))
+                {
+                    let mut s = token.to_string();
+                    let mut is_last = is_last;
+                    for _ in 1..token.chars().count() {
+                        is_last = match chars.next() {
+                            Some((_, is_last, _)) => is_last,
+                            None => true,
+                        };
+                    }
+                    if !is_first {
+                        if let Some(prefix) = &self.continuing_subword_prefix {
+                            s = format!("{}{}", prefix, s);
+                        }
+                    }
+                    if is_last {
+                        if let Some(suffix) = &self.end_of_word_suffix {
+                            s = format!("{}{}", s, suffix);
+                        }
+                    }
+                    if !w2id.contains_key(&s) {
+                        id2w.push(s.clone());
+                        w2id.insert(s.clone(), (id2w.len() - 1) as u32);
+                    }
+                    preserved_ids.insert(w2id[&s]);
+                    current_word.add(w2id[&s], 1);
+                    continue;
+                }
+
                 let mut s = c.to_string();
                 if w2id.contains_key(&s) {
                     // Found the initial char in the authorized alphabet
@@ -433,6 +541,24 @@ impl BpeTrainer {
         &self,
         word_counts: &HashMap<String, u64>,
         model: &mut BPE,
+    ) -> Result<Vec<AddedToken>> {
+        self.do_train_with(word_counts, model, false)
+    }
+
+    /// Train like `Trainer::train`, also adding each char of the initial alphabet with the
+    /// `continuing_subword_prefix`, so that WordPiece can use it inside of a word
+    pub(crate) fn train_with_prefixed_initial_alphabet(
+        &self,
+        model: &mut BPE,
+    ) -> Result<Vec<AddedToken>> {
+        self.do_train_with(&*self.words_to_train()?, model, true)
+    }
+
+    fn do_train_with(
+        &self,
+        word_counts: &HashMap<String, u64>,
+        model: &mut BPE,
+        prefixed_initial_alphabet: bool,
     ) -> Result<Vec<AddedToken>> {
         let mut word_to_id: HashMap<String, u32> = HashMap::with_capacity(self.vocab_size);
         let mut id_to_word: Vec<String> = Vec::with_capacity(self.vocab_size);
@@ -444,18 +570,30 @@ impl BpeTrainer {
         // 1. Add all special tokens to the vocabulary
         //
         self.add_special_tokens(&mut word_to_id, &mut id_to_word);
+        self.add_preserved_tokens(&mut word_to_id, &mut id_to_word);
 
         //
         // 2. Compute the initial alphabet
         //
-        self.compute_alphabet(word_counts, &mut word_to_id, &mut id_to_word);
+        self.compute_alphabet(
+            word_counts,
+            &mut word_to_id,
+            &mut id_to_word,
+            prefixed_initial_alphabet,
+        );
 
         //
         // 3. Tokenize words
         //
         self.update_progress(&progress, word_counts.len(), "Tokenize words");
-        let (words, counts) =
-            self.tokenize_words(word_counts, &mut word_to_id, &mut id_to_word, &progress);
+        let mut preserved_ids = HashSet::new();
+        let (words, counts) = self.tokenize_words(
+            word_counts,
+            &mut word_to_id,
+            &mut id_to_word,
+            &mut preserved_ids,
+            &progress,
+        );
         self.finalize_progress(&progress, words.len());
 
         //
@@ -489,6 +627,12 @@ impl BpeTrainer {
             }
 
             if queue.is_empty() {
+                // The corpus is too small to reach `vocab_size`, we keep what we have
+                warn!(
+                    "BPE training stopped with {} tokens instead of {}: no pair left to merge",
+                    word_to_id.len(),
+                    self.vocab_size
+                );
                 break;
             }
 
@@ -503,6 +647,11 @@ impl BpeTrainer {
                 break;
             }
 
+            // The preserved tokens are never absorbed by a merge
+            if preserved_ids.contains(&top.pair.0) || preserved_ids.contains(&top.pair.1) {
+                continue;
+            }
+
             let part_a = &id_to_word[top.pair.0 as usize];
             let mut part_b = id_to_word[top.pair.1 as usize].to_owned();
 
@@ -609,7 +758,148 @@ impl BpeTrainer {
             model.end_of_word_suffix = None;
         }
 
-        Ok(self.special_tokens.clone())
+        let mut added_tokens = self.special_tokens.clone();
+        let mut preserved: Vec<_> = self.preserve_tokens.iter().collect();
+        preserved.sort();
+        added_tokens.extend(
+            preserved
+                .into_iter()
+                .filter(|token| !token.is_empty())
+                .map(|token| AddedToken::from(token.to_owned(), false)),
+        );
+        Ok(added_tokens)
+    }
+
+    /// Continue the training of `base_bpe` on `new_data`, appending at most `new_merges` merges
+    /// to the existing ones. Each sequence of `new_data` is split on whitespace, and its words
+    /// are first segmented with the existing merges. All the tokens of `base_bpe` keep their
+    /// ids. Pairs involving the unknown token are never merged, and when there is no unknown
+    /// token, the chars missing from the vocabulary are ignored.
+    pub fn train_incremental<I: Iterator<Item = String>>(
+        &self,
+        mut base_bpe: BPE,
+        new_data: I,
+        new_merges: usize,
+    ) -> Result<BPE> {
+        // The existing merges must always apply during the segmentation
+        let dropout = base_bpe.dropout.take();
+
+        let mut counts: HashMap<String, u64> = HashMap::new();
+        for sequence in new_data {
+            for word in sequence.split_whitespace() {
+                *counts.entry(word.to_owned()).or_insert(0) += 1;
+            }
+        }
+        let mut words = counts
+            .into_iter()
+            .map(|(word, count)| Ok((base_bpe.merge_word(&word)?, count)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let unk_id = base_bpe
+            .unk_token
+            .as_ref()
+            .and_then(|unk| base_bpe.vocab.get(unk))
+            .copied();
+        let max_token_length = self.max_token_length.unwrap_or(usize::MAX);
+        let mut next_id = base_bpe.vocab_r.keys().max().map_or(0, |id| id + 1);
+
+        for _ in 0..new_merges {
+            let mut pair_counts: HashMap<Pair, u64> = HashMap::new();
+            for (word, count) in &words {
+                for pair in word.get_chars().windows(2) {
+                    if unk_id.is_some_and(|unk| pair.contains(&unk)) {
+                        continue;
+                    }
+                    *pair_counts.entry((pair[0], pair[1])).or_insert(0) += count;
+                }
+            }
+
+            let merged_token = |(a, b): &Pair| {
+                let b = &base_bpe.vocab_r[b];
+                let b = match &base_bpe.continuing_subword_prefix {
+                    Some(prefix) => b.strip_prefix(prefix.as_str()).unwrap_or(b),
+                    None => b,
+                };
+                format!("{}{}", base_bpe.vocab_r[a], b)
+            };
+            // Most frequent pair first, ties broken by the merged token for determinism
+            let best = pair_counts
+                .into_iter()
+                .filter(|(_, count)| *count >= self.min_frequency.max(1))
+                .map(|(pair, count)| (count, merged_token(&pair), pair))
+                .filter(|(_, token, _)| token.chars().count() <= max_token_length)
+                .max_by(|(ca, ta, _), (cb, tb, _)| ca.cmp(cb).then_with(|| tb.cmp(ta)));
+            let (token, (a, b)) = match best {
+                Some((_, token, pair)) => (token, pair),
+                None => break,
+            };
+
+            let new_id = match base_bpe.vocab.get(&token) {
+                Some(id) => *id,
+                None => {
+                    let id = next_id;
+                    next_id += 1;
+                    base_bpe.vocab.insert(token.clone(), id);
+                    base_bpe.vocab_r.insert(id, token);
+                    id
+                }
+            };
+            let rank = base_bpe.merges.len() as u32;
+            base_bpe.merges.insert((a, b), (rank, new_id));
+            for (word, _) in &mut words {
+                word.merge(a, b, new_id, usize::MAX);
+            }
+        }
+
+        base_bpe.dropout = dropout;
+        base_bpe.clear_cache();
+        Ok(base_bpe)
+    }
+
+    /// Train a new BPE model on the given files, reading and counting the words of the files
+    /// concurrently on a dedicated pool of `num_threads` threads (0 uses the default number of
+    /// threads). Like `train_incremental`, each line is split on whitespace, as well as the
+    /// lines of the weighted corpora, which get added to the files. The files are
+    /// processed serially when parallelism is disabled with `TOKENIZERS_PARALLELISM`.
+    pub fn train_from_files_parallel(
+        &self,
+        files: &[&Path],
+        num_threads: usize,
+    ) -> Result<(BPE, Vec<AddedToken>)> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()?;
+        let word_counts = pool.install(|| {
+            files
+                .into_maybe_par_iter()
+                .map(|file| -> Result<HashMap<String, u64>> {
+                    let content = std::fs::read_to_string(file)?;
+                    let mut counts = HashMap::new();
+                    for word in content.split_whitespace() {
+                        *counts.entry(word.to_owned()).or_insert(0) += 1;
+                    }
+                    Ok(counts)
+                })
+                .reduce(
+                    || Ok(HashMap::new()),
+                    |acc, counts| {
+                        let (mut acc, counts) = (acc?, counts?);
+                        for (word, count) in counts {
+                            *acc.entry(word).or_insert(0) += count;
+                        }
+                        Ok(acc)
+                    },
+                )
+        })?;
+
+        let mut word_counts = word_counts;
+        for (word, count) in self.count_corpora(&split_whitespace)? {
+            *word_counts.entry(word).or_insert(0) += count;
+        }
+
+        let mut model = BPE::default();
+        let added_tokens = self.do_train(&word_counts, &mut model)?;
+        Ok((model, added_tokens))
     }
 }
 
@@ -618,7 +908,7 @@ impl Trainer for BpeTrainer {
 
     /// Train a BPE model
     fn train(&self, model: &mut BPE) -> Result<Vec<AddedToken>> {
-        self.do_train(&self.words, model)
+        self.do_train(&*self.words_to_train()?, model)
     }
 
     /// Whether we should show progress
@@ -626,42 +916,144 @@ impl Trainer for BpeTrainer {
         self.show_progress
     }
 
+    /// A `BPEDecoder` able to join back the subwords, when they use a `continuing_subword_prefix`
+    fn decoder(&self) -> Option<DecoderWrapper> {
+        let prefix = self
+            .continuing_subword_prefix
+            .as_ref()
+            .filter(|prefix| !prefix.is_empty())?;
+        let suffix = self.end_of_word_suffix.clone().unwrap_or_default();
+        Some(
+            BPEDecoder::new(suffix)
+                .with_continuing_subword_prefix(prefix.clone())
+                .into(),
+        )
+    }
+
     fn feed<I, S, F>(&mut self, iterator: I, process: F) -> Result<()>
     where
         I: Iterator<Item = S> + Send,
         S: AsRef<str> + Send,
         F: Fn(&str) -> Result<Vec<String>> + Sync,
     {
-        let words: Result<HashMap<String, u64>> = iterator
+        let mut words = count_words(iterator, &process)?;
+        for (word, count) in self.count_corpora(&process)? {
+            *words.entry(word).or_insert(0) += count;
+        }
+        self.words = words;
+        Ok(())
+    }
+
+    /// Each sequence contributes `weight` times to the word counts. A weight of 0.0 ignores
+    /// the sequence, and a negative or non-finite weight is an error.
+    fn feed_weighted<I, S, F>(&mut self, iterator: I, process: F) -> Result<()>
+    where
+        I: Iterator<Item = (S, f64)> + Send,
+        S: AsRef<str> + Send,
+        F: Fn(&str) -> Result<Vec<String>> + Sync,
+    {
+        let weighted = iterator
             .maybe_par_bridge()
-            .map(|sequence| {
-                let words = process(sequence.as_ref())?;
+            .map(|(sequence, weight)| {
+                if !weight.is_finite() || weight < 0.0 {
+                    return Err(Error::InvalidCorpusWeight(weight).into());
+                }
                 let mut map = HashMap::new();
-                for word in words {
-                    map.entry(word).and_modify(|c| *c += 1).or_insert(1);
+                for word in process(sequence.as_ref())? {
+                    *map.entry(word).or_insert(0.0) += weight;
                 }
                 Ok(map)
             })
             .reduce(
                 || Ok(HashMap::new()),
-                |acc, ws| {
+                |acc: Result<HashMap<String, f64>>, ws| {
                     let mut acc = acc?;
                     for (k, v) in ws? {
-                        acc.entry(k).and_modify(|c| *c += v).or_insert(v);
+                        *acc.entry(k).or_insert(0.0) += v;
                     }
                     Ok(acc)
                 },
-            );
+            )?;
 
-        self.words = words?;
+        // Words whose weighted count rounds to 0 must not influence the vocabulary at all
+        let mut words: HashMap<String, u64> = weighted
+            .into_iter()
+            .map(|(word, count)| (word, count.round() as u64))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        for (word, count) in self.count_corpora(&process)? {
+            *words.entry(word).or_insert(0) += count;
+        }
+        self.words = words;
         Ok(())
     }
 }
 
+impl BpeTrainer {
+    /// The words fed to the trainer, or the ones of the corpora when nothing was fed, since
+    /// they haven't been counted yet
+    fn words_to_train(&self) -> Result<Cow<'_, HashMap<String, u64>>> {
+        if self.words.is_empty() && !self.corpora.is_empty() {
+            Ok(Cow::Owned(self.count_corpora(&split_whitespace)?))
+        } else {
+            Ok(Cow::Borrowed(&self.words))
+        }
+    }
+
+    /// Count the words of the weighted corpora, multiplied by their weight
+    fn count_corpora<F>(&self, process: &F) -> Result<HashMap<String, u64>>
+    where
+        F: Fn(&str) -> Result<Vec<String>> + Sync,
+    {
+        let mut words = HashMap::new();
+        for (corpus, weight) in self.corpora.iter().filter(|(_, weight)| *weight > 0) {
+            for (word, count) in count_words(corpus.iter(), process)? {
+                *words.entry(word).or_insert(0) += count * weight;
+            }
+        }
+        Ok(words)
+    }
+}
+
+/// The words of `sequence` when training without a tokenizer
+fn split_whitespace(sequence: &str) -> Result<Vec<String>> {
+    Ok(sequence.split_whitespace().map(str::to_owned).collect())
+}
+
+/// Count the words produced by `process` on each of the given sequences
+fn count_words<I, S, F>(iterator: I, process: &F) -> Result<HashMap<String, u64>>
+where
+    I: Iterator<Item = S> + Send,
+    S: AsRef<str> + Send,
+    F: Fn(&str) -> Result<Vec<String>> + Sync,
+{
+    iterator
+        .maybe_par_bridge()
+        .map(|sequence| {
+            let words = process(sequence.as_ref())?;
+            let mut map = HashMap::new();
+            for word in words {
+                map.entry(word).and_modify(|c| *c += 1).or_insert(1);
+            }
+            Ok(map)
+        })
+        .reduce(
+            || Ok(HashMap::new()),
+            |acc, ws| {
+                let mut acc = acc?;
+                for (k, v) in ws? {
+                    acc.entry(k).and_modify(|c| *c += v).or_insert(v);
+                }
+                Ok(acc)
+            },
+        )
+}
+
 #[cfg(test)]
 mod tests {
     use super::{BpeTrainer, Pair, BPE};
-    use std::collections::HashMap;
+    use crate::tokenizer::{AddedToken, Trainer};
+    use std::collections::{HashMap, HashSet};
 
     #[test]
     fn test_train() {
@@ -736,6 +1128,91 @@ mod tests {
         .collect();
         assert_eq!(model.merges, expected_merges);
     }
+    #[test]
+    fn bpe_train_small_corpus_stops_early() {
+        use crate::Model;
+
+        let word_counts: HashMap<String, u64> = [("low".into(), 2), ("lower".into(), 1)]
+            .iter()
+            .chain(&[("lowest".into(), 1)])
+            .cloned()
+            .collect();
+        let trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .vocab_size(1000)
+            .build();
+        let mut model = BPE::default();
+        trainer.do_train(&word_counts, &mut model).unwrap();
+
+        // Every word ends up being a single token
+        assert!(model.get_vocab_size() < 1000);
+        assert!(model.verify_vocabulary_consistency().is_ok());
+        for word in ["low", "lower", "lowest"] {
+            let tokens = model.tokenize(word).unwrap();
+            assert_eq!(tokens.len(), 1);
+            assert_eq!(tokens[0].value, word);
+        }
+        assert_eq!(model.tokenize("lowlow").unwrap().len(), 2);
+
+        // Nothing to merge, only the alphabet is left
+        let word_counts: HashMap<String, u64> = [("a".into(), 1), ("b".into(), 2)]
+            .iter()
+            .chain(&[("c".into(), 1)])
+            .cloned()
+            .collect();
+        let mut model = BPE::default();
+        trainer.do_train(&word_counts, &mut model).unwrap();
+        assert_eq!(model.get_vocab_size(), 3);
+        assert_eq!(model.num_merges(), 0);
+        let tokens: Vec<_> = model
+            .tokenize("cab")
+            .unwrap()
+            .into_iter()
+            .map(|t| t.value)
+            .collect();
+        assert_eq!(tokens, vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn bpe_train_preserve_tokens() {
+        let word_counts: HashMap<String, u64> = [
+            ("http://a.com".into(), 5),
+            ("http://b.org".into(), 5),
+            ("https://c.net".into(), 3),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let train = |preserve_tokens: HashSet<String>| {
+            let trainer = BpeTrainer::builder()
+                .show_progress(false)
+                .vocab_size(100)
+                .preserve_tokens(preserve_tokens)
+                .build();
+            let mut model = BPE::default();
+            let added_tokens = trainer.do_train(&word_counts, &mut model).unwrap();
+            (model, added_tokens)
+        };
+        let absorbing_http = |model: &BPE| {
+            model
+                .get_vocab()
+                .into_keys()
+                .filter(|token| token.contains("http") && token != "http")
+                .count()
+        };
+
+        // `http` gets merged with `://` by default
+        let (model, added_tokens) = train(HashSet::new());
+        assert!(model.get_vocab().contains_key("http://"));
+        assert!(added_tokens.is_empty());
+
+        let (model, added_tokens) = train(vec!["http".to_string()].into_iter().collect());
+        assert_eq!(absorbing_http(&model), 0);
+        assert!(model.get_vocab().contains_key("http"));
+        assert!(model.get_vocab().contains_key("://"));
+        assert_eq!(added_tokens, vec![AddedToken::from("http", false)]);
+    }
+
     #[test]
     fn bpe_test_max_token_length_16() {
         /* bpe_test_max_token_length series of tests test the max_token_length flag of bpetrainer
@@ -847,4 +1324,293 @@ mod tests {
         .collect();
         assert_eq!(trained_vocab, expected_vocab)
     }
+
+    fn train_with_corpus(weight: Option<u64>) -> BPE {
+        let main = ["xy xy xy zw"];
+        let extra = vec!["zw zq".to_string()];
+        let mut builder = BpeTrainer::builder().show_progress(false).vocab_size(6);
+        if let Some(weight) = weight {
+            builder = builder.add_corpus(extra.into_iter(), weight);
+        }
+        let mut trainer = builder.build();
+        trainer
+            .feed(main.iter(), |s| {
+                Ok(s.split_whitespace().map(|w| w.to_owned()).collect())
+            })
+            .unwrap();
+        let mut model = BPE::default();
+        trainer.train(&mut model).unwrap();
+        model
+    }
+
+    #[test]
+    fn bpe_corpus_with_zero_weight_has_no_effect() {
+        let without = train_with_corpus(None);
+        let with_zero = train_with_corpus(Some(0));
+        assert_eq!(without.vocab, with_zero.vocab);
+        assert_eq!(without.merges, with_zero.merges);
+        assert!(with_zero.vocab.contains_key("xy"));
+        assert!(!with_zero.vocab.contains_key("q"));
+    }
+
+    #[test]
+    fn bpe_corpus_with_high_weight_dominates() {
+        let model = train_with_corpus(Some(10));
+        // `zw` now appears 1 + 10 times and `zq` 10 times, both more than the 3 `xy`
+        let zw = model.vocab["zw"];
+        assert_eq!(model.merges[&(model.vocab["z"], model.vocab["w"])], (0, zw));
+        assert!(!model.vocab.contains_key("xy"));
+    }
+
+    #[test]
+    fn bpe_initial_alphabet_with_prefix() {
+        let mut trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .continuing_subword_prefix("##".to_string())
+            .add_initial_alphabet(['x'])
+            .build();
+        trainer
+            .feed(["ab ab"].iter(), |s| {
+                Ok(s.split_whitespace().map(|w| w.to_owned()).collect())
+            })
+            .unwrap();
+
+        // Only the chars themselves are added, unlike for WordPiece
+        let mut model = BPE::default();
+        trainer.train(&mut model).unwrap();
+        let mut vocab: Vec<_> = model.vocab.keys().map(|token| token.as_str()).collect();
+        vocab.sort_unstable();
+        assert_eq!(vocab, ["##b", "a", "ab", "b", "x"]);
+
+        let mut model = BPE::default();
+        trainer
+            .train_with_prefixed_initial_alphabet(&mut model)
+            .unwrap();
+        assert!(model.vocab.contains_key("##x"));
+    }
+
+    #[test]
+    fn bpe_corpus_weight_repeats_the_corpus() {
+        let corpus = || vec!["zw zq".to_string(), "zq".to_string()].into_iter();
+        let weighted = BpeTrainer::builder()
+            .show_progress(false)
+            .vocab_size(6)
+            .add_corpus(corpus(), 3)
+            .build();
+        let repeated = BpeTrainer::builder()
+            .show_progress(false)
+            .vocab_size(6)
+            .add_corpus(corpus().chain(corpus()).chain(corpus()), 1)
+            .build();
+
+        // Without feeding anything, the corpora are split on whitespace
+        let (mut weighted_model, mut repeated_model) = (BPE::default(), BPE::default());
+        weighted.train(&mut weighted_model).unwrap();
+        repeated.train(&mut repeated_model).unwrap();
+        assert_eq!(weighted_model.vocab, repeated_model.vocab);
+        assert_eq!(weighted_model.merges, repeated_model.merges);
+        let zq = weighted_model.vocab["zq"];
+        assert_eq!(
+            weighted_model.merges[&(weighted_model.vocab["z"], weighted_model.vocab["q"])],
+            (0, zq)
+        );
+    }
+
+    #[test]
+    fn bpe_corpus_serialization() {
+        let trainer = BpeTrainer::builder()
+            .add_corpus(vec!["zw zq".to_string()].into_iter(), 2)
+            .build();
+        let serialized = serde_json::to_string(&trainer).unwrap();
+        assert!(serialized.contains(r#""corpora":[[["zw zq"],2]]"#));
+        let deserialized: BpeTrainer = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, trainer);
+
+        // Without any corpus, like the trainers serialized before
+        let serialized = serialized.replace(r#","corpora":[[["zw zq"],2]]"#, "");
+        let deserialized: BpeTrainer = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, BpeTrainer::default());
+    }
+
+    #[test]
+    fn bpe_trainer_registers_decoder() {
+        use crate::decoders::bpe::BPEDecoder;
+        use crate::models::TrainerWrapper;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+        use crate::{NormalizerWrapper, PostProcessorWrapper, Tokenizer, TokenizerImpl};
+
+        let mut tokenizer = Tokenizer::new(BPE::default());
+        tokenizer.with_pre_tokenizer(Whitespace {});
+        let mut trainer: TrainerWrapper = BpeTrainer::builder()
+            .show_progress(false)
+            .continuing_subword_prefix("##".into())
+            .vocab_size(20)
+            .build()
+            .into();
+        tokenizer
+            .train(&mut trainer, ["hello world", "held worn"].iter())
+            .unwrap();
+        assert!(tokenizer.get_decoder().is_some());
+
+        let encoding = tokenizer.encode("hello world", false).unwrap();
+        assert!(encoding.get_tokens().iter().any(|t| t.starts_with("##")));
+        let decoded = tokenizer.decode(encoding.get_ids(), false).unwrap();
+        assert_eq!(decoded, "hello world");
+
+        // The decoder is also registered when the tokenizer uses `BPEDecoder` directly
+        let mut tokenizer: TokenizerImpl<
+            BPE,
+            NormalizerWrapper,
+            Whitespace,
+            PostProcessorWrapper,
+            BPEDecoder,
+        > = TokenizerImpl::new(BPE::default());
+        tokenizer.with_pre_tokenizer(Whitespace {});
+        let mut trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .continuing_subword_prefix("##".into())
+            .vocab_size(20)
+            .build();
+        tokenizer
+            .train(&mut trainer, ["hello world", "held worn"].iter())
+            .unwrap();
+        let encoding = tokenizer.encode("hello world", false).unwrap();
+        let decoded = tokenizer.decode(encoding.get_ids(), false).unwrap();
+        assert_eq!(decoded, "hello world");
+
+        // Without a prefix, no decoder is registered
+        let mut tokenizer = Tokenizer::new(BPE::default());
+        let mut trainer: TrainerWrapper = BpeTrainer::builder().show_progress(false).build().into();
+        tokenizer.train(&mut trainer, ["hello"].iter()).unwrap();
+        assert!(tokenizer.get_decoder().is_none());
+    }
+
+    #[test]
+    fn bpe_train_from_weighted_iterator() {
+        use crate::models::TrainerWrapper;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+        use crate::Tokenizer;
+
+        let train = |sequences: Vec<(&str, f64)>| {
+            let mut tokenizer = Tokenizer::new(BPE::default());
+            tokenizer.with_pre_tokenizer(Whitespace {});
+            let mut trainer: TrainerWrapper = BpeTrainer::builder()
+                .show_progress(false)
+                .vocab_size(14)
+                .build()
+                .into();
+            tokenizer
+                .train_from_weighted_iterator(&mut trainer, sequences.into_iter())
+                .map(|tokenizer| tokenizer.get_vocab(false))
+        };
+
+        let doubled = train(vec![("low lower", 2.0), ("newest wider", 1.0)]).unwrap();
+        let repeated = train(vec![
+            ("low lower", 1.0),
+            ("newest wider", 1.0),
+            ("low lower", 1.0),
+        ])
+        .unwrap();
+        assert_eq!(doubled, repeated);
+        assert_ne!(
+            doubled,
+            train(vec![("low lower", 1.0), ("newest wider", 2.0)]).unwrap()
+        );
+
+        // A zero weight ignores the sequence, a negative one is an error
+        let ignored = train(vec![("low lower", 1.0), ("newest wider", 0.0)]).unwrap();
+        assert!(!ignored.contains_key("n"));
+        assert!(train(vec![("low lower", -1.0)]).is_err());
+    }
+
+    #[test]
+    fn bpe_train_incremental() {
+        use crate::Model;
+
+        let trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .vocab_size(20)
+            .build();
+        let mut base = BPE::default();
+        let mut base_trainer = trainer.clone();
+        base_trainer
+            .feed(["low lower lowest"].iter(), |s| {
+                Ok(s.split(' ').map(|s| s.to_owned()).collect())
+            })
+            .unwrap();
+        base_trainer.train(&mut base).unwrap();
+
+        // Without new data, nothing changes
+        let same = trainer
+            .train_incremental(base.clone(), std::iter::empty(), 10)
+            .unwrap();
+        assert_eq!(same.get_vocab(), base.get_vocab());
+        assert_eq!(same.merges, base.merges);
+
+        let new_data = vec!["tower towers tow".to_string(); 3];
+        let extended = trainer
+            .train_incremental(base.clone(), new_data.into_iter(), 3)
+            .unwrap();
+        assert_eq!(extended.num_merges(), base.num_merges() + 3);
+        // The base tokens are pinned
+        for (token, id) in base.get_vocab() {
+            assert_eq!(extended.token_to_id(&token), Some(id));
+        }
+        // The new merges come after the existing ones
+        for (pair, (rank, _)) in &extended.merges {
+            match base.merges.get(pair) {
+                Some((base_rank, _)) => assert_eq!(base_rank, rank),
+                None => assert!(*rank as usize >= base.num_merges()),
+            }
+        }
+        let n_tokens = |bpe: &BPE| bpe.tokenize("towers").unwrap().len();
+        assert!(n_tokens(&extended) < n_tokens(&base));
+    }
+
+    #[test]
+    fn bpe_train_from_files_parallel() {
+        use std::io::Write;
+        use std::path::Path;
+
+        let trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .special_tokens(vec![AddedToken::from("[UNK]", true)])
+            .build();
+
+        let dir = tempfile::tempdir().unwrap();
+        let lines = [
+            "low lower lowest",
+            "new newer newest\n",
+            "wide wider widest",
+        ];
+        let paths: Vec<_> = (0..30)
+            .map(|i| {
+                let path = dir.path().join(format!("{}.txt", i));
+                let mut file = std::fs::File::create(&path).unwrap();
+                writeln!(file, "{}", lines[i % 3]).unwrap();
+                path
+            })
+            .collect();
+        let files: Vec<&Path> = paths.iter().map(|p| p.as_path()).collect();
+
+        let (model, added_tokens) = trainer.train_from_files_parallel(&files, 4).unwrap();
+        assert_eq!(added_tokens, vec![AddedToken::from("[UNK]", true)]);
+
+        // Same as counting all the words serially
+        let mut serial_trainer = trainer.clone();
+        serial_trainer
+            .feed(lines.iter().cycle().take(30), |s| {
+                Ok(s.split_whitespace().map(|s| s.to_owned()).collect())
+            })
+            .unwrap();
+        let mut expected = BPE::default();
+        serial_trainer.train(&mut expected).unwrap();
+        assert_eq!(model.get_vocab(), expected.get_vocab());
+        assert_eq!(model.merges, expected.merges);
+
+        let missing = dir.path().join("missing.txt");
+        assert!(trainer
+            .train_from_files_parallel(&[missing.as_path()], 1)
+            .is_err());
+    }
 }
@@ -1,4 +1,4 @@
-use super::Pair;
+use super::{MergeTrace, Pair};
 use rand::{thread_rng, Rng};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap};
@@ -158,7 +158,14 @@ impl Word {
         changes
     }
 
-    pub(super) fn merge_all(&mut self, merges: &HashMap<Pair, (u32, u32)>, dropout: Option<f32>) {
+    /// Apply all the possible merges, pushing each of them to the optional `trace` as the
+    /// positions of its left and right symbols in the initial word, with its rank
+    pub(super) fn merge_all(
+        &mut self,
+        merges: &HashMap<Pair, (u32, u32)>,
+        dropout: Option<f32>,
+        mut trace: Option<&mut MergeTrace>,
+    ) {
         let mut queue = BinaryHeap::with_capacity(self.symbols.len());
         let mut skip = Vec::with_capacity(queue.len());
 
@@ -207,6 +214,9 @@ impl Word {
                 }
 
                 // Otherwise, let's merge
+                if let Some(trace) = trace.as_mut() {
+                    trace.push((top.pos, next_pos, top.rank as usize));
+                }
                 self.symbols[top.pos].merge_with(&right, top.new_id);
                 // Tag the right part as removed
                 self.symbols[next_pos].len = 0;
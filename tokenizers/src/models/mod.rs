@@ -5,7 +5,8 @@ pub mod unigram;
 pub mod wordlevel;
 pub mod wordpiece;
 
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize, Serializer};
@@ -14,7 +15,7 @@ use crate::models::bpe::{BpeTrainer, BPE};
 use crate::models::unigram::{Unigram, UnigramTrainer};
 use crate::models::wordlevel::{WordLevel, WordLevelTrainer};
 use crate::models::wordpiece::{WordPiece, WordPieceTrainer};
-use crate::{AddedToken, Model, Result, Token, Trainer};
+use crate::{AddedToken, DecoderWrapper, Model, Result, Token, Trainer};
 
 /// Wraps a vocab mapping (ID -> token) to a struct that will be serialized in order
 /// of token ID, smallest to largest.
@@ -138,6 +139,175 @@ impl Model for ModelWrapper {
             Self::Unigram(t) => t.get_trainer().into(),
         }
     }
+
+    fn model_type(&self) -> &'static str {
+        match self {
+            Self::WordLevel(t) => t.model_type(),
+            Self::WordPiece(t) => t.model_type(),
+            Self::BPE(t) => t.model_type(),
+            Self::Unigram(t) => t.model_type(),
+        }
+    }
+
+    fn get_unk_id(&self) -> Option<u32> {
+        match self {
+            Self::WordLevel(t) => t.token_to_id(&t.unk_token),
+            Self::WordPiece(t) => t.token_to_id(&t.unk_token),
+            Self::BPE(t) => t.unk_token.as_ref().and_then(|unk| t.token_to_id(unk)),
+            Self::Unigram(t) => t.get_unk_id().map(|id| id as u32),
+        }
+    }
+}
+
+impl ModelWrapper {
+    /// Get the tokens of the vocabulary, from the most to the least frequent as far as the
+    /// model can tell: by merge rank for BPE, by score for Unigram, and by id otherwise.
+    pub(crate) fn tokens_by_rank(&self) -> Vec<String> {
+        let by_id = |vocab: HashMap<String, u32>| {
+            let mut tokens: Vec<_> = vocab.into_iter().collect();
+            tokens.sort_by_key(|(_, id)| *id);
+            tokens
+                .into_iter()
+                .map(|(token, _)| token)
+                .collect::<Vec<_>>()
+        };
+        match self {
+            Self::BPE(bpe) => {
+                let mut merged: Vec<_> = bpe.merges.values().collect();
+                merged.sort();
+                let mut tokens: Vec<String> = merged
+                    .into_iter()
+                    .filter_map(|(_, id)| bpe.id_to_token(*id))
+                    .collect();
+                let seen: HashSet<_> = tokens.iter().cloned().collect();
+                tokens.extend(
+                    by_id(bpe.get_vocab())
+                        .into_iter()
+                        .filter(|token| !seen.contains(token)),
+                );
+                tokens
+            }
+            Self::Unigram(unigram) => {
+                let mut pieces: Vec<_> = unigram.iter().collect();
+                pieces.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+                pieces.into_iter().map(|(token, _)| token.clone()).collect()
+            }
+            Self::WordPiece(t) => by_id(t.get_vocab()),
+            Self::WordLevel(t) => by_id(t.get_vocab()),
+        }
+    }
+
+    /// Build a copy of this model with only the tokens of `keep` (and the unknown token), the
+    /// new ids following the order of the old ones. For BPE, the tokens needed to build the
+    /// kept ones are kept too, along with the merges producing them.
+    pub(crate) fn retain_tokens(&self, keep: &HashSet<u32>) -> Result<Self> {
+        let mut keep = keep.clone();
+        keep.extend(self.get_unk_id());
+        if let Self::BPE(bpe) = self {
+            // The parts of a merge always come from merges of lower rank
+            let mut merges: Vec<_> = bpe.merges.iter().collect();
+            merges.sort_by_key(|(_, (rank, _))| std::cmp::Reverse(*rank));
+            for ((a, b), (_, new_id)) in merges {
+                if keep.contains(new_id) {
+                    keep.extend([*a, *b]);
+                }
+            }
+        }
+
+        let mut kept: Vec<u32> = keep
+            .into_iter()
+            .filter(|id| self.id_to_token(*id).is_some())
+            .collect();
+        kept.sort_unstable();
+        let tokens: Vec<String> = kept.iter().filter_map(|id| self.id_to_token(*id)).collect();
+        let vocab: HashMap<String, u32> = tokens
+            .iter()
+            .enumerate()
+            .map(|(id, token)| (token.clone(), id as u32))
+            .collect();
+
+        Ok(match self {
+            Self::BPE(bpe) => {
+                let mut merges: Vec<_> = bpe
+                    .merges
+                    .iter()
+                    .filter(|(_, (_, new_id))| vocab.contains_key(&bpe.vocab_r[new_id]))
+                    .collect();
+                merges.sort_by_key(|(_, (rank, _))| *rank);
+                let merges = merges
+                    .into_iter()
+                    .map(|((a, b), _)| (bpe.vocab_r[a].clone(), bpe.vocab_r[b].clone()))
+                    .collect();
+
+                let mut builder = BPE::builder()
+                    .vocab_and_merges(vocab, merges)
+                    .fuse_unk(bpe.fuse_unk)
+                    .byte_fallback(bpe.byte_fallback)
+                    .ignore_merges(bpe.ignore_merges);
+                if let Some(dropout) = bpe.dropout {
+                    builder = builder.dropout(dropout);
+                }
+                if let Some(unk_token) = &bpe.unk_token {
+                    builder = builder.unk_token(unk_token.clone());
+                }
+                if let Some(prefix) = &bpe.continuing_subword_prefix {
+                    builder = builder.continuing_subword_prefix(prefix.clone());
+                }
+                if let Some(suffix) = &bpe.end_of_word_suffix {
+                    builder = builder.end_of_word_suffix(suffix.clone());
+                }
+                builder.build()?.into()
+            }
+            Self::Unigram(unigram) => {
+                let unk_id = unigram
+                    .get_unk_id()
+                    .and_then(|unk_id| kept.iter().position(|id| *id as usize == unk_id));
+                let pieces = kept
+                    .iter()
+                    .map(|id| unigram.vocab[*id as usize].clone())
+                    .collect();
+                Unigram::from(pieces, unk_id, unigram.byte_fallback())?.into()
+            }
+            Self::WordPiece(wordpiece) => WordPiece::builder()
+                .vocab(vocab)
+                .unk_token(wordpiece.unk_token.clone())
+                .continuing_subword_prefix(wordpiece.continuing_subword_prefix.clone())
+                .max_input_chars_per_word(wordpiece.max_input_chars_per_word)
+                .build()?
+                .into(),
+            Self::WordLevel(wordlevel) => WordLevel::builder()
+                .vocab(vocab)
+                .unk_token(wordlevel.unk_token.clone())
+                .build()?
+                .into(),
+        })
+    }
+
+    /// The probability of the unknown token of a Unigram model, see
+    /// `Tokenizer::get_unk_probability`
+    pub(crate) fn unk_probability(&self) -> Option<f64> {
+        match self {
+            Self::Unigram(unigram) => unigram.get_unk_id().map(|_| unigram.get_unk_score().exp()),
+            _ => None,
+        }
+    }
+
+    /// The rank of the BPE merge producing `token_id`, see
+    /// `Tokenizer::get_token_frequency_ranking`
+    pub(crate) fn merge_rank(&self, token_id: u32) -> Option<usize> {
+        match self {
+            Self::BPE(bpe) => bpe.merge_rank(token_id),
+            _ => None,
+        }
+    }
+
+    /// The merges of a BPE model with their rank, by increasing rank
+    pub(crate) fn merges_by_rank(&self) -> Option<Vec<((String, String), u32)>> {
+        match self {
+            Self::BPE(bpe) => Some(bpe.merges_by_rank()),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -194,6 +364,29 @@ impl Trainer for TrainerWrapper {
             Self::UnigramTrainer(wpt) => wpt.feed(iterator, process),
         }
     }
+
+    fn feed_weighted<I, S, F>(&mut self, iterator: I, process: F) -> Result<()>
+    where
+        I: Iterator<Item = (S, f64)> + Send,
+        S: AsRef<str> + Send,
+        F: Fn(&str) -> Result<Vec<String>> + Sync,
+    {
+        match self {
+            Self::BpeTrainer(bpe) => bpe.feed_weighted(iterator, process),
+            Self::WordPieceTrainer(wpt) => wpt.feed_weighted(iterator, process),
+            Self::WordLevelTrainer(wpt) => wpt.feed_weighted(iterator, process),
+            Self::UnigramTrainer(wpt) => wpt.feed_weighted(iterator, process),
+        }
+    }
+
+    fn decoder(&self) -> Option<DecoderWrapper> {
+        match self {
+            Self::BpeTrainer(bpe) => bpe.decoder(),
+            Self::WordPieceTrainer(wpt) => wpt.decoder(),
+            Self::WordLevelTrainer(wpt) => wpt.decoder(),
+            Self::UnigramTrainer(wpt) => wpt.decoder(),
+        }
+    }
 }
 
 impl_enum_from!(BpeTrainer, TrainerWrapper, BpeTrainer);
@@ -204,6 +397,7 @@ impl_enum_from!(WordLevelTrainer, TrainerWrapper, WordLevelTrainer);
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Tokenizer;
 
     #[test]
     fn trainer_wrapper_train_model_wrapper() {
@@ -224,4 +418,49 @@ mod tests {
         let serialized = serde_json::to_string(&ordered).unwrap();
         assert_eq!(serialized, "{\"Hi\":0,\"There\":2}");
     }
+
+    #[test]
+    fn model_type_matches_serialization() {
+        let models: Vec<ModelWrapper> = vec![
+            BPE::default().into(),
+            WordPiece::default().into(),
+            WordLevel::default().into(),
+            Unigram::default().into(),
+        ];
+        for model in models {
+            let value = serde_json::to_value(&model).unwrap();
+            assert_eq!(value["type"], model.model_type());
+            assert_eq!(
+                Tokenizer::new(model.clone()).get_model_type(),
+                value["type"]
+            );
+        }
+    }
+
+    #[test]
+    fn unk_probability() {
+        let vocab = vec![
+            ("<unk>".to_string(), 0.0),
+            ("a".to_string(), -1.0),
+            ("b".to_string(), -2.0),
+        ];
+        let unigram: ModelWrapper = Unigram::from(vocab.clone(), Some(0), false).unwrap().into();
+        let probability = unigram.unk_probability().unwrap();
+        assert!((probability - (-12.0f64).exp()).abs() < 1e-12);
+
+        let unigram: ModelWrapper = Unigram::from(vocab, None, false).unwrap().into();
+        assert_eq!(unigram.unk_probability(), None);
+    }
+
+    #[test]
+    fn lookups_of_other_models() {
+        let model: ModelWrapper = WordLevel::default().into();
+        assert_eq!(model.unk_probability(), None);
+        assert_eq!(model.merge_rank(0), None);
+        assert_eq!(model.merges_by_rank(), None);
+
+        let model: ModelWrapper = BPE::default().into();
+        assert_eq!(model.unk_probability(), None);
+        assert_eq!(model.merges_by_rank(), Some(vec![]));
+    }
 }
@@ -153,12 +153,20 @@ impl Unigram {
     pub fn byte_fallback(&self) -> bool {
         self.byte_fallback
     }
+    pub fn get_unk_id(&self) -> Option<usize> {
+        self.unk_id
+    }
+    /// The log probability given to the unknown token when it replaces a character that no
+    /// piece covers, which is the lowest score of the vocabulary minus a fixed penalty.
+    pub fn get_unk_score(&self) -> f64 {
+        self.min_score - K_UNK_PENALTY
+    }
     pub(super) fn len(&self) -> usize {
         self.vocab.len()
     }
 
     pub(super) fn populate_nodes(&self, lattice: &mut Lattice) {
-        let unk_score = self.min_score - K_UNK_PENALTY;
+        let unk_score = self.get_unk_score();
 
         let len = lattice.len();
 
@@ -257,7 +265,7 @@ impl Unigram {
             }
         }
         let size = sentence.len();
-        let unk_score = self.min_score - K_UNK_PENALTY;
+        let unk_score = self.get_unk_score();
 
         let mut best_path_ends_at = vec![BestPathNode::default(); size + 1];
         let mut starts_at = 0;
@@ -471,6 +479,10 @@ impl Model for Unigram {
     fn get_trainer(&self) -> Self::Trainer {
         UnigramTrainer::default()
     }
+
+    fn model_type(&self) -> &'static str {
+        "Unigram"
+    }
 }
 
 #[cfg(test)]
@@ -56,7 +56,7 @@ impl<'de> Visitor<'de> for UnigramVisitor {
                 }
                 "byte_fallback" => byte_fallback = map.next_value()?,
                 "vocab" => vocab = Some(map.next_value()?),
-                "type" => match map.next_value()? {
+                "type" => match map.next_value::<String>()?.as_ref() {
                     "Unigram" => {}
                     u => {
                         return Err(serde::de::Error::invalid_value(
@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 // A token and a score
 type SentencePiece = (String, f64);
@@ -33,6 +35,57 @@ fn digamma(mut x: f64) -> f64 {
 pub enum UnigramTrainerError {
     #[error("The vocabulary is not large enough to contain all chars")]
     VocabularyTooSmall,
+    #[error("No training state to checkpoint, the trainer must be trained first")]
+    NoCheckpoint,
+    #[error("Unknown checkpoint version `{0}`")]
+    UnknownCheckpointVersion(String),
+}
+
+const CHECKPOINT_VERSION: &str = "1.0";
+
+/// The state of the EM training, as saved by `UnigramTrainer::save_checkpoint`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct UnigramCheckpoint {
+    version: String,
+    /// Number of EM iterations already done
+    iteration: u32,
+    /// Whether the pieces were pruned after the last EM iteration
+    #[serde(default)]
+    pruned: bool,
+    /// The current pieces. The scores that are not a number (like the one of the `<UNK>`
+    /// piece) can't be represented in JSON, so they are stored as `None`
+    pieces: Vec<(String, Option<f64>)>,
+}
+
+impl UnigramCheckpoint {
+    fn new(iteration: u32, pruned: bool, pieces: &[SentencePiece]) -> Self {
+        Self {
+            version: CHECKPOINT_VERSION.into(),
+            iteration,
+            pruned,
+            pieces: pieces
+                .iter()
+                .map(|(piece, score)| (piece.clone(), Some(*score).filter(|s| !s.is_nan())))
+                .collect(),
+        }
+    }
+
+    fn pieces(&self) -> Vec<SentencePiece> {
+        self.pieces
+            .iter()
+            .map(|(piece, score)| (piece.clone(), score.unwrap_or(f64::NAN)))
+            .collect()
+    }
+}
+
+/// The last state reached by the training. Each clone of the trainer gets its own copy.
+#[derive(Debug, Default)]
+struct CheckpointState(Mutex<Option<UnigramCheckpoint>>);
+
+impl Clone for CheckpointState {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.0.lock().unwrap().clone()))
+    }
 }
 
 fn to_log_prob(pieces: &mut [SentencePiece]) {
@@ -65,10 +118,23 @@ pub struct UnigramTrainer {
 
     #[builder(default = "16")]
     pub max_piece_length: usize,
+    /// Stop the training after this number of EM iterations in total, even if the
+    /// vocabulary is still too large. Mostly useful to checkpoint long trainings.
+    #[builder(default = "None")]
+    pub max_iterations: Option<u32>,
+    /// Write the checkpoint to this file after each EM iteration and each pruning, so that a
+    /// training can be resumed with `train_from_checkpoint` even when it gets interrupted.
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub checkpoint_path: Option<PathBuf>,
     #[builder(default = "1_000_000")]
     seed_size: usize,
     #[builder(default = "HashMap::new()")]
     words: HashMap<String, u32>,
+    /// The state reached by the current or last training
+    #[builder(setter(skip))]
+    #[serde(skip)]
+    checkpoint: CheckpointState,
 }
 
 impl Default for UnigramTrainer {
@@ -526,23 +592,96 @@ impl UnigramTrainer {
             .collect();
         new_pieces
     }
+    /// Save the state reached by the current or last training, updated after each EM
+    /// iteration and each pruning, to resume it later with `train_from_checkpoint`. The
+    /// checkpoint is a versioned JSON file.
+    pub fn save_checkpoint(&self, path: &Path) -> Result<()> {
+        let checkpoint = self.checkpoint.0.lock().unwrap();
+        let checkpoint = checkpoint
+            .as_ref()
+            .ok_or(UnigramTrainerError::NoCheckpoint)?;
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer(file, checkpoint)?;
+        Ok(())
+    }
+
+    /// Keep the current state of the training, and write it to the `checkpoint_path` if any
+    fn update_checkpoint(
+        &self,
+        iteration: u32,
+        pruned: bool,
+        pieces: &[SentencePiece],
+    ) -> Result<()> {
+        *self.checkpoint.0.lock().unwrap() =
+            Some(UnigramCheckpoint::new(iteration, pruned, pieces));
+        if let Some(path) = &self.checkpoint_path {
+            self.save_checkpoint(path)?;
+        }
+        Ok(())
+    }
+
+    /// Resume the training saved in the given checkpoint, on the given sentences. They
+    /// should be the same as the ones used before the checkpoint, each item being a
+    /// sentence as it comes out of the pre-tokenizer.
+    pub fn train_from_checkpoint<I>(
+        &mut self,
+        path: &Path,
+        iterator: I,
+    ) -> Result<(Unigram, Vec<AddedToken>)>
+    where
+        I: Iterator<Item = String> + Send,
+    {
+        let content = std::fs::read_to_string(path)?;
+        let checkpoint: UnigramCheckpoint = serde_json::from_str(&content)?;
+        if checkpoint.version != CHECKPOINT_VERSION {
+            return Err(UnigramTrainerError::UnknownCheckpointVersion(checkpoint.version).into());
+        }
+
+        self.feed(iterator, |s| Ok(vec![s.to_owned()]))?;
+        let sentences: Vec<_> = self.words.iter().map(|(s, i)| (s.to_owned(), *i)).collect();
+        let mut model = Unigram::default();
+        let special_tokens = self.do_train_from(
+            sentences,
+            Some((checkpoint.pieces(), checkpoint.iteration, checkpoint.pruned)),
+            &mut model,
+        )?;
+        Ok((model, special_tokens))
+    }
+
     pub fn do_train(
         &self,
         sentences: Vec<Sentence>,
         model: &mut Unigram,
     ) -> Result<Vec<AddedToken>> {
-        let progress = self.setup_progress();
-        //
-        // 1. Compute frequent substrings
-        // TODO Should be able to upgrade to u64 when needed
-        self.update_progress(&progress, sentences.len(), "Suffix array seeds");
-        let mut pieces: Vec<SentencePiece> =
-            Vec::with_capacity(self.vocab_size.try_into().unwrap());
+        self.do_train_from(sentences, None, model)
+    }
 
-        // We use a UNK token when training, whatever the `self.unk_token`
-        pieces.push(("<UNK>".into(), f64::NAN));
-        pieces.extend(self.make_seed_sentence_pieces(&sentences, &progress));
-        self.finalize_progress(&progress, sentences.len());
+    /// Train, starting either from scratch or from the given pieces, reached after the
+    /// given number of EM iterations, and pruned after the last one or not
+    fn do_train_from(
+        &self,
+        sentences: Vec<Sentence>,
+        start: Option<(Vec<SentencePiece>, u32, bool)>,
+        model: &mut Unigram,
+    ) -> Result<Vec<AddedToken>> {
+        let progress = self.setup_progress();
+        let (mut pieces, mut iteration, mut pruned) = match start {
+            Some(start) => start,
+            None => {
+                //
+                // 1. Compute frequent substrings
+                // TODO Should be able to upgrade to u64 when needed
+                self.update_progress(&progress, sentences.len(), "Suffix array seeds");
+                let mut pieces: Vec<SentencePiece> =
+                    Vec::with_capacity(self.vocab_size.try_into().unwrap());
+
+                // We use a UNK token when training, whatever the `self.unk_token`
+                pieces.push(("<UNK>".into(), f64::NAN));
+                pieces.extend(self.make_seed_sentence_pieces(&sentences, &progress));
+                self.finalize_progress(&progress, sentences.len());
+                (pieces, 0, false)
+            }
+        };
 
         // Useful to check compatibility with spm.
         debug!(
@@ -567,16 +706,32 @@ impl UnigramTrainer {
         if required_chars.len() as u32 > self.vocab_size {
             return Err(Box::new(UnigramTrainerError::VocabularyTooSmall));
         }
+        let n_sub_iterations = self.n_sub_iterations;
+        let reached_max = |iteration: u32| self.max_iterations.is_some_and(|max| iteration >= max);
+        // When resuming right at the end of a sub-EM loop, only the pruning is left to do
+        let mut sub_em_done =
+            n_sub_iterations > 0 && iteration > 0 && iteration % n_sub_iterations == 0 && !pruned;
         let mut new_model = Unigram::from(pieces.clone(), Some(0), false)?;
-        loop {
+        'em: loop {
             // Sub-EM iteration.
-            for _iter in 0..self.n_sub_iterations {
+            let first_iter = iteration % n_sub_iterations.max(1);
+            for _iter in first_iter..n_sub_iterations {
+                if sub_em_done {
+                    break;
+                }
+                if reached_max(iteration) {
+                    break 'em;
+                }
+                iteration += 1;
+
                 // Executes E step
                 let (_objective, _num_tokens, expected) = self.run_e_step(&new_model, &sentences);
 
                 // Executes M step.
                 pieces = self.run_m_step(&pieces, &expected);
                 new_model = Unigram::from(pieces.clone(), Some(0), false)?;
+                pruned = false;
+                self.update_checkpoint(iteration, pruned, &pieces)?;
 
                 // Useful comment for checking compatibility with spm
                 debug!(
@@ -591,18 +746,22 @@ impl UnigramTrainer {
                     p.inc(1);
                 }
             } // end of Sub EM iteration
+            sub_em_done = false;
 
             // Stops the iteration when the size of sentences reaches to the
             // desired symbol size.
-            if pieces.len() <= desired_vocab_size {
+            if pieces.len() <= desired_vocab_size || reached_max(iteration) {
                 break;
             }
 
             // Prunes pieces.
             pieces = self.prune_sentence_pieces(&new_model, &pieces, &sentences);
             new_model = Unigram::from(pieces.clone(), Some(0), false)?;
+            pruned = true;
+            self.update_checkpoint(iteration, pruned, &pieces)?;
         }
         self.finalize_progress(&progress, expected_updates);
+        self.update_checkpoint(iteration, pruned, &pieces)?;
 
         // Finally, adjusts the size of sentencepices to be |vocab_size|.
         *model = self.finalize(new_model, required_chars)?;
@@ -822,4 +981,172 @@ mod tests {
         // ln(2) - ln(3)
         assert_approx_eq!(scores[1], -0.405, 0.01);
     }
+
+    #[test]
+    fn test_checkpoint() {
+        let syllables = ["ka", "ri", "mo", "te", "su", "na", "lo", "pe"];
+        let sentences: Vec<String> = syllables
+            .iter()
+            .flat_map(|a| syllables.iter().map(move |b| format!("{}{}", a, b)))
+            .flat_map(|ab| syllables.iter().map(move |c| format!("{}{}", ab, c)))
+            .collect();
+        let trainer = |max_iterations| {
+            UnigramTrainerBuilder::default()
+                .show_progress(false)
+                .vocab_size(16)
+                .n_sub_iterations(5)
+                .max_iterations(Some(max_iterations))
+                .build()
+                .unwrap()
+        };
+        let train = |trainer: &mut UnigramTrainer| {
+            trainer
+                .feed(sentences.iter(), |s| Ok(vec![s.to_owned()]))
+                .unwrap();
+            let mut model = Unigram::default();
+            trainer.train(&mut model).unwrap();
+            model
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        assert!(trainer(5).save_checkpoint(&path).is_err());
+
+        let mut full = trainer(10);
+        let expected = train(&mut full);
+        let full_checkpoint = full.checkpoint.0.lock().unwrap().clone().unwrap();
+        assert_eq!(full_checkpoint.iteration, 10);
+
+        let mut first = trainer(5);
+        train(&mut first);
+        first.save_checkpoint(&path).unwrap();
+        let saved: UnigramCheckpoint =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.version, "1.0");
+        assert_eq!(saved.iteration, 5);
+
+        let mut resumed = trainer(10);
+        let (model, _) = resumed
+            .train_from_checkpoint(&path, sentences.clone().into_iter())
+            .unwrap();
+        let resumed_checkpoint = resumed.checkpoint.0.lock().unwrap().clone().unwrap();
+        assert_eq!(resumed_checkpoint.iteration, 10);
+
+        // The EM state is the same as when training in one go...
+        let pieces = |checkpoint: &UnigramCheckpoint| {
+            checkpoint.pieces.iter().cloned().collect::<HashMap<_, _>>()
+        };
+        let (full_pieces, resumed_pieces) = (pieces(&full_checkpoint), pieces(&resumed_checkpoint));
+        assert_eq!(full_pieces.len(), resumed_pieces.len());
+        for (piece, score) in full_pieces {
+            match (score, resumed_pieces[&piece]) {
+                (Some(score), Some(resumed)) => assert_approx_eq!(resumed, score, 1e-9),
+                (score, resumed) => assert_eq!(score, resumed),
+            }
+        }
+
+        // ...and so is the final model, except for the small penalties given to the missing
+        // chars, which depend on the iteration order of a `HashSet`
+        let scores = |model: &Unigram| model.iter().cloned().collect::<HashMap<_, _>>();
+        let (expected, model) = (scores(&expected), scores(&model));
+        assert_eq!(
+            expected.keys().collect::<HashSet<_>>(),
+            model.keys().collect::<HashSet<_>>()
+        );
+        for (piece, score) in expected {
+            assert_approx_eq!(model[&piece], score, 0.01);
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_during_training() {
+        let syllables = ["ka", "ri", "mo", "te", "su", "na", "lo", "pe"];
+        let sentences: Vec<String> = syllables
+            .iter()
+            .flat_map(|a| syllables.iter().map(move |b| format!("{}{}", a, b)))
+            .flat_map(|ab| syllables.iter().map(move |c| format!("{}{}", ab, c)))
+            .collect();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        let trainer = |max_iterations, checkpoint_path: Option<PathBuf>| {
+            UnigramTrainerBuilder::default()
+                .show_progress(false)
+                .vocab_size(16)
+                .n_sub_iterations(5)
+                .max_iterations(Some(max_iterations))
+                .checkpoint_path(checkpoint_path)
+                .build()
+                .unwrap()
+        };
+        let train = |trainer: &mut UnigramTrainer| {
+            trainer
+                .feed(sentences.iter(), |s| Ok(vec![s.to_owned()]))
+                .unwrap();
+            let mut model = Unigram::default();
+            trainer.train(&mut model).unwrap();
+            model
+        };
+
+        // A training interrupted in the middle of its second sub-EM loop has written its last
+        // state
+        let mut interrupted = trainer(7, Some(path.clone()));
+        train(&mut interrupted);
+        let saved: UnigramCheckpoint =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.iteration, 7);
+        assert!(!saved.pruned);
+
+        // Each clone has its own state
+        let mut clone = interrupted.clone();
+        clone.max_iterations = Some(3);
+        clone.checkpoint_path = None;
+        train(&mut clone);
+        assert_eq!(
+            clone
+                .checkpoint
+                .0
+                .lock()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .iteration,
+            3
+        );
+        assert_eq!(
+            interrupted
+                .checkpoint
+                .0
+                .lock()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .iteration,
+            7
+        );
+
+        let expected = train(&mut trainer(10, None));
+        let (model, _) = trainer(10, None)
+            .train_from_checkpoint(&path, sentences.clone().into_iter())
+            .unwrap();
+        let scores = |model: &Unigram| model.iter().cloned().collect::<HashMap<_, _>>();
+        let (expected, model) = (scores(&expected), scores(&model));
+        assert_eq!(
+            expected.keys().collect::<HashSet<_>>(),
+            model.keys().collect::<HashSet<_>>()
+        );
+        for (piece, score) in expected {
+            assert_approx_eq!(model[&piece], score, 0.01);
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_unknown_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        std::fs::write(&path, r#"{"version":"0.1","iteration":1,"pieces":[]}"#).unwrap();
+        let mut trainer = UnigramTrainer::default();
+        assert!(trainer
+            .train_from_checkpoint(&path, vec!["a".to_string()].into_iter())
+            .is_err());
+    }
 }
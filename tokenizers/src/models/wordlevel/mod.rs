@@ -213,6 +213,10 @@ impl Model for WordLevel {
     fn get_trainer(&self) -> Self::Trainer {
         WordLevelTrainer::default()
     }
+
+    fn model_type(&self) -> &'static str {
+        "WordLevel"
+    }
 }
 
 #[cfg(test)]
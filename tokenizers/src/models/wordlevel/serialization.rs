@@ -57,7 +57,7 @@ impl<'de> Visitor<'de> for WordLevelVisitor {
             match key.as_ref() {
                 "vocab" => builder = builder.vocab(map.next_value()?),
                 "unk_token" => builder = builder.unk_token(map.next_value()?),
-                "type" => match map.next_value()? {
+                "type" => match map.next_value::<String>()?.as_ref() {
                     "WordLevel" => {}
                     u => {
                         return Err(serde::de::Error::invalid_value(
@@ -295,6 +295,10 @@ impl Model for WordPiece {
     fn get_trainer(&self) -> Self::Trainer {
         WordPieceTrainer::builder().build()
     }
+
+    fn model_type(&self) -> &'static str {
+        "WordPiece"
+    }
 }
 
 #[cfg(test)]
@@ -66,6 +66,15 @@ impl WordPieceTrainerBuilder {
         self
     }
 
+    /// Add the given chars to the initial alphabet. These chars are always part of the
+    /// vocabulary, both alone and with the `continuing_subword_prefix`, even when they don't
+    /// appear in the training data.
+    #[must_use]
+    pub fn add_initial_alphabet<I: IntoIterator<Item = char>>(mut self, chars: I) -> Self {
+        self.bpe_trainer_builder = self.bpe_trainer_builder.add_initial_alphabet(chars);
+        self
+    }
+
     /// Set the continuing_subword_prefix
     #[must_use]
     pub fn continuing_subword_prefix(mut self, prefix: String) -> Self {
@@ -142,6 +151,11 @@ impl WordPieceTrainer {
         self.bpe_trainer.initial_alphabet = alphabet;
     }
 
+    /// Add the given chars to the initial alphabet, like `WordPieceTrainerBuilder::add_initial_alphabet`
+    pub fn add_initial_alphabet(&mut self, chars: &[char]) {
+        self.bpe_trainer.initial_alphabet.extend(chars);
+    }
+
     pub fn continuing_subword_prefix(&self) -> &Option<String> {
         &self.bpe_trainer.continuing_subword_prefix
     }
@@ -164,7 +178,9 @@ impl WordPieceTrainer {
 
     pub fn train(&self, model: &mut WordPiece) -> Result<Vec<AddedToken>> {
         let mut bpe = BPE::default();
-        let special_tokens = self.bpe_trainer.train(&mut bpe)?;
+        let special_tokens = self
+            .bpe_trainer
+            .train_with_prefixed_initial_alphabet(&mut bpe)?;
         let new_wordpiece = WordPiece::from_bpe(&bpe);
 
         // Transfer the vocab
@@ -196,4 +212,67 @@ impl Trainer for WordPieceTrainer {
     {
         self.bpe_trainer.feed(iterator, process)
     }
+
+    fn feed_weighted<I, S, F>(&mut self, iterator: I, process: F) -> Result<()>
+    where
+        I: Iterator<Item = (S, f64)> + Send,
+        S: AsRef<str> + Send,
+        F: Fn(&str) -> Result<Vec<String>> + Sync,
+    {
+        self.bpe_trainer.feed_weighted(iterator, process)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Model;
+
+    fn train(trainer: &mut WordPieceTrainer) -> WordPiece {
+        trainer
+            .feed(["hello world", "hello there"].iter(), |s| {
+                Ok(s.split(' ').map(|s| s.to_owned()).collect())
+            })
+            .unwrap();
+        let mut model = WordPiece::default();
+        trainer.train(&mut model).unwrap();
+        model
+    }
+
+    #[test]
+    fn initial_alphabet_injection() {
+        let digits: Vec<char> = ('0'..='9').collect();
+        let mut trainer = WordPieceTrainer::builder()
+            .show_progress(false)
+            .min_frequency(100)
+            .special_tokens(vec![AddedToken::from("[UNK]", true)])
+            .add_initial_alphabet(digits.clone())
+            .build();
+        trainer.add_initial_alphabet(&['$', '%']);
+        let model = train(&mut trainer);
+
+        // Never seen during training, and still kept with a high `min_frequency`
+        for c in digits.iter().chain(&['$', '%']) {
+            assert!(model.token_to_id(&c.to_string()).is_some());
+            assert!(model.token_to_id(&format!("##{}", c)).is_some());
+        }
+        let tokens: Vec<_> = model
+            .tokenize("h3$")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.value)
+            .collect();
+        assert_eq!(tokens, ["h", "##3", "##$"]);
+
+        // Without them, the whole word is unknown
+        let mut trainer = WordPieceTrainer::builder()
+            .show_progress(false)
+            .special_tokens(vec![AddedToken::from("[UNK]", true)])
+            .build();
+        let model = train(&mut trainer);
+        assert!(model.token_to_id("3").is_none());
+        let tokens = model.tokenize("h3$").unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].value, "[UNK]");
+    }
 }
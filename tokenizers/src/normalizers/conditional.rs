@@ -0,0 +1,154 @@
+use crate::normalizers::NormalizerWrapper;
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The conditions a `ConditionalNormalizer` can check on its input. They are named, rather
+/// than given as any closure, so that they can be serialized.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Condition {
+    /// The input has some uppercase characters, and no lowercase one
+    IsAllCaps,
+    /// The input has strictly more than this number of characters
+    LongerThan(usize),
+    /// The regex matches somewhere in the input
+    MatchesRegex(String),
+}
+
+/// We use this custom deserializer to compile the `regex` of `Condition::MatchesRegex`
+#[doc(hidden)]
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+struct ConditionalNormalizerDeserializer {
+    condition: Condition,
+    normalizer: Box<NormalizerWrapper>,
+}
+
+impl std::convert::TryFrom<ConditionalNormalizerDeserializer> for ConditionalNormalizer {
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn try_from(v: ConditionalNormalizerDeserializer) -> Result<Self> {
+        Self::new(v.condition, *v.normalizer)
+    }
+}
+
+/// This normalizer applies the wrapped `normalizer` only when its input matches the
+/// `condition`, and leaves the input unchanged otherwise. The input is the whole
+/// `NormalizedString` given to the normalizer, so each part of the text between added tokens
+/// gets checked on its own.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", try_from = "ConditionalNormalizerDeserializer")]
+pub struct ConditionalNormalizer {
+    condition: Condition,
+    normalizer: Box<NormalizerWrapper>,
+    #[serde(skip)]
+    regex: Option<Regex>,
+}
+
+impl ConditionalNormalizer {
+    /// Fails when the pattern of a `Condition::MatchesRegex` is not a valid regex
+    pub fn new<N: Into<NormalizerWrapper>>(condition: Condition, normalizer: N) -> Result<Self> {
+        let regex = match &condition {
+            Condition::MatchesRegex(pattern) => Some(Regex::new(pattern)?),
+            _ => None,
+        };
+        Ok(Self {
+            condition,
+            normalizer: Box::new(normalizer.into()),
+            regex,
+        })
+    }
+
+    pub fn get_condition(&self) -> &Condition {
+        &self.condition
+    }
+
+    pub fn get_normalizer(&self) -> &NormalizerWrapper {
+        &self.normalizer
+    }
+
+    /// Whether the wrapped normalizer applies to `input`
+    pub fn matches(&self, input: &str) -> bool {
+        match &self.condition {
+            Condition::IsAllCaps => {
+                input.chars().any(char::is_uppercase) && !input.chars().any(char::is_lowercase)
+            }
+            Condition::LongerThan(n) => input.chars().count() > *n,
+            Condition::MatchesRegex(_) => self
+                .regex
+                .as_ref()
+                .is_some_and(|regex| regex.is_match(input)),
+        }
+    }
+}
+
+impl Normalizer for ConditionalNormalizer {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        if self.matches(normalized.get()) {
+            self.normalizer.normalize(normalized)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalizers::Lowercase;
+
+    fn normalize(normalizer: &ConditionalNormalizer, input: &str) -> String {
+        let mut n = NormalizedString::from(input);
+        normalizer.normalize(&mut n).unwrap();
+        n.get().to_owned()
+    }
+
+    #[test]
+    fn is_all_caps_lowercase() {
+        let normalizer = ConditionalNormalizer::new(Condition::IsAllCaps, Lowercase).unwrap();
+        assert_eq!(normalize(&normalizer, "HELLO WORLD!"), "hello world!");
+        assert_eq!(normalize(&normalizer, "ÉCOLE 42"), "école 42");
+        assert_eq!(normalize(&normalizer, "Hello WORLD"), "Hello WORLD");
+        assert_eq!(normalize(&normalizer, "hello"), "hello");
+        // Without any cased character, there are no caps
+        assert_eq!(normalize(&normalizer, "42 !"), "42 !");
+        assert_eq!(normalize(&normalizer, ""), "");
+    }
+
+    #[test]
+    fn other_conditions() {
+        let normalizer = ConditionalNormalizer::new(Condition::LongerThan(3), Lowercase).unwrap();
+        assert_eq!(normalize(&normalizer, "ABC"), "ABC");
+        assert_eq!(normalize(&normalizer, "ABCD"), "abcd");
+
+        let normalizer =
+            ConditionalNormalizer::new(Condition::MatchesRegex(r"^\d".into()), Lowercase).unwrap();
+        assert_eq!(normalize(&normalizer, "1 ABC"), "1 abc");
+        assert_eq!(normalize(&normalizer, "ABC 1"), "ABC 1");
+
+        assert!(
+            ConditionalNormalizer::new(Condition::MatchesRegex("(".into()), Lowercase).is_err()
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let normalizer = ConditionalNormalizer::new(Condition::IsAllCaps, Lowercase).unwrap();
+        let normalizer_s = r#"{"type":"ConditionalNormalizer","condition":"IsAllCaps","normalizer":{"type":"Lowercase"}}"#;
+        assert_eq!(serde_json::to_string(&normalizer).unwrap(), normalizer_s);
+        let wrapper: NormalizerWrapper = serde_json::from_str(normalizer_s).unwrap();
+        assert!(matches!(
+            wrapper,
+            NormalizerWrapper::ConditionalNormalizer(ref n) if n.get_condition() == &Condition::IsAllCaps
+        ));
+
+        let normalizer_s = r#"{"type":"ConditionalNormalizer","condition":{"MatchesRegex":"^\\d"},"normalizer":{"type":"Lowercase"}}"#;
+        let normalizer: ConditionalNormalizer = serde_json::from_str(normalizer_s).unwrap();
+        assert_eq!(normalize(&normalizer, "1 ABC"), "1 abc");
+        assert_eq!(serde_json::to_string(&normalizer).unwrap(), normalizer_s);
+
+        assert!(serde_json::from_str::<ConditionalNormalizer>(
+            r#"{"type":"ConditionalNormalizer","condition":{"MatchesRegex":"("},"normalizer":{"type":"Lowercase"}}"#
+        )
+        .is_err());
+    }
+}
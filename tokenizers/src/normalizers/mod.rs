@@ -1,18 +1,26 @@
 pub mod bert;
 pub mod byte_level;
+pub mod conditional;
 pub mod precompiled;
 pub mod prepend;
+pub mod regex_replace;
 pub mod replace;
+pub mod sentencepiece;
 pub mod strip;
 pub mod unicode;
 pub mod utils;
 pub use crate::normalizers::bert::BertNormalizer;
 pub use crate::normalizers::byte_level::ByteLevel;
+pub use crate::normalizers::conditional::{Condition, ConditionalNormalizer};
 pub use crate::normalizers::precompiled::Precompiled;
 pub use crate::normalizers::prepend::Prepend;
+pub use crate::normalizers::regex_replace::RegexReplace;
 pub use crate::normalizers::replace::Replace;
+pub use crate::normalizers::sentencepiece::SentencePieceNormalizer;
 pub use crate::normalizers::strip::{Strip, StripAccents};
-pub use crate::normalizers::unicode::{Nmt, NFC, NFD, NFKC, NFKD};
+pub use crate::normalizers::unicode::{
+    Nmt, UnicodeNormForm, UnicodeNormalizer, NFC, NFD, NFKC, NFKD,
+};
 pub use crate::normalizers::utils::{Lowercase, Sequence};
 use serde::{Deserialize, Serialize};
 
@@ -35,6 +43,11 @@ pub enum NormalizerWrapper {
     Precompiled(Precompiled),
     Replace(Replace),
     Prepend(Prepend),
+    // Before `ByteLevel`, which would accept any other tagged struct
+    ConditionalNormalizer(ConditionalNormalizer),
+    RegexReplace(RegexReplace),
+    SentencePieceNormalizer(SentencePieceNormalizer),
+    UnicodeNormalizer(UnicodeNormalizer),
     ByteLevel(ByteLevel),
 }
 
@@ -55,6 +68,10 @@ impl Normalizer for NormalizerWrapper {
             Self::Replace(lc) => lc.normalize(normalized),
             Self::Prepend(lc) => lc.normalize(normalized),
             Self::ByteLevel(lc) => lc.normalize(normalized),
+            Self::RegexReplace(lc) => lc.normalize(normalized),
+            Self::SentencePieceNormalizer(lc) => lc.normalize(normalized),
+            Self::UnicodeNormalizer(lc) => lc.normalize(normalized),
+            Self::ConditionalNormalizer(lc) => lc.normalize(normalized),
         }
     }
 }
@@ -73,3 +90,15 @@ impl_enum_from!(Precompiled, NormalizerWrapper, Precompiled);
 impl_enum_from!(Replace, NormalizerWrapper, Replace);
 impl_enum_from!(Prepend, NormalizerWrapper, Prepend);
 impl_enum_from!(ByteLevel, NormalizerWrapper, ByteLevel);
+impl_enum_from!(RegexReplace, NormalizerWrapper, RegexReplace);
+impl_enum_from!(
+    SentencePieceNormalizer,
+    NormalizerWrapper,
+    SentencePieceNormalizer
+);
+impl_enum_from!(UnicodeNormalizer, NormalizerWrapper, UnicodeNormalizer);
+impl_enum_from!(
+    ConditionalNormalizer,
+    NormalizerWrapper,
+    ConditionalNormalizer
+);
@@ -0,0 +1,185 @@
+use crate::normalizer::Range;
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// We use this custom deserializer to compile the `regex` for `RegexReplace`
+#[doc(hidden)]
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+struct RegexReplaceDeserializer {
+    pattern: String,
+    replacement: String,
+}
+
+impl std::convert::TryFrom<RegexReplaceDeserializer> for RegexReplace {
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn try_from(v: RegexReplaceDeserializer) -> Result<Self> {
+        Self::new(v.pattern, v.replacement)
+    }
+}
+
+/// This normalizer replaces every match of the regex `pattern` with `replacement`, in which
+/// capture groups can be referenced as `$1` or `${name}` (see `regex::Captures::expand`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", try_from = "RegexReplaceDeserializer")]
+pub struct RegexReplace {
+    pattern: String,
+    replacement: String,
+    #[serde(skip)]
+    regex: Regex,
+}
+
+impl PartialEq for RegexReplace {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && self.replacement == other.replacement
+    }
+}
+
+impl RegexReplace {
+    pub fn new<P: Into<String>, R: Into<String>>(pattern: P, replacement: R) -> Result<Self> {
+        let pattern = pattern.into();
+        let regex = Regex::new(&pattern)?;
+        Ok(Self {
+            pattern,
+            replacement: replacement.into(),
+            regex,
+        })
+    }
+}
+
+impl Normalizer for RegexReplace {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        let replacements: Vec<_> = self
+            .regex
+            .captures_iter(normalized.get())
+            .map(|captures| {
+                let range = captures.get(0).unwrap().range();
+                let mut content = String::new();
+                captures.expand(&self.replacement, &mut content);
+                (range, content)
+            })
+            .collect();
+
+        // Going backward keeps the ranges of the remaining matches valid
+        for (range, content) in replacements.into_iter().rev() {
+            let removed = normalized.get()[range.clone()].chars().count();
+            let added = content.chars().count();
+            if added == 0 {
+                normalized.transform_range(Range::Normalized(range), vec![], removed);
+                continue;
+            }
+
+            // Each new char is aligned with the char it replaces, the extra ones being
+            // inserted or removed at the end
+            let kept = removed.min(added);
+            let dest = content.chars().enumerate().map(|(i, c)| {
+                let change = match (i + 1).cmp(&kept) {
+                    std::cmp::Ordering::Less => 0,
+                    std::cmp::Ordering::Equal => -((removed - kept) as isize),
+                    std::cmp::Ordering::Greater => 1,
+                };
+                (c, change)
+            });
+            normalized.transform_range(Range::Normalized(range), dest, 0);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_references() {
+        let mut n = NormalizedString::from("John's cat, Mary's dog");
+        RegexReplace::new(r"(\w+)'s", "$1 is")
+            .unwrap()
+            .normalize(&mut n)
+            .unwrap();
+        assert_eq!(n.get(), "John is cat, Mary is dog");
+
+        let mut n = NormalizedString::from("2024-06-01");
+        RegexReplace::new(r"(?P<y>\d+)-(?P<m>\d+)-(?P<d>\d+)", "${d}/${m}/${y}")
+            .unwrap()
+            .normalize(&mut n)
+            .unwrap();
+        assert_eq!(n.get(), "01/06/2024");
+    }
+
+    #[test]
+    fn alignments() {
+        // Longer replacement
+        let mut n = NormalizedString::from("a's b");
+        RegexReplace::new(r"(\w)'s", "$1 is")
+            .unwrap()
+            .normalize(&mut n)
+            .unwrap();
+        assert_eq!(n.get(), "a is b");
+        assert_eq!(n.len_original(), 5);
+        assert_eq!(n.get_range_original(Range::Normalized(0..4)), Some("a's"));
+        assert_eq!(n.get_range_original(Range::Normalized(5..6)), Some("b"));
+        assert_eq!(n.get_range(Range::Original(4..5)), Some("b"));
+
+        // Shorter replacement
+        let mut n = NormalizedString::from("x ... y");
+        RegexReplace::new(r"\.+", "…")
+            .unwrap()
+            .normalize(&mut n)
+            .unwrap();
+        assert_eq!(n.get(), "x … y");
+        // The new char is aligned with the first char of the match
+        assert_eq!(n.get_range_original(Range::Normalized(2..5)), Some("."));
+        assert_eq!(n.get_range(Range::Original(6..7)), Some("y"));
+
+        // Removal
+        let mut n = NormalizedString::from("a--b");
+        RegexReplace::new("-+", "")
+            .unwrap()
+            .normalize(&mut n)
+            .unwrap();
+        assert_eq!(n.get(), "ab");
+        assert_eq!(n.get_range(Range::Original(3..4)), Some("b"));
+    }
+
+    #[test]
+    fn serialization() {
+        let replace = RegexReplace::new(r"(\w+)'s", "$1 is").unwrap();
+        let replace_s = r#"{"type":"RegexReplace","pattern":"(\\w+)'s","replacement":"$1 is"}"#;
+        assert_eq!(serde_json::to_string(&replace).unwrap(), replace_s);
+        assert_eq!(
+            serde_json::from_str::<RegexReplace>(replace_s).unwrap(),
+            replace
+        );
+        assert!(serde_json::from_str::<RegexReplace>(
+            r#"{"type":"RegexReplace","pattern":"(","replacement":""}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn serialization_through_wrapper() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::normalizers::NormalizerWrapper;
+        use crate::Tokenizer;
+        use std::str::FromStr;
+
+        let replace_s = r#"{"type":"RegexReplace","pattern":" {2,}","replacement":" "}"#;
+        let wrapper: NormalizerWrapper = serde_json::from_str(replace_s).unwrap();
+        assert!(matches!(wrapper, NormalizerWrapper::RegexReplace(_)));
+        assert_eq!(serde_json::to_string(&wrapper).unwrap(), replace_s);
+
+        let mut tokenizer = Tokenizer::new(WordLevel::default());
+        tokenizer.with_normalizer(RegexReplace::new(" {2,}", " ").unwrap());
+        let tokenizer = Tokenizer::from_str(&tokenizer.to_string(false).unwrap()).unwrap();
+        let mut n = NormalizedString::from("héllo   world");
+        tokenizer
+            .get_normalizer()
+            .unwrap()
+            .normalize(&mut n)
+            .unwrap();
+        assert_eq!(n.get(), "héllo world");
+    }
+}
@@ -0,0 +1,227 @@
+use crate::normalizers::unicode::Nmt;
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
+
+/// We use this custom deserializer to rebuild the mappings of `SentencePieceNormalizer`
+#[doc(hidden)]
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+struct SentencePieceNormalizerDeserializer {
+    tsv: String,
+}
+
+impl std::convert::TryFrom<SentencePieceNormalizerDeserializer> for SentencePieceNormalizer {
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn try_from(v: SentencePieceNormalizerDeserializer) -> Result<Self> {
+        Self::from_tsv(&v.tsv)
+    }
+}
+
+/// Replicates the `nmt_nfkc` normalization of SentencePiece, without requiring its
+/// precompiled charsmap: the `Nmt` normalization and NFKC are followed by the custom mappings
+/// of a `normalization_rule_tsv`, then the whitespaces are stripped and merged.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", try_from = "SentencePieceNormalizerDeserializer")]
+pub struct SentencePieceNormalizer {
+    tsv: String,
+    #[serde(skip)]
+    mappings: HashMap<String, String>,
+    #[serde(skip)]
+    max_source_len: usize,
+}
+
+impl PartialEq for SentencePieceNormalizer {
+    fn eq(&self, other: &Self) -> bool {
+        self.tsv == other.tsv
+    }
+}
+
+/// Parse a space separated list of hexadecimal code points, like `41 302`
+fn parse_codepoints(field: &str) -> Result<String> {
+    field
+        .split_whitespace()
+        .map(|hex| {
+            u32::from_str_radix(hex, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| {
+                    format!("Invalid code point `{}` in normalization rules", hex).into()
+                })
+        })
+        .collect()
+}
+
+impl SentencePieceNormalizer {
+    /// Build the normalizer from the content of a `normalization_rule_tsv`. Each line maps the
+    /// source code points to the target ones (which may be empty to remove the source), with
+    /// optional comments after a third tab.
+    pub fn from_tsv(tsv: &str) -> Result<Self> {
+        let mut mappings = HashMap::new();
+        for line in tsv.lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split('\t');
+            let source = parse_codepoints(fields.next().unwrap_or(""))?;
+            let target = parse_codepoints(fields.next().unwrap_or(""))?;
+            if source.is_empty() {
+                return Err(format!("Missing source in normalization rule `{}`", line).into());
+            }
+            mappings.insert(source, target);
+        }
+        let max_source_len = mappings
+            .keys()
+            .map(|s| s.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        Ok(Self {
+            tsv: tsv.to_owned(),
+            mappings,
+            max_source_len,
+        })
+    }
+
+    /// Replace the longest matching sources with their targets, keeping the alignments
+    fn apply_mappings(&self, normalized: &mut NormalizedString) {
+        if self.mappings.is_empty() {
+            return;
+        }
+        let chars: Vec<char> = normalized.get().chars().collect();
+        let mut dest: Vec<(char, isize)> = Vec::with_capacity(chars.len());
+        let mut initial_offset = 0;
+
+        let mut i = 0;
+        while i < chars.len() {
+            let matched = (1..=self.max_source_len.min(chars.len() - i))
+                .rev()
+                .find_map(|len| {
+                    let source: String = chars[i..i + len].iter().collect();
+                    self.mappings.get(&source).map(|target| (len, target))
+                });
+            let (removed, target) = match matched {
+                Some(matched) => matched,
+                None => {
+                    dest.push((chars[i], 0));
+                    i += 1;
+                    continue;
+                }
+            };
+
+            // Each new char is aligned with the char it replaces, the extra ones being
+            // inserted or removed at the end
+            let added = target.chars().count();
+            let kept = removed.min(added);
+            dest.extend(target.chars().enumerate().map(|(j, c)| {
+                let change = if j < kept { 0 } else { 1 };
+                (c, change)
+            }));
+            let extra = (removed - kept) as isize;
+            if extra > 0 {
+                match dest.last_mut() {
+                    Some((_, change)) => *change -= extra,
+                    None => initial_offset += extra as usize,
+                }
+            }
+            i += removed;
+        }
+        normalized.transform(dest, initial_offset);
+    }
+}
+
+impl Normalizer for SentencePieceNormalizer {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        Nmt.normalize(normalized)?;
+        normalized.nfkc();
+        self.apply_mappings(normalized);
+
+        normalized.strip();
+        let previous_is_space = Cell::new(false);
+        normalized.filter(|c| {
+            let is_space = c == ' ';
+            let keep = !(is_space && previous_is_space.get());
+            previous_is_space.set(is_space);
+            keep
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalizer::Range;
+
+    fn normalize(normalizer: &SentencePieceNormalizer, input: &str) -> NormalizedString {
+        let mut n = NormalizedString::from(input);
+        normalizer.normalize(&mut n).unwrap();
+        n
+    }
+
+    #[test]
+    fn nmt_nfkc() {
+        // Outputs of SentencePiece with the default `nmt_nfkc` rules
+        let normalizer = SentencePieceNormalizer::from_tsv("").unwrap();
+        let cases = [
+            ("ｈｅｌｌｏ　ｗｏｒｌｄ", "hello world"),
+            ("  Hello   world  ", "Hello world"),
+            ("ﬁne ①", "fine 1"),
+            ("a\u{7}b\tc", "ab c"),
+            ("ｶﾀｶﾅ", "カタカナ"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize(&normalizer, input).get(), expected);
+        }
+    }
+
+    #[test]
+    fn custom_mappings() {
+        // With a comment, a multi code points source and a removal
+        let tsv = "41\t61\t# A => a\n6E 302\t6E\nAD\t\n";
+        let normalizer = SentencePieceNormalizer::from_tsv(tsv).unwrap();
+
+        let n = normalize(&normalizer, "An\u{302}o\u{AD}B");
+        assert_eq!(n.get(), "anoB");
+        // The replacement is aligned with the first char of its source
+        assert_eq!(n.get_range_original(Range::Normalized(1..2)), Some("n"));
+        assert_eq!(
+            n.get_range(Range::Original(n.len_original() - 1..)),
+            Some("B")
+        );
+
+        assert!(SentencePieceNormalizer::from_tsv("XYZ\t41").is_err());
+        assert!(SentencePieceNormalizer::from_tsv("\t41").is_err());
+    }
+
+    #[test]
+    fn serialization() {
+        let normalizer = SentencePieceNormalizer::from_tsv("41\t61").unwrap();
+        let serialized = serde_json::to_string(&normalizer).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"SentencePieceNormalizer","tsv":"41\t61"}"#
+        );
+        let deserialized: SentencePieceNormalizer = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, normalizer);
+        assert_eq!(normalize(&deserialized, "A").get(), "a");
+    }
+
+    #[test]
+    fn serialization_through_wrapper() {
+        use crate::normalizers::NormalizerWrapper;
+
+        let normalizer_s = r#"{"type":"SentencePieceNormalizer","tsv":"41\t61"}"#;
+        let wrapper: NormalizerWrapper = serde_json::from_str(normalizer_s).unwrap();
+        assert!(matches!(
+            wrapper,
+            NormalizerWrapper::SentencePieceNormalizer(_)
+        ));
+        assert_eq!(serde_json::to_string(&wrapper).unwrap(), normalizer_s);
+        let mut n = NormalizedString::from("A  b");
+        wrapper.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "a b");
+    }
+}
@@ -1,46 +1,65 @@
 use crate::tokenizer::{NormalizedString, Normalizer, Result};
 use crate::utils::macro_rules_attribute;
+use serde::{Deserialize, Serialize};
 
-#[derive(Default, Copy, Clone, Debug)]
-#[macro_rules_attribute(impl_serde_type!)]
-pub struct NFD;
-impl Normalizer for NFD {
-    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
-        normalized.nfd();
-        Ok(())
-    }
+/// The four Unicode normalization forms
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum UnicodeNormForm {
+    NFC,
+    NFD,
+    NFKC,
+    NFKD,
 }
 
-#[derive(Default, Copy, Clone, Debug)]
-#[macro_rules_attribute(impl_serde_type!)]
-pub struct NFKD;
-impl Normalizer for NFKD {
-    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
-        normalized.nfkd();
-        Ok(())
-    }
+/// Applies the Unicode normalization `form`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub struct UnicodeNormalizer {
+    pub form: UnicodeNormForm,
 }
 
-#[derive(Default, Copy, Clone, Debug)]
-#[macro_rules_attribute(impl_serde_type!)]
-pub struct NFC;
-impl Normalizer for NFC {
-    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
-        normalized.nfc();
-        Ok(())
+impl UnicodeNormalizer {
+    pub fn new(form: UnicodeNormForm) -> Self {
+        Self { form }
     }
 }
 
-#[derive(Default, Copy, Clone, Debug)]
-#[macro_rules_attribute(impl_serde_type!)]
-pub struct NFKC;
-impl Normalizer for NFKC {
+impl Normalizer for UnicodeNormalizer {
     fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
-        normalized.nfkc();
+        match self.form {
+            UnicodeNormForm::NFC => normalized.nfc(),
+            UnicodeNormForm::NFD => normalized.nfd(),
+            UnicodeNormForm::NFKC => normalized.nfkc(),
+            UnicodeNormForm::NFKD => normalized.nfkd(),
+        };
         Ok(())
     }
 }
 
+/// Each form also has its own unit struct, which keeps its own serialization type
+macro_rules! unicode_form_normalizer {
+    ($form:ident) => {
+        #[derive(Default, Copy, Clone, Debug)]
+        #[macro_rules_attribute(impl_serde_type!)]
+        pub struct $form;
+        impl Normalizer for $form {
+            fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+                UnicodeNormalizer::from(*self).normalize(normalized)
+            }
+        }
+        impl From<$form> for UnicodeNormalizer {
+            fn from(_: $form) -> Self {
+                Self::new(UnicodeNormForm::$form)
+            }
+        }
+    };
+}
+
+unicode_form_normalizer!(NFD);
+unicode_form_normalizer!(NFKD);
+unicode_form_normalizer!(NFC);
+unicode_form_normalizer!(NFKC);
+
 fn do_nmt(normalized: &mut NormalizedString) {
     // Ascii Control characters
     normalized
@@ -100,4 +119,53 @@ mod tests {
 
         assert_eq!(n.alignments_original(), vec![(0, 2), (0, 2), (0, 2)]);
     }
+
+    #[test]
+    fn unicode_normalizer() {
+        let forms: [(UnicodeNormForm, &dyn Normalizer); 4] = [
+            (UnicodeNormForm::NFC, &NFC),
+            (UnicodeNormForm::NFD, &NFD),
+            (UnicodeNormForm::NFKC, &NFKC),
+            (UnicodeNormForm::NFKD, &NFKD),
+        ];
+        for (form, normalizer) in forms {
+            let mut n = NormalizedString::from("e\u{301} \u{fb01} é");
+            UnicodeNormalizer::new(form).normalize(&mut n).unwrap();
+            let mut expected = NormalizedString::from("e\u{301} \u{fb01} é");
+            normalizer.normalize(&mut expected).unwrap();
+            assert_eq!(n, expected);
+        }
+        assert_eq!(
+            UnicodeNormalizer::from(NFKD),
+            UnicodeNormalizer::new(UnicodeNormForm::NFKD)
+        );
+        assert_eq!(serde_json::to_string(&NFKD).unwrap(), r#"{"type":"NFKD"}"#);
+    }
+
+    #[test]
+    fn unicode_normalizer_serialization() {
+        use crate::NormalizerWrapper;
+
+        let normalizer = UnicodeNormalizer::new(UnicodeNormForm::NFKC);
+        let json = r#"{"type":"UnicodeNormalizer","form":"NFKC"}"#;
+        assert_eq!(serde_json::to_string(&normalizer).unwrap(), json);
+        assert_eq!(
+            serde_json::from_str::<UnicodeNormalizer>(json).unwrap(),
+            normalizer
+        );
+
+        let wrapper: NormalizerWrapper = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            wrapper,
+            NormalizerWrapper::UnicodeNormalizer(n) if n == normalizer
+        ));
+        assert_eq!(serde_json::to_string(&wrapper).unwrap(), json);
+        let mut n = NormalizedString::from("\u{fb01}");
+        wrapper.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "fi");
+
+        // The forms keep their own serialization
+        let wrapper: NormalizerWrapper = serde_json::from_str(r#"{"type":"NFKC"}"#).unwrap();
+        assert!(matches!(wrapper, NormalizerWrapper::NFKC(_)));
+    }
 }
@@ -4,7 +4,7 @@ use crate::utils::SysRegex;
 use serde::{Deserialize, Serialize};
 
 use crate::tokenizer::{
-    Decoder, Encoding, PostProcessor, PreTokenizedString, PreTokenizer, Result,
+    Decoder, EncodeOptions, Encoding, PostProcessor, PreTokenizedString, PreTokenizer, Result,
     SplitDelimiterBehavior,
 };
 use crate::utils::macro_rules_attribute;
@@ -116,9 +116,19 @@ impl ByteLevel {
 // TODO: Give the ability to modify this regex
 impl PreTokenizer for ByteLevel {
     fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        self.pre_tokenize_with_options(pretokenized, &EncodeOptions::default())
+    }
+
+    /// `options.add_prefix_space`, when set, overrides `self.add_prefix_space`
+    fn pre_tokenize_with_options(
+        &self,
+        pretokenized: &mut PreTokenizedString,
+        options: &EncodeOptions,
+    ) -> Result<()> {
         let re_ref: &SysRegex = &RE;
+        let add_prefix_space = options.add_prefix_space.unwrap_or(self.add_prefix_space);
         pretokenized.split(|_, mut normalized| {
-            if self.add_prefix_space && !normalized.get().starts_with(' ') {
+            if add_prefix_space && !normalized.get().starts_with(' ') {
                 normalized.prepend(" ");
             }
             if self.use_regex {
@@ -241,6 +251,7 @@ mod tests {
         Decoder, Encoding, OffsetReferential, OffsetType, PostProcessor, PreTokenizedString,
         PreTokenizer,
     };
+    use crate::PreTokenizerWrapper;
     use std::iter::FromIterator;
 
     #[test]
@@ -591,4 +602,44 @@ mod tests {
         .unwrap();
         assert!(!byte_level.use_regex);
     }
+
+    #[test]
+    fn add_prefix_space_override() {
+        use crate::pre_tokenizers::sequence::Sequence;
+
+        let splits = |pretok: &dyn PreTokenizer, options: &EncodeOptions| {
+            let mut pretokenized = PreTokenizedString::from("Hello");
+            pretok
+                .pre_tokenize_with_options(&mut pretokenized, options)
+                .unwrap();
+            pretokenized
+                .get_splits(OffsetReferential::Normalized, OffsetType::Byte)
+                .into_iter()
+                .map(|(s, _, _)| s.to_owned())
+                .collect::<Vec<_>>()
+        };
+        let keep = EncodeOptions::default();
+        let with_space = EncodeOptions {
+            add_prefix_space: Some(true),
+            ..Default::default()
+        };
+        let without_space = EncodeOptions {
+            add_prefix_space: Some(false),
+            ..Default::default()
+        };
+
+        let bytelevel = ByteLevel::default().add_prefix_space(true);
+        assert_eq!(splits(&bytelevel, &keep), vec!["ĠHello"]);
+        assert_eq!(splits(&bytelevel, &without_space), vec!["Hello"]);
+
+        let bytelevel = ByteLevel::default().add_prefix_space(false);
+        assert_eq!(splits(&bytelevel, &keep), vec!["Hello"]);
+        assert_eq!(splits(&bytelevel, &with_space), vec!["ĠHello"]);
+
+        // The options go through the wrappers
+        let sequence = Sequence::new(vec![bytelevel.into()]);
+        assert_eq!(splits(&sequence, &with_space), vec!["ĠHello"]);
+        let wrapper: PreTokenizerWrapper = sequence.into();
+        assert_eq!(splits(&wrapper, &with_space), vec!["ĠHello"]);
+    }
 }
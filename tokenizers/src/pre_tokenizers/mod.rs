@@ -20,8 +20,8 @@ use crate::pre_tokenizers::punctuation::Punctuation;
 use crate::pre_tokenizers::sequence::Sequence;
 use crate::pre_tokenizers::split::Split;
 use crate::pre_tokenizers::unicode_scripts::UnicodeScripts;
-use crate::pre_tokenizers::whitespace::{Whitespace, WhitespaceSplit};
-use crate::{PreTokenizedString, PreTokenizer};
+use crate::pre_tokenizers::whitespace::{KeepWhitespaceSplit, Whitespace, WhitespaceSplit};
+use crate::{EncodeOptions, PreTokenizedString, PreTokenizer};
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 #[serde(untagged)]
@@ -35,6 +35,7 @@ pub enum PreTokenizerWrapper {
     Split(Split),
     Punctuation(Punctuation),
     WhitespaceSplit(WhitespaceSplit),
+    KeepWhitespaceSplit(KeepWhitespaceSplit),
     Digits(Digits),
     UnicodeScripts(UnicodeScripts),
 }
@@ -51,10 +52,23 @@ impl PreTokenizer for PreTokenizerWrapper {
             Self::Sequence(tok) => tok.pre_tokenize(normalized),
             Self::Split(tok) => tok.pre_tokenize(normalized),
             Self::WhitespaceSplit(wspt) => wspt.pre_tokenize(normalized),
+            Self::KeepWhitespaceSplit(wspt) => wspt.pre_tokenize(normalized),
             Self::Digits(wspt) => wspt.pre_tokenize(normalized),
             Self::UnicodeScripts(us) => us.pre_tokenize(normalized),
         }
     }
+
+    fn pre_tokenize_with_options(
+        &self,
+        normalized: &mut PreTokenizedString,
+        options: &EncodeOptions,
+    ) -> crate::Result<()> {
+        match self {
+            Self::ByteLevel(bpt) => bpt.pre_tokenize_with_options(normalized, options),
+            Self::Sequence(tok) => tok.pre_tokenize_with_options(normalized, options),
+            _ => self.pre_tokenize(normalized),
+        }
+    }
 }
 
 impl_enum_from!(BertPreTokenizer, PreTokenizerWrapper, BertPreTokenizer);
@@ -66,6 +80,11 @@ impl_enum_from!(Sequence, PreTokenizerWrapper, Sequence);
 impl_enum_from!(Split, PreTokenizerWrapper, Split);
 impl_enum_from!(Metaspace, PreTokenizerWrapper, Metaspace);
 impl_enum_from!(WhitespaceSplit, PreTokenizerWrapper, WhitespaceSplit);
+impl_enum_from!(
+    KeepWhitespaceSplit,
+    PreTokenizerWrapper,
+    KeepWhitespaceSplit
+);
 impl_enum_from!(Digits, PreTokenizerWrapper, Digits);
 impl_enum_from!(UnicodeScripts, PreTokenizerWrapper, UnicodeScripts);
 
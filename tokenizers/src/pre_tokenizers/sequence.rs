@@ -1,5 +1,5 @@
 use crate::pre_tokenizers::PreTokenizerWrapper;
-use crate::tokenizer::{PreTokenizedString, PreTokenizer, Result};
+use crate::tokenizer::{EncodeOptions, PreTokenizedString, PreTokenizer, Result};
 use crate::utils::macro_rules_attribute;
 use serde::{Deserialize, Serialize};
 
@@ -30,6 +30,17 @@ impl PreTokenizer for Sequence {
         }
         Ok(())
     }
+
+    fn pre_tokenize_with_options(
+        &self,
+        pretokenized: &mut PreTokenizedString,
+        options: &EncodeOptions,
+    ) -> Result<()> {
+        for pretokenizer in &self.pretokenizers {
+            pretokenizer.pre_tokenize_with_options(pretokenized, options)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -32,6 +32,12 @@ impl PreTokenizer for Whitespace {
 #[macro_rules_attribute(impl_serde_type!)]
 pub struct WhitespaceSplit;
 
+impl Default for WhitespaceSplit {
+    fn default() -> Self {
+        Self
+    }
+}
+
 impl PreTokenizer for WhitespaceSplit {
     fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
         pretokenized.split(|_, normalized| {
@@ -40,6 +46,30 @@ impl PreTokenizer for WhitespaceSplit {
     }
 }
 
+/// Same as `WhitespaceSplit`, except that the runs of whitespace are kept as their own splits
+/// instead of being removed, so that the splits cover the whole input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[macro_rules_attribute(impl_serde_type!)]
+pub struct KeepWhitespaceSplit;
+
+impl Default for KeepWhitespaceSplit {
+    fn default() -> Self {
+        Self
+    }
+}
+
+impl PreTokenizer for KeepWhitespaceSplit {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"\s+").unwrap();
+        }
+        let re_ref: &Regex = &RE;
+
+        pretokenized
+            .split(|_, normalized| normalized.split(re_ref, SplitDelimiterBehavior::Isolated))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,4 +132,61 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn whitespace_split_keep_whitespace() {
+        let tests = vec![
+            (
+                "Hey  man!",
+                vec![("Hey", (0, 3)), ("  ", (3, 5)), ("man!", (5, 9))],
+            ),
+            (
+                " Hey,\t\nman ",
+                vec![
+                    (" ", (0, 1)),
+                    ("Hey,", (1, 5)),
+                    ("\t\n", (5, 7)),
+                    ("man", (7, 10)),
+                    (" ", (10, 11)),
+                ],
+            ),
+        ];
+        let pretok = KeepWhitespaceSplit;
+        for (s, res) in tests {
+            let mut pretokenized = PreTokenizedString::from(s);
+            pretok.pre_tokenize(&mut pretokenized).unwrap();
+            let splits = pretokenized
+                .get_splits(OffsetReferential::Original, OffsetType::Byte)
+                .into_iter()
+                .map(|(s, o, _)| (s, o))
+                .collect::<Vec<_>>();
+            // The splits must cover the input contiguously
+            assert_eq!(
+                splits.iter().map(|(s, _)| *s).collect::<String>(),
+                s.to_string()
+            );
+            assert!(splits.windows(2).all(|w| w[0].1 .1 == w[1].1 .0));
+            assert_eq!(splits, res);
+        }
+    }
+
+    #[test]
+    fn whitespace_split_serialization() {
+        use crate::pre_tokenizers::PreTokenizerWrapper;
+
+        let pretok: PreTokenizerWrapper =
+            serde_json::from_str(r#"{"type":"WhitespaceSplit"}"#).unwrap();
+        assert_eq!(
+            pretok,
+            PreTokenizerWrapper::WhitespaceSplit(WhitespaceSplit {})
+        );
+
+        let serialized = serde_json::to_string(&KeepWhitespaceSplit).unwrap();
+        assert_eq!(serialized, r#"{"type":"KeepWhitespaceSplit"}"#);
+        let pretok: PreTokenizerWrapper = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(
+            pretok,
+            PreTokenizerWrapper::KeepWhitespaceSplit(KeepWhitespaceSplit)
+        );
+    }
 }
@@ -23,6 +23,13 @@ impl BertProcessing {
     pub fn new(sep: (String, u32), cls: (String, u32)) -> Self {
         Self { sep, cls }
     }
+
+    /// Update the ids of the special tokens, keeping the ones `token_to_id` doesn't know
+    pub(crate) fn update_ids(&mut self, token_to_id: &dyn Fn(&str) -> Option<u32>) {
+        for (token, id) in [&mut self.sep, &mut self.cls] {
+            *id = token_to_id(token).unwrap_or(*id);
+        }
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -52,6 +52,20 @@ impl PostProcessor for PostProcessorWrapper {
     }
 }
 
+impl PostProcessorWrapper {
+    /// Update the ids of the special tokens added by this processor, after a change of the
+    /// vocabulary. The tokens unknown to `token_to_id` keep their id.
+    pub(crate) fn update_ids(&mut self, token_to_id: &dyn Fn(&str) -> Option<u32>) {
+        match self {
+            Self::Bert(bert) => bert.update_ids(token_to_id),
+            Self::ByteLevel(_) => {}
+            Self::Roberta(roberta) => roberta.update_ids(token_to_id),
+            Self::Template(template) => template.update_ids(token_to_id),
+            Self::Sequence(sequence) => sequence.update_ids(token_to_id),
+        }
+    }
+}
+
 impl_enum_from!(BertProcessing, PostProcessorWrapper, Bert);
 impl_enum_from!(ByteLevel, PostProcessorWrapper, ByteLevel);
 impl_enum_from!(RobertaProcessing, PostProcessorWrapper, Roberta);
@@ -44,6 +44,13 @@ impl RobertaProcessing {
         self.add_prefix_space = v;
         self
     }
+
+    /// Update the ids of the special tokens, keeping the ones `token_to_id` doesn't know
+    pub(crate) fn update_ids(&mut self, token_to_id: &dyn Fn(&str) -> Option<u32>) {
+        for (token, id) in [&mut self.sep, &mut self.cls] {
+            *id = token_to_id(token).unwrap_or(*id);
+        }
+    }
 }
 
 impl PostProcessor for RobertaProcessing {
@@ -13,6 +13,16 @@ impl Sequence {
     pub fn new(processors: Vec<PostProcessorWrapper>) -> Self {
         Self { processors }
     }
+
+    pub(crate) fn push(&mut self, processor: PostProcessorWrapper) {
+        self.processors.push(processor);
+    }
+
+    pub(crate) fn update_ids(&mut self, token_to_id: &dyn Fn(&str) -> Option<u32>) {
+        for processor in &mut self.processors {
+            processor.update_ids(token_to_id);
+        }
+    }
 }
 
 impl PostProcessor for Sequence {
@@ -488,6 +488,15 @@ impl TemplateProcessing {
         TemplateProcessingBuilder::default()
     }
 
+    /// Update the ids of the special tokens, keeping the ones `token_to_id` doesn't know
+    pub(crate) fn update_ids(&mut self, token_to_id: &dyn Fn(&str) -> Option<u32>) {
+        for special_token in self.special_tokens.0.values_mut() {
+            for (token, id) in special_token.tokens.iter().zip(&mut special_token.ids) {
+                *id = token_to_id(token).unwrap_or(*id);
+            }
+        }
+    }
+
     fn apply_template(
         &self,
         template: &[Piece],
@@ -25,6 +25,17 @@ pub struct AddedToken {
     pub normalized: bool,
     /// Whether this token is special
     pub special: bool,
+    /// Whether the content of this token should go through the normalizer when added to
+    /// a `Tokenizer`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub normalize_when_loading: bool,
+    /// Whether the content of this token is a regex, matching the text to extract, rather
+    /// than the literal text itself
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_regex: bool,
+    /// The id this token should get when added to a vocabulary, if it is still free there
+    #[serde(skip)]
+    pub id: Option<u32>,
 }
 
 impl AddedToken {
@@ -38,6 +49,35 @@ impl AddedToken {
             ..Default::default()
         }
     }
+    /// Build a token extracting every match of the regex `pattern` from the input text, rather
+    /// than a literal content. The pattern is kept as the content of the token, and the id of
+    /// the token is the one of all its matches. When added to a vocabulary, the token gets
+    /// `id` if it is still free, or the next available id like any other token.
+    ///
+    /// The pattern is not normalized, so a normalized regex token matches against the raw
+    /// normalized text. Fails when `pattern` is not a valid regex.
+    pub fn from_regex(pattern: &str, id: Option<u32>) -> crate::Result<Self> {
+        Regex::new(pattern)?;
+        Ok(Self {
+            content: pattern.to_owned(),
+            is_regex: true,
+            id,
+            ..Default::default()
+        })
+    }
+    /// Whether this token was built with `from_regex`
+    pub fn is_regex(&self) -> bool {
+        self.is_regex
+    }
+    /// Whether this token is found somewhere in `text`: its regex matches it, or its content
+    /// is part of it. This does not check the `single_word`, `lstrip` and `rstrip` options.
+    pub fn matches(&self, text: &str) -> bool {
+        if self.is_regex {
+            Regex::new(&self.content).is_ok_and(|regex| regex.is_match(text))
+        } else {
+            !self.content.is_empty() && text.contains(&self.content)
+        }
+    }
     /// Specify whether this token should only match on whole single words, and never
     /// part of a word.
     #[must_use]
@@ -72,6 +112,22 @@ impl AddedToken {
         self.special = special;
         self
     }
+    /// Specify whether the content of this token should be normalized by the `Tokenizer`'s
+    /// normalizer before being registered, so that it gets stored (and looked up) in its
+    /// normalized form. Unlike `normalized`, which only changes how the token is matched
+    /// against the input text, this modifies the content itself.
+    #[must_use]
+    pub fn normalize_when_loading(mut self, enabled: bool) -> Self {
+        self.normalize_when_loading = enabled;
+        self
+    }
+    /// Specify the id this token should get when added to a vocabulary. It is only used when
+    /// still free there, otherwise the token gets the next available id.
+    #[must_use]
+    pub fn id(mut self, id: Option<u32>) -> Self {
+        self.id = id;
+        self
+    }
 }
 impl Default for AddedToken {
     fn default() -> Self {
@@ -82,6 +138,9 @@ impl Default for AddedToken {
             rstrip: false,
             normalized: true,
             special: false,
+            normalize_when_loading: false,
+            is_regex: false,
+            id: None,
         }
     }
 }
@@ -92,7 +151,8 @@ impl std::hash::Hash for AddedToken {
     }
 }
 
-type MatchingSet = (AhoCorasick, Vec<u32>);
+/// The trie of the literal tokens with their ids, and the regex tokens with their id
+type MatchingSet = (AhoCorasick, Vec<u32>, Vec<(Regex, u32)>);
 
 lazy_static! {
     static ref STARTS_WITH_WORD: Regex = Regex::new(r"^\w").unwrap();
@@ -163,6 +223,9 @@ pub struct AddedVocabulary {
 
     /// Whether or not special tokens should be splitted when encoding. This is equivalent to ignoring them
     encode_special_tokens: bool,
+
+    /// The ids below this one are never given to new tokens, unless requested
+    reserved_ids_end: u32,
 }
 
 impl AddedVocabulary {
@@ -181,9 +244,10 @@ impl AddedVocabulary {
             added_tokens: vec![],
             special_tokens: vec![],
             special_tokens_set: HashSet::new(),
-            split_trie: (trie, vec![]),
-            split_normalized_trie: (normalized_trie, vec![]),
+            split_trie: (trie, vec![], vec![]),
+            split_normalized_trie: (normalized_trie, vec![], vec![]),
             encode_special_tokens: false,
+            reserved_ids_end: 0,
         }
     }
     /// Size of the additional vocabulary
@@ -197,6 +261,21 @@ impl AddedVocabulary {
         self.added_tokens_map.is_empty()
     }
 
+    /// The id that the next added token would get, if it is not part of the vocabulary
+    pub(crate) fn next_id(&self, model: &impl Model) -> u32 {
+        self.added_tokens_map
+            .values()
+            .map(|id| id + 1)
+            .chain([model.get_vocab_size() as u32, self.reserved_ids_end])
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Keep the ids below `end` for another use: the tokens added from now on get larger ids
+    pub(crate) fn reserve_ids_until(&mut self, end: u32) {
+        self.reserved_ids_end = self.reserved_ids_end.max(end);
+    }
+
     /// Get the additional vocabulary
     pub fn get_vocab(&self) -> &HashMap<String, u32> {
         &self.added_tokens_map
@@ -262,9 +341,13 @@ impl AddedVocabulary {
         model: &impl Model,
         normalizer: Option<&N>,
     ) -> usize {
+        // The regex tokens that don't compile get ignored
+        let is_valid = |token: &AddedToken| !token.is_regex || Regex::new(&token.content).is_ok();
+
         // Handle special tokens (if any)
         for token in tokens {
             if token.special
+                && is_valid(token)
                 && !token.content.is_empty()
                 && !self.special_tokens_set.contains(&token.content)
             {
@@ -276,25 +359,34 @@ impl AddedVocabulary {
         // Then we delegate to `add_tokens`, that will take care of refreshing added tokens too.
         let mut ignored = 0;
         for token in tokens {
-            if token.content.is_empty() || self.added_tokens_map_r.values().any(|val| val == token)
+            if token.content.is_empty()
+                || !is_valid(token)
+                || self.added_tokens_map_r.values().any(|val| val == token)
             {
                 ignored += 1;
                 continue;
             }
             // If a token is already part of the vocabulary, we mark it as added
+            let requested_id = token.id.filter(|id| {
+                !self.added_tokens_map_r.contains_key(id) && model.id_to_token(*id).is_none()
+            });
             let new_id = if let Some(new_id) = self.token_to_id(&token.content, model) {
                 new_id
+            } else if let Some(requested_id) = requested_id {
+                requested_id
             } else {
-                self.added_tokens_map.values().cloned().max().map_or(
-                    model.get_vocab_size() as u32,
-                    |max| {
+                self.added_tokens_map
+                    .values()
+                    .cloned()
+                    .max()
+                    .map_or(model.get_vocab_size() as u32, |max| {
                         if (max >= model.get_vocab_size() as u32) || model.get_vocab_size() == 0 {
                             max + 1
                         } else {
                             model.get_vocab_size() as u32
                         }
-                    },
-                )
+                    })
+                    .max(self.reserved_ids_end)
             };
             // Make sure we modify the previous entry
             self.added_tokens_map
@@ -320,11 +412,56 @@ impl AddedVocabulary {
         tokens.len() - ignored
     }
 
+    /// Remove all the special tokens, except the ones that are also regular added tokens.
+    /// Returns the number of special tokens removed.
+    pub fn reset_special_tokens<N: Normalizer>(
+        &mut self,
+        model: &impl Model,
+        normalizer: Option<&N>,
+    ) -> usize {
+        let removed = std::mem::take(&mut self.special_tokens);
+        self.special_tokens_set.clear();
+        for token in &removed {
+            if !self.added_tokens.iter().any(|t| t.content == token.content) {
+                self.remove_token(&token.content);
+            }
+        }
+        self.refresh_added_tokens(model, normalizer);
+        removed.len()
+    }
+
+    /// Remove all the regular added tokens, except the ones that are also special tokens.
+    /// Returns the number of added tokens removed.
+    pub fn reset_added_tokens<N: Normalizer>(
+        &mut self,
+        model: &impl Model,
+        normalizer: Option<&N>,
+    ) -> usize {
+        let removed = std::mem::take(&mut self.added_tokens);
+        for token in &removed {
+            if !self.special_tokens_set.contains(&token.content) {
+                self.remove_token(&token.content);
+            }
+        }
+        self.refresh_added_tokens(model, normalizer);
+        removed.len()
+    }
+
+    fn remove_token(&mut self, content: &str) {
+        if let Some(id) = self.added_tokens_map.remove(content) {
+            self.added_tokens_map_r.remove(&id);
+        }
+    }
+
     /// Reconstruct our internal RegexSet when new tokens are added to the vocabulary.
     ///
     /// We keep two different RegexSet, one that will take care of matching against the
     /// non-normalized string, and one matching against the normalized one.
-    fn refresh_added_tokens<N: Normalizer>(&mut self, model: &impl Model, normalizer: Option<&N>) {
+    pub(crate) fn refresh_added_tokens<N: Normalizer>(
+        &mut self,
+        model: &impl Model,
+        normalizer: Option<&N>,
+    ) {
         type TupleTokenId<'a> = (&'a AddedToken, u32);
         let (normalized, non_normalized): (Vec<TupleTokenId>, Vec<TupleTokenId>) = self
             .special_tokens
@@ -339,12 +476,28 @@ impl AddedVocabulary {
             })
             .partition(|(token, _)| token.normalized);
 
+        // The regex tokens are matched on their own, without a trie
+        fn split_regexes(tokens: Vec<TupleTokenId>) -> (Vec<TupleTokenId>, Vec<(Regex, u32)>) {
+            let (regexes, literals): (Vec<TupleTokenId>, Vec<TupleTokenId>) =
+                tokens.into_iter().partition(|(token, _)| token.is_regex);
+            let regexes = regexes
+                .into_iter()
+                .map(|(token, id)| {
+                    let regex = Regex::new(&token.content).expect("Invalid regex added token");
+                    (regex, id)
+                })
+                .collect();
+            (literals, regexes)
+        }
+        let (non_normalized, regexes) = split_regexes(non_normalized);
+        let (normalized, nregexes) = split_regexes(normalized);
+
         let (tokens, ids): (Vec<&AddedToken>, Vec<u32>) = non_normalized.into_iter().unzip();
         let trie = AhoCorasickBuilder::new()
             .match_kind(MatchKind::LeftmostLongest)
             .build(tokens.iter().map(|token| &token.content))
             .expect("Failed to build tried when refreshing tokens");
-        self.split_trie = (trie, ids);
+        self.split_trie = (trie, ids, regexes);
 
         let (ntokens, nids): (Vec<&AddedToken>, Vec<u32>) = normalized.into_iter().unzip();
         let patterns: Vec<_> = ntokens
@@ -361,7 +514,7 @@ impl AddedVocabulary {
             .match_kind(MatchKind::LeftmostLongest)
             .build(patterns.iter().map(|content| content.get()))
             .expect("Failed to build tried when refreshing tokens (normalized)");
-        self.split_normalized_trie = (normalized_trie, nids);
+        self.split_normalized_trie = (normalized_trie, nids, nregexes);
     }
 
     /// Find any AddedToken in the given sentence, using the provided MatchingSet.
@@ -373,14 +526,37 @@ impl AddedVocabulary {
             return vec![(None, (0, 0))];
         }
 
+        let mut matches: Vec<(usize, usize, u32)> = split_re
+            .0
+            .find_iter(sentence)
+            .map(|mat| (mat.start(), mat.end(), split_re.1[mat.pattern()]))
+            .collect();
+        if !split_re.2.is_empty() {
+            for (regex, id) in &split_re.2 {
+                matches.extend(
+                    regex
+                        .find_iter(sentence)
+                        .filter(|mat| !mat.is_empty())
+                        .map(|mat| (mat.start(), mat.end(), *id)),
+                );
+            }
+            // Keep the leftmost-longest matches that don't overlap, like the trie does with
+            // the literal tokens, which win the ties
+            matches.sort_by_key(|(start, stop, _)| (*start, std::cmp::Reverse(*stop)));
+            let mut last_stop = 0;
+            matches.retain(|(start, stop, _)| {
+                let keep = *start >= last_stop;
+                if keep {
+                    last_stop = *stop;
+                }
+                keep
+            });
+        }
+
         let mut start_offset = 0;
         let mut splits = vec![];
 
-        for mat in split_re.0.find_iter(sentence) {
-            let mut start = mat.start();
-            let mut stop = mat.end();
-            let aho_id = mat.pattern();
-            let id = split_re.1[aho_id];
+        for (mut start, mut stop, id) in matches {
             let added_token = &self.added_tokens_map_r.get(&id).unwrap();
 
             if self.encode_special_tokens && self.special_tokens_set.contains(&added_token.content)
@@ -1029,4 +1205,90 @@ mod tests {
             vec![("my", Some(vec![0])), ("ä»Ĭ", Some(vec![1])),]
         );
     }
+
+    #[test]
+    fn regex_tokens() {
+        let model = ModelMock::new(&[("test", 0), ("tost", 1)]);
+        let mut vocab = AddedVocabulary::new();
+        let normalizer: Option<&NormalizerWrapper> = None;
+
+        let date = AddedToken::from_regex(r"\d{4}-\d{2}-\d{2}", Some(10)).unwrap();
+        assert!(date.is_regex());
+        assert!(date.matches("on 2024-06-01"));
+        assert!(!date.matches("on 2024-06"));
+        assert!(!AddedToken::from("<pad>", true).is_regex());
+        assert!(AddedToken::from("<pad>", true).matches("a<pad>"));
+        assert!(AddedToken::from_regex("(", None).is_err());
+
+        assert_eq!(
+            vocab.add_tokens(
+                &[
+                    date,
+                    AddedToken::from_regex(r"<\w+>", None).unwrap(),
+                    AddedToken::from("<mask>", true),
+                    // A regex token that doesn't compile gets ignored
+                    AddedToken {
+                        content: "(".into(),
+                        is_regex: true,
+                        ..Default::default()
+                    },
+                ],
+                &model,
+                normalizer
+            ),
+            3
+        );
+        // The pre-assigned id was free, the others get the next ones
+        assert_eq!(vocab.get_vocab()[r"\d{4}-\d{2}-\d{2}"], 10);
+        assert_eq!(vocab.get_vocab()[r"<\w+>"], 11);
+        assert_eq!(vocab.get_vocab()["<mask>"], 12);
+
+        // Each match of a regex token gets its id
+        let result = vocab.extract_and_normalize(normalizer, "2024-06-01 <mask> <masked> x");
+        assert_eq!(
+            simplify_output(&result),
+            vec![
+                ("2024-06-01", Some(vec![10])),
+                (" ", None),
+                ("<mask>", Some(vec![12])),
+                (" ", None),
+                ("<masked>", Some(vec![11])),
+                (" x", None),
+            ]
+        );
+
+        // A pre-assigned id already taken gets ignored
+        vocab.add_tokens(
+            &[AddedToken::from_regex(r"\$\d+", Some(1)).unwrap()],
+            &model,
+            normalizer,
+        );
+        assert_eq!(vocab.get_vocab()[r"\$\d+"], 13);
+
+        // Literal tokens can have a pre-assigned id too
+        vocab.add_tokens(
+            &[AddedToken::from("<sep>", true).id(Some(20))],
+            &model,
+            normalizer,
+        );
+        assert_eq!(vocab.get_vocab()["<sep>"], 20);
+    }
+
+    #[test]
+    fn regex_tokens_serialization() {
+        let token = AddedToken::from_regex(r"<\w+>", None).unwrap();
+        let serialized = serde_json::to_string(&token).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"content":"<\\w+>","single_word":false,"lstrip":false,"rstrip":false,"normalized":true,"special":false,"is_regex":true}"#
+        );
+        let deserialized: AddedToken = serde_json::from_str(&serialized).unwrap();
+        assert!(deserialized.is_regex());
+        assert_eq!(deserialized, token);
+        assert!(deserialized.matches("a <b>"));
+
+        // Literal tokens don't get the flag
+        let serialized = serde_json::to_string(&AddedToken::from("<pad>", true)).unwrap();
+        assert!(!serialized.contains("is_regex"));
+    }
 }
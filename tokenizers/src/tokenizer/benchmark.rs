@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use crate::tokenizer::{Result, Tokenizer};
+
+/// Latency statistics of `Tokenizer::benchmark_encode`. The percentiles are computed over the
+/// duration of each single encoding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub mean_ns: f64,
+    pub p50_ns: f64,
+    pub p95_ns: f64,
+    pub p99_ns: f64,
+    pub throughput_chars_per_sec: f64,
+    pub throughput_tokens_per_sec: f64,
+}
+
+/// The value below which `percentile` % of the sorted `durations` fall (nearest rank)
+fn percentile(durations: &[u128], percentile: f64) -> f64 {
+    if durations.is_empty() {
+        return 0.0;
+    }
+    let rank = (percentile / 100.0 * durations.len() as f64).ceil() as usize;
+    durations[rank.clamp(1, durations.len()) - 1] as f64
+}
+
+impl Tokenizer {
+    /// Measure the latency of `encode` (with special tokens) by encoding each of the `samples`
+    /// `iterations` times, one after the other. All the stats are `0.0` when nothing gets
+    /// encoded.
+    pub fn benchmark_encode(&self, samples: &[&str], iterations: usize) -> Result<BenchmarkResult> {
+        let mut durations = Vec::with_capacity(samples.len() * iterations);
+        let (mut n_chars, mut n_tokens) = (0, 0);
+        for _ in 0..iterations {
+            for sample in samples {
+                let start = Instant::now();
+                let encoding = self.encode(*sample, true)?;
+                durations.push(start.elapsed().as_nanos());
+                n_chars += sample.chars().count();
+                n_tokens += encoding.len();
+            }
+        }
+        durations.sort_unstable();
+
+        let total_ns: u128 = durations.iter().sum();
+        let per_sec = |count: usize| {
+            if total_ns == 0 {
+                0.0
+            } else {
+                count as f64 * 1e9 / total_ns as f64
+            }
+        };
+        Ok(BenchmarkResult {
+            mean_ns: if durations.is_empty() {
+                0.0
+            } else {
+                total_ns as f64 / durations.len() as f64
+            },
+            p50_ns: percentile(&durations, 50.0),
+            p95_ns: percentile(&durations, 95.0),
+            p99_ns: percentile(&durations, 99.0),
+            throughput_chars_per_sec: per_sec(n_chars),
+            throughput_tokens_per_sec: per_sec(n_tokens),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles() {
+        let durations: Vec<u128> = (1..=100).collect();
+        assert_eq!(percentile(&durations, 50.0), 50.0);
+        assert_eq!(percentile(&durations, 95.0), 95.0);
+        assert_eq!(percentile(&durations, 99.0), 99.0);
+        assert_eq!(percentile(&[7], 99.0), 7.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+    }
+}
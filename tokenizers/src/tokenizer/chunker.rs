@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use crate::tokenizer::{EncodeOptions, Encoding, InputSequence, OffsetType, Result, Tokenizer};
+use crate::utils::truncation::TruncationDirection;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ChunkerError {
+    /// A window must contain at least one token
+    #[error("Chunker error: `max_tokens` must be greater than 0")]
+    NoTokens,
+    /// The windows would never move forward
+    #[error("Chunker error: `overlap_tokens` ({0}) must be strictly less than `max_tokens` ({1})")]
+    OverlapTooLarge(usize, usize),
+}
+
+/// Splits a long text into overlapping windows of at most `max_tokens` tokens.
+///
+/// The text is encoded without special tokens, and without the truncation or padding
+/// configured on the `Tokenizer`. Each window then starts `max_tokens - overlap_tokens`
+/// tokens after the previous one, and its offsets point into the original text.
+#[derive(Clone, Debug)]
+pub struct PromptChunker {
+    pub tokenizer: Arc<Tokenizer>,
+    pub max_tokens: usize,
+    pub overlap_tokens: usize,
+}
+
+impl PromptChunker {
+    pub fn new(tokenizer: Arc<Tokenizer>, max_tokens: usize, overlap_tokens: usize) -> Self {
+        Self {
+            tokenizer,
+            max_tokens,
+            overlap_tokens,
+        }
+    }
+
+    /// Encode the given text and split it into windows
+    pub fn chunk(&self, text: &str) -> Result<Vec<Encoding>> {
+        if self.max_tokens == 0 {
+            return Err(ChunkerError::NoTokens.into());
+        }
+        if self.overlap_tokens >= self.max_tokens {
+            return Err(ChunkerError::OverlapTooLarge(self.overlap_tokens, self.max_tokens).into());
+        }
+
+        let mut encoding = self.tokenizer.encode_single_sequence(
+            InputSequence::from(text),
+            0,
+            OffsetType::Byte,
+            &EncodeOptions::default(),
+        )?;
+        if encoding.is_empty() {
+            return Ok(vec![]);
+        }
+
+        encoding.truncate(
+            self.max_tokens,
+            self.overlap_tokens,
+            TruncationDirection::Right,
+        );
+        let overflowing = encoding.take_overflowing();
+        Ok(std::iter::once(encoding).chain(overflowing).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::test_utils::word_level_tokenizer;
+
+    fn chunker(max_tokens: usize, overlap_tokens: usize) -> PromptChunker {
+        PromptChunker::new(Arc::new(word_level_tokenizer()), max_tokens, overlap_tokens)
+    }
+
+    fn windows(chunks: &[Encoding], text: &str) -> Vec<Vec<String>> {
+        chunks
+            .iter()
+            .map(|chunk| {
+                // Offsets are absolute in the original text
+                for (token, (start, end)) in chunk.get_tokens().iter().zip(chunk.get_offsets()) {
+                    assert_eq!(&text[*start..*end], token);
+                }
+                chunk.get_tokens().to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn overlapping_windows() {
+        let text = "hello world ! hello world";
+        let chunks = chunker(3, 1).chunk(text).unwrap();
+        assert_eq!(
+            windows(&chunks, text),
+            vec![vec!["hello", "world", "!"], vec!["!", "hello", "world"]]
+        );
+        assert_eq!(chunks[1].get_offsets()[0], (12, 13));
+
+        let chunks = chunker(2, 1).chunk(text).unwrap();
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks.iter().all(|c| c.len() == 2));
+    }
+
+    #[test]
+    fn no_overlap() {
+        let text = "hello world ! hello world";
+        let chunks = chunker(2, 0).chunk(text).unwrap();
+        assert_eq!(
+            windows(&chunks, text),
+            vec![vec!["hello", "world"], vec!["!", "hello"], vec!["world"]]
+        );
+    }
+
+    #[test]
+    fn shorter_than_window() {
+        let text = "hello world";
+        let chunks = chunker(8, 2).chunk(text).unwrap();
+        assert_eq!(windows(&chunks, text), vec![vec!["hello", "world"]]);
+        assert!(chunker(8, 2).chunk("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_overlap() {
+        assert!(chunker(2, 2).chunk("hello world !").is_err());
+        assert!(chunker(2, 3).chunk("hello world !").is_err());
+        assert!(chunker(0, 0).chunk("hello world !").is_err());
+    }
+}
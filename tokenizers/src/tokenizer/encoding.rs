@@ -1,9 +1,10 @@
 use crate::parallelism::*;
-use crate::tokenizer::{Offsets, Token};
+use crate::tokenizer::{Offsets, Result, Token, Tokenizer};
 use crate::utils::padding::PaddingDirection;
 use crate::utils::truncation::TruncationDirection;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io::Write;
 use std::ops::Range;
 
 /// Represents the output of a `Tokenizer`.
@@ -55,6 +56,42 @@ impl Encoding {
         }
     }
 
+    /// Rebuild an `Encoding` from a bare sequence of ids, using the given tokenizer to look up
+    /// the tokens. The original text is not available, so the offsets are all `(0, 0)` and
+    /// are not meaningful, and there is no word information. The attention mask is `0` for
+    /// the padding token of the tokenizer, if padding is enabled, and `1` everywhere else.
+    pub fn from_ids(ids: &[u32], tokenizer: &Tokenizer) -> Result<Self> {
+        let pad_id = tokenizer.get_padding().map(|padding| padding.pad_id);
+        let tokens = ids
+            .iter()
+            .map(|id| {
+                tokenizer
+                    .id_to_token(*id)
+                    .ok_or_else(|| format!("Unknown token id {}", id).into())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let special_tokens_mask = tokens
+            .iter()
+            .map(|token| u32::from(tokenizer.get_added_vocabulary().is_special_token(token)))
+            .collect();
+        let attention_mask = ids
+            .iter()
+            .map(|id| u32::from(Some(*id) != pad_id))
+            .collect();
+
+        Ok(Self {
+            ids: ids.to_vec(),
+            type_ids: vec![0; ids.len()],
+            tokens,
+            words: vec![None; ids.len()],
+            offsets: vec![(0, 0); ids.len()],
+            special_tokens_mask,
+            attention_mask,
+            overflowing: vec![],
+            sequence_ranges: HashMap::new(),
+        })
+    }
+
     pub fn with_capacity(len: usize) -> Self {
         Self {
             ids: Vec::with_capacity(len),
@@ -134,6 +171,19 @@ impl Encoding {
         &mut self.words
     }
 
+    /// Owned copy of `get_word_ids`, named like the Python `Encoding.word_ids`: the index of
+    /// the word each token comes from, as defined by the pre-tokenizer, or `None` for the tokens
+    /// that don't belong to any word (like special tokens). For a pair, the indices restart
+    /// from 0 with the second sequence.
+    pub fn word_ids(&self) -> Vec<Option<u32>> {
+        self.get_word_ids().to_vec()
+    }
+
+    /// Synonym of `word_ids`, like the Python `Encoding.words`
+    pub fn words(&self) -> Vec<Option<u32>> {
+        self.word_ids()
+    }
+
     pub fn get_sequence_ids(&self) -> Vec<Option<usize>> {
         let mut sequences = vec![None; self.len()];
         for seq_id in 0..self.n_sequences() {
@@ -188,6 +238,130 @@ impl Encoding {
         std::mem::take(&mut self.overflowing)
     }
 
+    /// Copy the tokens in `range`, with absolute offsets. The overflowing encodings are
+    /// not kept.
+    fn sliced(&self, range: Range<usize>) -> Encoding {
+        let sequence_ranges = self
+            .sequence_ranges
+            .iter()
+            .filter_map(|(seq_id, seq_range)| {
+                let start = seq_range.start.max(range.start);
+                let end = seq_range.end.min(range.end);
+                (start < end).then(|| (*seq_id, start - range.start..end - range.start))
+            })
+            .collect();
+        Encoding {
+            ids: self.ids[range.clone()].to_vec(),
+            type_ids: self.type_ids[range.clone()].to_vec(),
+            tokens: self.tokens[range.clone()].to_vec(),
+            words: self.words[range.clone()].to_vec(),
+            offsets: self.offsets[range.clone()].to_vec(),
+            special_tokens_mask: self.special_tokens_mask[range.clone()].to_vec(),
+            attention_mask: self.attention_mask[range].to_vec(),
+            overflowing: vec![],
+            sequence_ranges,
+        }
+    }
+
+    /// Extract the tokens from `start` to `end` (excluded) into a new `Encoding`. The offsets
+    /// stay relative to the original input, and the overflowing encodings are not kept.
+    pub fn slice(&self, start: usize, end: usize) -> Result<Encoding> {
+        if start > end || end > self.len() {
+            return Err(format!(
+                "Invalid slice {}..{} of an Encoding of length {}",
+                start,
+                end,
+                self.len()
+            )
+            .into());
+        }
+        Ok(self.sliced(start..end))
+    }
+
+    /// Remove the leading and trailing special tokens (according to the `special_tokens_mask`),
+    /// keeping the ones in the middle of the encoding. The overflowing encodings are not kept.
+    pub fn trim_special_tokens(&self) -> Encoding {
+        let start = self
+            .special_tokens_mask
+            .iter()
+            .position(|mask| *mask == 0)
+            .unwrap_or(self.len());
+        let end = self
+            .special_tokens_mask
+            .iter()
+            .rposition(|mask| *mask == 0)
+            .map_or(start, |last| last + 1);
+        self.sliced(start..end)
+    }
+
+    /// Serialize this `Encoding` as JSON, writing it directly to the given writer
+    pub fn to_json_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    /// Serialize this `Encoding` as a JSON string
+    pub fn to_json_string(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Serialize this `Encoding` as a JSON string that is the same for equal encodings: the
+    /// keys of all the objects are sorted, including the ones of the sequence ranges
+    pub fn to_json_stable(&self) -> Result<String> {
+        fn sorted(value: serde_json::Value) -> serde_json::Value {
+            match value {
+                serde_json::Value::Object(map) => {
+                    let mut entries: Vec<_> = map.into_iter().collect();
+                    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    serde_json::Value::Object(
+                        entries
+                            .into_iter()
+                            .map(|(key, value)| (key, sorted(value)))
+                            .collect(),
+                    )
+                }
+                serde_json::Value::Array(values) => {
+                    serde_json::Value::Array(values.into_iter().map(sorted).collect())
+                }
+                value => value,
+            }
+        }
+        Ok(serde_json::to_string(&sorted(serde_json::to_value(self)?))?)
+    }
+
+    /// Get the usual inputs of a Transformers model: the `input_ids`, `attention_mask` and
+    /// `token_type_ids`
+    pub fn to_feature_dict(&self) -> HashMap<&'static str, Vec<u32>> {
+        self.to_feature_dict_with_fields(&["input_ids", "attention_mask", "token_type_ids"])
+    }
+
+    /// Get the given model inputs, among `input_ids`, `attention_mask`, `token_type_ids` and
+    /// `special_tokens_mask`. The other fields are ignored.
+    pub fn to_feature_dict_with_fields(&self, fields: &[&str]) -> HashMap<&'static str, Vec<u32>> {
+        fields
+            .iter()
+            .filter_map(|field| match *field {
+                "input_ids" => Some(("input_ids", self.ids.clone())),
+                "attention_mask" => Some(("attention_mask", self.attention_mask.clone())),
+                "token_type_ids" => Some(("token_type_ids", self.type_ids.clone())),
+                "special_tokens_mask" => {
+                    Some(("special_tokens_mask", self.special_tokens_mask.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get the ids, attention mask and type ids as `i64`, the usual dtype of NumPy and PyTorch
+    pub fn to_arrays(&self) -> EncodingArrays {
+        let cast = |values: &[u32]| values.iter().map(|v| i64::from(*v)).collect();
+        EncodingArrays {
+            ids: cast(&self.ids),
+            attention_mask: cast(&self.attention_mask),
+            token_type_ids: cast(&self.type_ids),
+        }
+    }
+
     pub(crate) fn process_tokens_with_offsets_mut<F>(&mut self, func: F)
     where
         F: FnMut((usize, (&String, &mut Offsets))),
@@ -210,7 +384,7 @@ impl Encoding {
 
     /// Returns the index of the sequence containing the given token
     pub fn token_to_sequence(&self, token: usize) -> Option<usize> {
-        if token > self.len() {
+        if token >= self.len() {
             None
         } else if self.sequence_ranges.is_empty() {
             Some(0)
@@ -404,6 +578,40 @@ impl Encoding {
         encoding
     }
 
+    /// Concatenate the given encodings one after the other, like `merge` without
+    /// `growing_offsets`: the offsets are kept as they are, so they should all refer to the
+    /// same original text. The overflowing encodings are not kept.
+    ///
+    /// Fails if the fields of one of the encodings don't all have the same length.
+    pub fn concat(encodings: &[Encoding]) -> Result<Encoding> {
+        for (i, encoding) in encodings.iter().enumerate() {
+            let len = encoding.len();
+            let consistent = [
+                encoding.type_ids.len(),
+                encoding.tokens.len(),
+                encoding.words.len(),
+                encoding.offsets.len(),
+                encoding.special_tokens_mask.len(),
+                encoding.attention_mask.len(),
+            ]
+            .iter()
+            .all(|field_len| *field_len == len);
+            if !consistent {
+                return Err(format!(
+                    "The fields of the Encoding {} don't all have its length {}",
+                    i, len
+                )
+                .into());
+            }
+        }
+        Ok(Encoding::merge(
+            encodings
+                .iter()
+                .map(|encoding| encoding.sliced(0..encoding.len())),
+            false,
+        ))
+    }
+
     /// Merge ourself with the given `Encoding`. Happens in place.
     pub fn merge_with(&mut self, pair: Encoding, growing_offsets: bool) {
         // Handle merging the overflowing parts too: Combine them all
@@ -530,6 +738,147 @@ impl Encoding {
             }
         }
     }
+
+    /// Group the consecutive tokens of the same word into word spans, see
+    /// `Tokenizer::align_to_words`
+    pub(crate) fn word_alignments(&self, original_text: &str) -> Vec<WordAlignment> {
+        let mut alignments: Vec<WordAlignment> = vec![];
+        let mut previous = None;
+        for (i, (word, (start, end))) in self
+            .get_word_ids()
+            .iter()
+            .zip(self.get_offsets())
+            .enumerate()
+        {
+            let word = match word {
+                Some(word) => (self.token_to_sequence(i), *word),
+                None => {
+                    previous = None;
+                    continue;
+                }
+            };
+            match alignments.last_mut() {
+                Some(alignment) if previous == Some(word) => {
+                    alignment.word_start = alignment.word_start.min(*start);
+                    alignment.word_end = alignment.word_end.max(*end);
+                    alignment.token_end_idx = i + 1;
+                }
+                _ => alignments.push(WordAlignment {
+                    word_start: *start,
+                    word_end: *end,
+                    token_start_idx: i,
+                    token_end_idx: i + 1,
+                }),
+            }
+            previous = Some(word);
+        }
+        alignments.retain(|alignment| {
+            original_text
+                .get(alignment.word_start..alignment.word_end)
+                .is_some()
+        });
+        alignments
+    }
+
+    /// The substring of `original_text` covered by each token, see
+    /// `Tokenizer::get_token_char_spans`
+    pub(crate) fn token_char_spans<'a>(&self, original_text: &'a str) -> Result<Vec<&'a str>> {
+        self.get_offsets()
+            .iter()
+            .zip(self.get_special_tokens_mask())
+            .map(|((start, end), special)| {
+                if *special == 1 {
+                    return Ok("");
+                }
+                original_text.get(*start..*end).ok_or_else(|| {
+                    format!(
+                        "The offsets ({}, {}) are not a valid range of the text",
+                        start, end
+                    )
+                    .into()
+                })
+            })
+            .collect()
+    }
+
+    /// Project the `(char_start, char_end)` spans on the tokens of this encoding, which must
+    /// have char offsets, see `Tokenizer::encode_and_align`
+    pub(crate) fn char_spans_to_token_spans(
+        &self,
+        spans: &[(usize, usize)],
+    ) -> Vec<(usize, usize)> {
+        let tokens: Vec<(usize, Offsets)> = self
+            .get_offsets()
+            .iter()
+            .zip(self.get_special_tokens_mask())
+            .enumerate()
+            .filter(|(_, ((start, end), special))| **special == 0 && start < end)
+            .map(|(i, (offsets, _))| (i, *offsets))
+            .collect();
+
+        spans
+            .iter()
+            .map(|(start, end)| {
+                let mut covering = tokens
+                    .iter()
+                    .filter(|(_, (token_start, token_end))| token_start < end && token_end > start)
+                    .map(|(i, _)| *i);
+                match covering.next() {
+                    Some(first) => (first, covering.next_back().unwrap_or(first) + 1),
+                    None => {
+                        let next = tokens
+                            .iter()
+                            .find(|(_, (token_start, _))| token_start >= end)
+                            .map_or(self.len(), |(i, _)| *i);
+                        (next, next)
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// The span of a word in the original text, with the range of the tokens it produced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordAlignment {
+    /// Byte offsets of the word in the original text
+    pub word_start: usize,
+    pub word_end: usize,
+    /// Index of the first token of the word, and of the one after its last token
+    pub token_start_idx: usize,
+    pub token_end_idx: usize,
+}
+
+/// The ids, attention mask and token type ids of an `Encoding`, as `i64`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EncodingArrays {
+    pub ids: Vec<i64>,
+    pub attention_mask: Vec<i64>,
+    pub token_type_ids: Vec<i64>,
+}
+
+impl From<&Encoding> for EncodingArrays {
+    fn from(encoding: &Encoding) -> Self {
+        encoding.to_arrays()
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl EncodingArrays {
+    /// Convert into the `(ids, attention_mask, token_type_ids)` arrays
+    pub fn to_ndarray(
+        &self,
+    ) -> (
+        ndarray::Array1<i64>,
+        ndarray::Array1<i64>,
+        ndarray::Array1<i64>,
+    ) {
+        (
+            ndarray::Array1::from(self.ids.clone()),
+            ndarray::Array1::from(self.attention_mask.clone()),
+            ndarray::Array1::from(self.token_type_ids.clone()),
+        )
+    }
 }
 
 impl std::iter::FromIterator<Encoding> for Encoding {
@@ -564,6 +913,10 @@ impl std::iter::FromIterator<(u32, String, (usize, usize), Option<u32>, u32)> fo
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pre_tokenizers::whitespace::{Whitespace, WhitespaceSplit};
+    use crate::processors::bert::BertProcessing;
+    use crate::tokenizer::test_utils::word_level_tokenizer;
+    use crate::{AddedToken, PaddingParams};
     use std::iter::FromIterator;
 
     #[test]
@@ -605,6 +958,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn concat() {
+        let part = |i: u32| Encoding {
+            ids: vec![i, i + 10],
+            type_ids: vec![0, 0],
+            tokens: vec![format!("a{}", i), format!("b{}", i)],
+            words: vec![Some(0), Some(1)],
+            offsets: vec![
+                (2 * i as usize, 2 * i as usize + 1),
+                (2 * i as usize + 1, 2 * i as usize + 2),
+            ],
+            special_tokens_mask: vec![1, 0],
+            attention_mask: vec![1, 1],
+            overflowing: vec![Encoding::default()],
+            ..Default::default()
+        };
+
+        assert_eq!(Encoding::concat(&[]).unwrap(), Encoding::default());
+
+        let one = Encoding::concat(&[part(0)]).unwrap();
+        assert_eq!(
+            one,
+            Encoding {
+                overflowing: vec![],
+                ..part(0)
+            }
+        );
+
+        let two = Encoding::concat(&[part(0), part(1)]).unwrap();
+        assert_eq!(two.get_ids(), &[0, 10, 1, 11]);
+        assert_eq!(two.get_tokens(), &["a0", "b0", "a1", "b1"]);
+        // The offsets are kept as they are
+        assert_eq!(two.get_offsets(), &[(0, 1), (1, 2), (2, 3), (3, 4)]);
+        assert_eq!(two.get_special_tokens_mask(), &[1, 0, 1, 0]);
+        assert!(two.get_overflowing().is_empty());
+
+        let parts: Vec<_> = (0..5).map(part).collect();
+        let five = Encoding::concat(&parts).unwrap();
+        assert_eq!(five.len(), 10);
+        assert_eq!(five.get_ids()[8..], [4, 14]);
+        assert_eq!(five.get_offsets()[9], (9, 10));
+        assert_eq!(five.get_type_ids(), &[0; 10]);
+        assert_eq!(five.get_attention_mask(), &[1; 10]);
+
+        let inconsistent = Encoding {
+            attention_mask: vec![1],
+            ..part(1)
+        };
+        assert!(Encoding::concat(&[part(0), inconsistent]).is_err());
+    }
+
     #[test]
     fn truncate() {
         let mut a = Encoding {
@@ -906,4 +1310,491 @@ mod tests {
         );
         assert_eq!(a.sequence_ranges, HashMap::from([(0, 1..2)]));
     }
+
+    #[test]
+    fn padding_left_shifts_everything() {
+        let mut a = Encoding {
+            ids: vec![1, 2],
+            type_ids: vec![0, 0],
+            tokens: vec![String::from("Hello"), String::from("World")],
+            words: vec![Some(0), Some(1)],
+            offsets: vec![(0, 5), (6, 11)],
+            special_tokens_mask: vec![0, 0],
+            attention_mask: vec![1, 1],
+            sequence_ranges: HashMap::from([(0, 0..2)]),
+            ..Default::default()
+        };
+        a.overflowing = vec![a.clone()];
+        a.pad(4, 99, 2, "[PAD]", PaddingDirection::Left);
+
+        for encoding in [&a, &a.overflowing[0]] {
+            assert_eq!(encoding.ids, vec![99, 99, 1, 2]);
+            assert_eq!(encoding.type_ids, vec![2, 2, 0, 0]);
+            assert_eq!(encoding.tokens, vec!["[PAD]", "[PAD]", "Hello", "World"]);
+            assert_eq!(encoding.words, vec![None, None, Some(0), Some(1)]);
+            assert_eq!(encoding.offsets, vec![(0, 0), (0, 0), (0, 5), (6, 11)]);
+            assert_eq!(encoding.special_tokens_mask, vec![1, 1, 0, 0]);
+            assert_eq!(encoding.attention_mask, vec![0, 0, 1, 1]);
+            assert_eq!(
+                encoding.get_sequence_ids(),
+                vec![None, None, Some(0), Some(0)]
+            );
+        }
+        assert_eq!(a.word_to_tokens(1, 0), Some((3, 4)));
+        assert_eq!(a.token_to_word(2), Some((0, 0)));
+        assert_eq!(a.char_to_token(7, 0), Some(3));
+    }
+
+    #[test]
+    fn padding_left_pair() {
+        let mut a = Encoding {
+            ids: vec![1, 2],
+            type_ids: vec![0, 0],
+            tokens: vec![String::from("Hello"), String::from("World")],
+            words: vec![Some(0), Some(1)],
+            offsets: vec![(0, 5), (6, 11)],
+            special_tokens_mask: vec![0, 0],
+            attention_mask: vec![1, 1],
+            ..Default::default()
+        };
+        let mut b = Encoding {
+            ids: vec![3],
+            type_ids: vec![1],
+            tokens: vec![String::from("Pair")],
+            words: vec![Some(0)],
+            offsets: vec![(0, 4)],
+            special_tokens_mask: vec![0],
+            attention_mask: vec![1],
+            ..Default::default()
+        };
+        a.set_sequence_id(0);
+        b.set_sequence_id(1);
+        a.merge_with(b, false);
+        a.pad(5, 99, 0, "[PAD]", PaddingDirection::Left);
+
+        assert_eq!(a.ids, vec![99, 99, 1, 2, 3]);
+        assert_eq!(a.words, vec![None, None, Some(0), Some(1), Some(0)]);
+        assert_eq!(a.type_ids, vec![0, 0, 0, 0, 1]);
+        assert_eq!(
+            a.get_sequence_ids(),
+            vec![None, None, Some(0), Some(0), Some(1)]
+        );
+        assert_eq!(a.word_to_tokens(0, 1), Some((4, 5)));
+        assert_eq!(a.token_to_word(4), Some((1, 0)));
+        assert_eq!(a.char_to_token(1, 1), Some(4));
+        assert_eq!(a.token_to_sequence(1), None);
+    }
+
+    #[test]
+    fn json_writer() {
+        let encoding = Encoding {
+            ids: vec![1, 2],
+            type_ids: vec![0, 0],
+            tokens: vec![String::from("Hello"), String::from("World")],
+            words: vec![Some(0), Some(1)],
+            offsets: vec![(0, 5), (6, 11)],
+            special_tokens_mask: vec![0, 0],
+            attention_mask: vec![1, 1],
+            ..Default::default()
+        };
+
+        let mut buffer = vec![];
+        encoding.to_json_writer(&mut buffer).unwrap();
+        let as_string = encoding.to_json_string().unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), as_string);
+
+        let decoded: Encoding = serde_json::from_str(&as_string).unwrap();
+        assert_eq!(decoded, encoding);
+    }
+
+    #[test]
+    fn json_stable() {
+        // Each encoding gets its own `HashMap`, with its own iteration order
+        let encoding = || {
+            let ranges: HashMap<usize, Range<usize>> = (0..20).map(|i| (i, i..i + 1)).collect();
+            Encoding::new(
+                (0..20).collect(),
+                vec![0; 20],
+                (0..20).map(|i| i.to_string()).collect(),
+                (0..20).map(Some).collect(),
+                (0..20).map(|i| (i, i + 1)).collect(),
+                vec![0; 20],
+                vec![1; 20],
+                vec![],
+                ranges,
+            )
+        };
+        let (first, second) = (encoding(), encoding());
+        let json = first.to_json_stable().unwrap();
+        assert_eq!(json, second.to_json_stable().unwrap());
+        assert!(json.starts_with(r#"{"attention_mask":[1,1,"#));
+        assert!(json.contains(r#""sequence_ranges":{"0":{"end":1,"start":0},"1":"#));
+
+        let decoded: Encoding = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, first);
+    }
+
+    #[test]
+    fn feature_dict() {
+        use crate::tokenizer::PostProcessor;
+
+        let encoding = |ids: Vec<u32>| Encoding {
+            type_ids: vec![0; ids.len()],
+            tokens: vec![String::new(); ids.len()],
+            words: vec![None; ids.len()],
+            offsets: vec![(0, 0); ids.len()],
+            special_tokens_mask: vec![0; ids.len()],
+            attention_mask: vec![1; ids.len()],
+            ids,
+            ..Default::default()
+        };
+        let processor = BertProcessing::new(("[SEP]".into(), 102), ("[CLS]".into(), 101));
+        let mut encoding = processor
+            .process(encoding(vec![1, 2]), Some(encoding(vec![3])), true)
+            .unwrap();
+        encoding.pad(8, 0, 0, "[PAD]", PaddingDirection::Right);
+
+        let features = encoding.to_feature_dict();
+        assert_eq!(features.len(), 3);
+        assert_eq!(features["input_ids"], [101, 1, 2, 102, 3, 102, 0, 0]);
+        assert_eq!(features["attention_mask"], [1, 1, 1, 1, 1, 1, 0, 0]);
+        assert_eq!(features["token_type_ids"], [0, 0, 0, 0, 1, 1, 0, 0]);
+
+        let features =
+            encoding.to_feature_dict_with_fields(&["input_ids", "special_tokens_mask", "labels"]);
+        let mut keys: Vec<_> = features.keys().copied().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["input_ids", "special_tokens_mask"]);
+        assert_eq!(features["special_tokens_mask"], [1, 0, 0, 1, 0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn word_mappings_with_special_tokens() {
+        use crate::tokenizer::PostProcessor;
+
+        let encoding = |words: Vec<u32>| Encoding {
+            ids: vec![1; words.len()],
+            type_ids: vec![0; words.len()],
+            tokens: vec![String::new(); words.len()],
+            offsets: vec![(0, 0); words.len()],
+            special_tokens_mask: vec![0; words.len()],
+            attention_mask: vec![1; words.len()],
+            words: words.into_iter().map(Some).collect(),
+            ..Default::default()
+        };
+
+        // [CLS] a a b [SEP]
+        let single = encoding(vec![0, 0, 1]);
+        assert_eq!(single.token_to_sequence(3), None);
+        let processor = BertProcessing::new(("[SEP]".into(), 102), ("[CLS]".into(), 101));
+        let single = processor.process(single, None, true).unwrap();
+        assert_eq!(single.word_to_tokens(0, 0), Some((1, 3)));
+        assert_eq!(single.word_to_tokens(1, 0), Some((3, 4)));
+        assert_eq!(single.word_to_tokens(2, 0), None);
+        assert_eq!(single.token_to_word(0), None);
+        assert_eq!(single.token_to_word(2), Some((0, 0)));
+        assert_eq!(single.token_to_word(4), None);
+        assert_eq!(single.token_to_word(5), None);
+
+        // [CLS] a b b [SEP] a a b [SEP], the words of the second sequence start back from 0
+        let pair = processor
+            .process(encoding(vec![0, 1, 1]), Some(encoding(vec![0, 0, 1])), true)
+            .unwrap();
+        assert_eq!(pair.word_to_tokens(1, 0), Some((2, 4)));
+        assert_eq!(pair.word_to_tokens(0, 1), Some((5, 7)));
+        assert_eq!(pair.word_to_tokens(1, 1), Some((7, 8)));
+        assert_eq!(pair.word_to_tokens(2, 1), None);
+        assert_eq!(pair.token_to_word(3), Some((0, 1)));
+        assert_eq!(pair.token_to_word(4), None);
+        assert_eq!(pair.token_to_word(6), Some((1, 0)));
+        assert_eq!(pair.token_to_word(8), None);
+        for token in 0..pair.len() {
+            if let Some((sequence, word)) = pair.token_to_word(token) {
+                let (start, end) = pair.word_to_tokens(word, sequence).unwrap();
+                assert!((start..end).contains(&token));
+            }
+        }
+    }
+
+    #[test]
+    fn trim_special_tokens() {
+        let encoding = Encoding {
+            ids: vec![101, 1, 102, 2, 102, 0],
+            type_ids: vec![0, 0, 0, 1, 1, 0],
+            tokens: ["[CLS]", "hello", "[SEP]", "world", "[SEP]", "[PAD]"]
+                .map(String::from)
+                .to_vec(),
+            words: vec![None, Some(0), None, Some(0), None, None],
+            offsets: vec![(0, 0), (0, 5), (0, 0), (0, 5), (0, 0), (0, 0)],
+            special_tokens_mask: vec![1, 0, 1, 0, 1, 1],
+            attention_mask: vec![1, 1, 1, 1, 1, 0],
+            overflowing: vec![],
+            sequence_ranges: HashMap::from([(0, 1..2), (1, 3..4)]),
+        };
+
+        let trimmed = encoding.trim_special_tokens();
+        // The SEP between both sequences is kept
+        assert_eq!(trimmed.get_tokens(), ["hello", "[SEP]", "world"]);
+        assert_eq!(trimmed.get_special_tokens_mask(), [0, 1, 0]);
+        assert_eq!(trimmed.get_type_ids(), [0, 0, 1]);
+        assert_eq!(trimmed.get_offsets(), [(0, 5), (0, 0), (0, 5)]);
+        assert_eq!(trimmed.get_sequence_ids(), [Some(0), None, Some(1)]);
+        assert_eq!(trimmed.trim_special_tokens(), trimmed);
+
+        let only_special = Encoding {
+            special_tokens_mask: vec![1, 1],
+            ..Encoding::from_tokens(
+                vec![
+                    Token::new(101, "[CLS]".into(), (0, 0)),
+                    Token::new(102, "[SEP]".into(), (0, 0)),
+                ],
+                0,
+            )
+        };
+        assert!(only_special.trim_special_tokens().is_empty());
+    }
+
+    #[test]
+    fn slice() {
+        let encoding = Encoding {
+            ids: vec![101, 1, 2, 102, 3, 102],
+            type_ids: vec![0, 0, 0, 0, 1, 1],
+            tokens: ["[CLS]", "hel", "lo", "[SEP]", "world", "[SEP]"]
+                .map(String::from)
+                .to_vec(),
+            words: vec![None, Some(0), Some(0), None, Some(0), None],
+            offsets: vec![(0, 0), (0, 3), (3, 5), (0, 0), (0, 5), (0, 0)],
+            special_tokens_mask: vec![1, 0, 0, 1, 0, 1],
+            attention_mask: vec![1; 6],
+            overflowing: vec![],
+            sequence_ranges: HashMap::from([(0, 1..3), (1, 4..5)]),
+        };
+        assert_eq!(encoding.slice(0, encoding.len()).unwrap(), encoding);
+
+        let window = encoding.slice(2, 5).unwrap();
+        assert_eq!(window.get_ids(), [2, 102, 3]);
+        assert_eq!(window.get_tokens(), ["lo", "[SEP]", "world"]);
+        assert_eq!(window.get_offsets(), [(3, 5), (0, 0), (0, 5)]);
+        assert_eq!(window.get_word_ids(), [Some(0), None, Some(0)]);
+        assert_eq!(window.get_type_ids(), [0, 0, 1]);
+        assert_eq!(window.get_special_tokens_mask(), [0, 1, 0]);
+        assert_eq!(window.get_sequence_ids(), [Some(0), None, Some(1)]);
+
+        assert!(encoding.slice(3, 3).unwrap().is_empty());
+        assert!(encoding.slice(2, 7).is_err());
+        assert!(encoding.slice(4, 2).is_err());
+    }
+
+    #[test]
+    fn align_to_words() {
+        use crate::models::bpe::BPE;
+
+        // A character level BPE, with words split on whitespaces only
+        let text = "state-of-the-art isn't done.";
+        let mut chars: Vec<char> = text.chars().collect();
+        chars.sort_unstable();
+        chars.dedup();
+        let mut vocab: HashMap<String, u32> = chars
+            .iter()
+            .enumerate()
+            .map(|(id, c)| (c.to_string(), id as u32))
+            .collect();
+        vocab.insert("[CLS]".into(), vocab.len() as u32);
+        vocab.insert("[SEP]".into(), vocab.len() as u32);
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab.clone(), vec![])
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(bpe);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit);
+        tokenizer.with_post_processor(BertProcessing::new(
+            ("[SEP]".into(), vocab["[SEP]"]),
+            ("[CLS]".into(), vocab["[CLS]"]),
+        ));
+
+        let encoding = tokenizer.encode(text, true).unwrap();
+        let alignments = tokenizer.align_to_words(&encoding, text);
+        let alignment = |word_start, word_end, token_start_idx, token_end_idx| WordAlignment {
+            word_start,
+            word_end,
+            token_start_idx,
+            token_end_idx,
+        };
+        // The hyphenated word, the contraction and the punctuation all stay in their word,
+        // and the special tokens are skipped
+        assert_eq!(
+            alignments,
+            vec![
+                alignment(0, 16, 1, 17),
+                alignment(17, 22, 17, 22),
+                alignment(23, 28, 22, 27),
+            ]
+        );
+        let words: Vec<_> = alignments
+            .iter()
+            .map(|a| &text[a.word_start..a.word_end])
+            .collect();
+        assert_eq!(words, ["state-of-the-art", "isn't", "done."]);
+
+        // With a pre-tokenizer that splits on punctuation, each part is a word
+        tokenizer.with_pre_tokenizer(Whitespace {});
+        let encoding = tokenizer.encode("isn't", false).unwrap();
+        let alignments = tokenizer.align_to_words(&encoding, "isn't");
+        assert_eq!(
+            alignments,
+            vec![
+                alignment(0, 3, 0, 3),
+                alignment(3, 4, 3, 4),
+                alignment(4, 5, 4, 5)
+            ]
+        );
+
+        // Spans out of the original text are skipped
+        assert_eq!(tokenizer.align_to_words(&encoding, "isn'").len(), 2);
+    }
+
+    #[test]
+    fn get_token_char_spans() {
+        let mut tokenizer = word_level_tokenizer();
+        tokenizer.with_normalizer(crate::normalizers::Lowercase);
+        tokenizer.add_tokens(&[AddedToken::from("élan", false)]);
+
+        // The byte offsets differ from the char offsets after the multi-byte chars
+        let text = "Élan HELLO ¡world!";
+        let encoding = tokenizer.encode(text, false).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            &["élan", "hello", "[UNK]", "world", "!"]
+        );
+        assert_eq!(
+            tokenizer.get_token_char_spans(&encoding, text).unwrap(),
+            vec!["Élan", "HELLO", "¡", "world", "!"]
+        );
+
+        tokenizer.with_post_processor(BertProcessing::new(
+            ("[SEP]".into(), 5),
+            ("[CLS]".into(), 6),
+        ));
+        let encoding = tokenizer.encode(text, true).unwrap();
+        assert_eq!(
+            tokenizer.get_token_char_spans(&encoding, text).unwrap(),
+            vec!["", "Élan", "HELLO", "¡", "world", "!", ""]
+        );
+
+        assert!(tokenizer.get_token_char_spans(&encoding, "short").is_err());
+    }
+
+    #[test]
+    fn encode_and_align() {
+        use crate::models::wordpiece::WordPiece;
+
+        let vocab: HashMap<String, u32> = [
+            ("[UNK]", 0),
+            ("[CLS]", 1),
+            ("[SEP]", 2),
+            ("new", 3),
+            ("york", 4),
+            ("is", 5),
+            ("héé", 6),
+            ("##ge", 7),
+        ]
+        .iter()
+        .map(|(token, id)| (token.to_string(), *id))
+        .collect();
+        let model = WordPiece::builder().vocab(vocab).build().unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer
+            .with_pre_tokenizer(Whitespace {})
+            .with_post_processor(BertProcessing::new(
+                ("[SEP]".into(), 2),
+                ("[CLS]".into(), 1),
+            ));
+
+        // [CLS] new york is héé ##ge [SEP]
+        let text = "new york is héége";
+        let spans = [(0, 8), (4, 8), (12, 17), (13, 16), (8, 9), (11, 11)];
+        let (encoding, token_spans) = tokenizer.encode_and_align(text, &spans).unwrap();
+        assert_eq!(encoding.get_ids(), &[1, 3, 4, 5, 6, 7, 2]);
+        assert_eq!(
+            token_spans,
+            vec![(1, 3), (2, 3), (4, 6), (4, 6), (3, 3), (4, 4)]
+        );
+
+        assert!(tokenizer.encode_and_align(text, &[(3, 2)]).is_err());
+        assert!(tokenizer.encode_and_align(text, &[(0, 18)]).is_err());
+    }
+
+    #[test]
+    fn encoding_from_ids() {
+        let mut tokenizer = word_level_tokenizer();
+        tokenizer.add_special_tokens(&[AddedToken::from("[PAD]", true)]);
+        tokenizer.with_padding(Some(PaddingParams {
+            pad_id: 4,
+            pad_token: "[PAD]".into(),
+            ..Default::default()
+        }));
+
+        let encoding = Encoding::from_ids(&[1, 2, 0, 4, 4], &tokenizer).unwrap();
+        assert_eq!(encoding.get_ids(), &[1, 2, 0, 4, 4]);
+        assert_eq!(
+            encoding.get_tokens(),
+            &["hello", "world", "[UNK]", "[PAD]", "[PAD]"]
+        );
+        assert_eq!(encoding.get_attention_mask(), &[1, 1, 1, 0, 0]);
+        assert_eq!(encoding.get_special_tokens_mask(), &[0, 0, 0, 1, 1]);
+        assert_eq!(encoding.get_type_ids(), &[0; 5]);
+        assert_eq!(encoding.get_offsets(), &[(0, 0); 5]);
+        assert_eq!(encoding.get_word_ids(), &[None; 5]);
+
+        assert!(Encoding::from_ids(&[1, 42], &tokenizer).is_err());
+    }
+
+    #[test]
+    fn word_ids() {
+        let mut tokenizer = word_level_tokenizer();
+        let encoding = tokenizer.encode("hello world !", false).unwrap();
+        assert_eq!(encoding.word_ids(), vec![Some(0), Some(1), Some(2)]);
+        assert_eq!(encoding.words(), encoding.word_ids());
+        assert_eq!(encoding.word_ids(), encoding.get_word_ids());
+
+        tokenizer.with_post_processor(BertProcessing::new(
+            ("[SEP]".into(), 0),
+            ("[CLS]".into(), 0),
+        ));
+        let encoding = tokenizer.encode(("hello world", "world !"), true).unwrap();
+        assert_eq!(
+            encoding.word_ids(),
+            vec![None, Some(0), Some(1), None, Some(0), Some(1), None]
+        );
+        assert_eq!(encoding.words(), encoding.word_ids());
+    }
+
+    #[test]
+    fn encode_to_numpy_compatible() {
+        let mut tokenizer = word_level_tokenizer();
+        tokenizer.with_post_processor(BertProcessing::new(
+            ("[SEP]".into(), 5),
+            ("[CLS]".into(), 4),
+        ));
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: crate::PaddingStrategy::Fixed(6),
+            pad_id: 6,
+            ..Default::default()
+        }));
+
+        let arrays = tokenizer
+            .encode_to_numpy_compatible(("hello", "world"), true)
+            .unwrap();
+        assert_eq!(arrays.ids, vec![4, 1, 5, 2, 5, 6]);
+        assert_eq!(arrays.attention_mask, vec![1, 1, 1, 1, 1, 0]);
+        assert_eq!(arrays.token_type_ids, vec![0, 0, 0, 1, 1, 0]);
+
+        #[cfg(feature = "ndarray")]
+        {
+            let (ids, attention_mask, token_type_ids) = arrays.to_ndarray();
+            assert_eq!(ids, ndarray::arr1(&[4, 1, 5, 2, 5, 6]));
+            assert_eq!(attention_mask.sum(), 5);
+            assert_eq!(token_type_ids.len(), 6);
+        }
+    }
 }
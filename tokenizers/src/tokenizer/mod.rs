@@ -10,14 +10,16 @@
 //!   ...).
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::{read_to_string, File},
     io::prelude::*,
     io::BufReader,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
@@ -26,11 +28,17 @@ use crate::utils::parallelism::*;
 use crate::utils::progress::{ProgressBar, ProgressStyle};
 
 mod added_vocabulary;
+#[cfg(feature = "benchmarking")]
+mod benchmark;
+mod chunker;
 mod encoding;
 pub mod normalizer;
 pub mod pattern;
 pub mod pre_tokenizer;
+mod report;
 mod serialization;
+#[cfg(test)]
+mod test_utils;
 
 // Re-export wrappers
 pub use crate::decoders::DecoderWrapper;
@@ -41,13 +49,19 @@ pub use crate::processors::PostProcessorWrapper;
 // And some other types
 pub use crate::utils::iter::LinesWithEnding;
 pub use crate::utils::padding::{pad_encodings, PaddingDirection, PaddingParams, PaddingStrategy};
+pub use crate::utils::tiktoken::TiktokenError;
 pub use crate::utils::truncation::{
     truncate_encodings, TruncationDirection, TruncationParams, TruncationStrategy,
 };
 pub use added_vocabulary::*;
+#[cfg(feature = "benchmarking")]
+pub use benchmark::*;
+pub use chunker::*;
 pub use encoding::*;
 pub use normalizer::{NormalizedString, OffsetReferential, SplitDelimiterBehavior};
 pub use pre_tokenizer::*;
+pub use report::*;
+pub use serialization::{DeserializationError, VersionWarning};
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T> = std::result::Result<T, Error>;
@@ -65,6 +79,28 @@ pub trait Normalizer {
 /// the original string.
 pub trait PreTokenizer {
     fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()>;
+
+    /// Same as `pre_tokenize`, with some options overriding the configuration of the
+    /// `PreTokenizer` for this call only. The options that don't apply are ignored.
+    fn pre_tokenize_with_options(
+        &self,
+        pretokenized: &mut PreTokenizedString,
+        _options: &EncodeOptions,
+    ) -> Result<()> {
+        self.pre_tokenize(pretokenized)
+    }
+}
+
+/// Options overriding the configuration of some parts of the pipeline, for a single call
+/// to `Tokenizer::encode_with_options`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// Override the `add_prefix_space` of the `ByteLevel` pre-tokenizer
+    pub add_prefix_space: Option<bool>,
+    /// With `encode_batch_with_options`, start encoding the longest inputs (by number of
+    /// chars) first, for a better load balancing between the threads. The encodings are
+    /// still returned in the order of the inputs.
+    pub sort_for_parallel: bool,
 }
 
 /// Represents a model used during Tokenization (like BPE or Word or Unigram).
@@ -86,6 +122,15 @@ pub trait Model {
     fn save(&self, folder: &Path, prefix: Option<&str>) -> Result<Vec<PathBuf>>;
     /// Get an instance of a Trainer capable of training this Model
     fn get_trainer(&self) -> <Self as Model>::Trainer;
+    /// A stable name for the type of this `Model`, matching the `"type"` field of its
+    /// serialization. Defaults to `"Custom"` for the models defined outside of this crate
+    fn model_type(&self) -> &'static str {
+        "Custom"
+    }
+    /// Find the ID of the unknown token, if this `Model` has one
+    fn get_unk_id(&self) -> Option<u32> {
+        None
+    }
 }
 
 /// A `PostProcessor` has the responsibility to post process an encoded output of the `Tokenizer`.
@@ -157,6 +202,15 @@ pub trait Decoder {
         Ok(results.join(""))
     }
     fn decode_chain(&self, tokens: Vec<String>) -> Result<Vec<String>>;
+    /// Convert the decoder provided by `Trainer::decoder` into this type of decoder. Training a
+    /// `Tokenizer` without a decoder registers the result, if any. By default, a decoder can't
+    /// be built this way.
+    fn from_trainer_decoder(_decoder: DecoderWrapper) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
 /// A `Trainer` has the responsibility to train a model. We feed it with lines/sentences
@@ -175,6 +229,21 @@ pub trait Trainer {
         I: Iterator<Item = S> + Send,
         S: AsRef<str> + Send,
         F: Fn(&str) -> Result<Vec<String>> + Sync;
+    /// Same as `feed`, except that each sequence comes with a weight by which its word counts
+    /// get multiplied. By default, trainers don't support weights and return an error.
+    fn feed_weighted<I, S, F>(&mut self, _iterator: I, _process: F) -> Result<()>
+    where
+        I: Iterator<Item = (S, f64)> + Send,
+        S: AsRef<str> + Send,
+        F: Fn(&str) -> Result<Vec<String>> + Sync,
+    {
+        Err("This trainer does not support weighted sequences".into())
+    }
+    /// The decoder matching the trained model, if any. It is registered on the `Tokenizer`
+    /// being trained when it doesn't have a decoder yet.
+    fn decoder(&self) -> Option<DecoderWrapper> {
+        None
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -252,12 +321,33 @@ impl<'s> From<&'s [Cow<'s, str>]> for InputSequence<'s> {
     }
 }
 
+impl InputSequence<'_> {
+    /// The number of chars of the sequence, used to estimate the cost of its encoding
+    fn char_len(&self) -> usize {
+        match self {
+            Self::Raw(seq) => seq.chars().count(),
+            Self::PreTokenized(seq) => seq.iter().map(|s| s.chars().count()).sum(),
+            Self::PreTokenizedOwned(seq) => seq.iter().map(|s| s.chars().count()).sum(),
+            Self::PreTokenizedCow(seq) => seq.iter().map(|s| s.chars().count()).sum(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum EncodeInput<'s> {
     Single(InputSequence<'s>),
     Dual(InputSequence<'s>, InputSequence<'s>),
 }
 
+impl EncodeInput<'_> {
+    fn char_len(&self) -> usize {
+        match self {
+            Self::Single(seq) => seq.char_len(),
+            Self::Dual(seq, pair) => seq.char_len() + pair.char_len(),
+        }
+    }
+}
+
 impl<'s, I: Into<InputSequence<'s>>> From<I> for EncodeInput<'s> {
     fn from(input: I) -> Self {
         Self::Single(input.into())
@@ -346,6 +436,8 @@ where
             added_vocabulary: self.added_vocabulary,
             truncation: self.truncation,
             padding: self.padding,
+            fertility_estimate: None,
+            fallback_tokenizer: None,
         })
     }
 
@@ -422,6 +514,40 @@ impl Tokenizer {
         Self(TokenizerImpl::new(model.into()))
     }
 
+    /// Construct a lightweight Tokenizer to test code that uses a tokenizer, without loading
+    /// a real model file. It is backed by a `WordLevel` model whose vocabulary has `[UNK]`
+    /// with id `0` and the dummy tokens `token_1` up to `token_{vocab_size - 1}`, each having
+    /// the id in its name, with a `Whitespace` pre-tokenizer and no normalizer nor
+    /// post-processor. Any other word gets encoded as `[UNK]`. The vocabulary always contains
+    /// `[UNK]`, even when `vocab_size` is `0`.
+    ///
+    /// ```ignore
+    /// #[cfg(test)]
+    /// mod tests {
+    ///     use tokenizers::Tokenizer;
+    ///
+    ///     #[test]
+    ///     fn counts_tokens() {
+    ///         let tokenizer = Tokenizer::mock(10);
+    ///         let encoding = tokenizer.encode("token_3 token_7 other", false).unwrap();
+    ///         assert_eq!(encoding.get_ids(), &[3, 7, 0]);
+    ///     }
+    /// }
+    /// ```
+    pub fn mock(vocab_size: usize) -> Self {
+        let vocab = std::iter::once(("[UNK]".to_string(), 0))
+            .chain((1..vocab_size).map(|id| (format!("token_{id}"), id as u32)))
+            .collect();
+        let model = crate::models::wordlevel::WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .expect("A WordLevel without files always builds");
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(crate::pre_tokenizers::whitespace::Whitespace {});
+        tokenizer
+    }
+
     /// Unwrap the TokenizerImpl.
     pub fn into_inner(
         self,
@@ -436,13 +562,66 @@ impl Tokenizer {
     }
     pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Self> {
         let content = read_to_string(file)?;
-        let tokenizer = serde_json::from_str(&content)?;
-        Ok(tokenizer)
+        content.parse()
     }
     pub fn from_bytes<P: AsRef<[u8]>>(bytes: P) -> Result<Self> {
-        let tokenizer = serde_json::from_slice(bytes.as_ref())?;
+        let bytes = bytes.as_ref();
+        serialization::check_version(bytes, serde_json::from_slice(bytes))
+    }
+    /// Serialize the tokenizer as a single line of URL-safe base64 (without padding),
+    /// handy to pass it around in an environment variable or an HTTP header.
+    pub fn serialize_to_base64(&self) -> Result<String> {
+        let serialized = self.to_string(false)?;
+        Ok(URL_SAFE_NO_PAD.encode(serialized))
+    }
+    /// Instantiate a new Tokenizer from the output of `serialize_to_base64`
+    pub fn from_base64(s: &str) -> Result<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(s.trim())?;
+        Tokenizer::from_bytes(bytes)
+    }
+    /// Instantiate a new Tokenizer from a directory containing a `tokenizer.json` file.
+    ///
+    /// The added tokens of the optional `tokenizer_config.json`, `special_tokens_map.json`
+    /// and `added_tokens.json` files get merged in afterwards. `tokenizer.json` always wins:
+    /// a token is skipped when its content is already in the vocabulary, or when the id it
+    /// expects is already used by another token. The other tokens are added by increasing
+    /// expected id, so they get the next available ids.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json"))?;
+
+        let mut tokens = crate::utils::from_dir::sidecar_tokens(dir)?;
+        tokens.sort_by_key(|(id, _)| id.unwrap_or(u32::MAX));
+        for (id, token) in tokens {
+            let conflicting_id = id.is_some_and(|id| tokenizer.id_to_token(id).is_some());
+            if conflicting_id || tokenizer.token_to_id(&token.content).is_some() {
+                continue;
+            }
+            if token.special {
+                tokenizer.add_special_tokens(&[token]);
+            } else {
+                tokenizer.add_tokens(&[token]);
+            }
+        }
         Ok(tokenizer)
     }
+
+    /// Instantiate a new Tokenizer from the bytes of a SentencePiece `.model` file. `model_type`
+    /// is either `"bpe"` or `"unigram"`, and must match the type of the model. The normalizer,
+    /// pre-tokenizer and decoder are set up to reproduce SentencePiece, and its control and
+    /// unknown pieces are added as special tokens.
+    pub fn from_sentencepiece_model(model_bytes: &[u8], model_type: &str) -> Result<Self> {
+        crate::utils::sentencepiece::from_model(model_bytes, model_type)
+    }
+
+    /// Instantiate the Tokenizer of a tiktoken encoding, one of `"cl100k_base"`, `"p50k_base"`
+    /// or `"r50k_base"`, from the content of its `.tiktoken` rank file, made of
+    /// `{base64 token} {rank}` lines. The ranks become the ids of a byte-level BPE, and the
+    /// special tokens of the encoding are added with their ids.
+    pub fn from_tiktoken_ranks(encoding_name: &str, ranks: &[u8]) -> Result<Self> {
+        crate::utils::tiktoken::from_ranks(encoding_name, ranks)
+    }
+
     #[cfg(feature = "http")]
     pub fn from_pretrained<S: AsRef<str>>(
         identifier: S,
@@ -451,13 +630,512 @@ impl Tokenizer {
         let tokenizer_file = crate::utils::from_pretrained::from_pretrained(identifier, params)?;
         Tokenizer::from_file(tokenizer_file)
     }
+
+    /// Add up to `token_limit` of the most frequent tokens of `other` that we don't know yet,
+    /// as regular added tokens. The tokens of `other` are ranked by merge order for BPE, by
+    /// score for Unigram and by id otherwise. Returns the number of tokens added.
+    pub fn add_tokens_from(&mut self, other: &Tokenizer, token_limit: usize) -> usize {
+        let vocab = self.get_vocab(true);
+        let tokens: Vec<_> = other
+            .get_model()
+            .tokens_by_rank()
+            .into_iter()
+            .filter(|token| !vocab.contains_key(token))
+            .take(token_limit)
+            .map(|token| AddedToken::from(token, false))
+            .collect();
+        self.add_tokens(&tokens)
+    }
+
+    /// Apply the settings of a `tokenizer_config.json` on top of this tokenizer:
+    ///  - `model_max_length` sets the `max_length` of the truncation, enabling it if needed,
+    ///  - `truncation_side` sets the direction of the truncation, when it is enabled,
+    ///  - `padding_side` sets the direction of the padding, enabling it with the default
+    ///    parameters if needed,
+    ///  - `pad_token` sets the token of the padding, when it is enabled and the token exists,
+    ///  - the special tokens (`bos_token`, `eos_token`, `unk_token`, `sep_token`, `pad_token`,
+    ///    `cls_token`, `mask_token` and `additional_special_tokens`) get added as special
+    ///    tokens.
+    ///
+    /// All of these keys are optional, and the other ones are ignored. A `model_max_length`
+    /// that does not fit a `usize`, like the `1e30` used by `transformers` for the models
+    /// without limit, is ignored too.
+    pub fn load_config_from_json(&mut self, config_json: &str) -> Result<()> {
+        use std::convert::TryFrom;
+
+        let config: serde_json::Value = serde_json::from_str(config_json)?;
+        if !config.is_object() {
+            return Err("The tokenizer config should be a JSON object".into());
+        }
+
+        let special_tokens = crate::utils::from_dir::config_special_tokens(&config);
+        self.add_special_tokens(&special_tokens);
+
+        let max_length = config
+            .get("model_max_length")
+            .and_then(|value| value.as_u64())
+            .and_then(|value| usize::try_from(value).ok());
+        if let Some(max_length) = max_length {
+            let truncation = TruncationParams {
+                max_length,
+                ..self.get_truncation().cloned().unwrap_or_default()
+            };
+            self.with_truncation(Some(truncation))?;
+        }
+        if let Some(side) = config.get("truncation_side").and_then(|v| v.as_str()) {
+            let direction = match side {
+                "left" => TruncationDirection::Left,
+                "right" => TruncationDirection::Right,
+                _ => return Err(format!("Invalid truncation_side: {}", side).into()),
+            };
+            if let Some(truncation) = self.get_truncation_mut() {
+                truncation.direction = direction;
+            }
+        }
+
+        if let Some(side) = config.get("padding_side").and_then(|v| v.as_str()) {
+            let direction = match side {
+                "left" => PaddingDirection::Left,
+                "right" => PaddingDirection::Right,
+                _ => return Err(format!("Invalid padding_side: {}", side).into()),
+            };
+            let padding = PaddingParams {
+                direction,
+                ..self.get_padding().cloned().unwrap_or_default()
+            };
+            self.with_padding(Some(padding));
+        }
+        let pad_token = config
+            .get("pad_token")
+            .and_then(|value| crate::utils::from_dir::parse_token(value, true));
+        if let Some(pad_token) = pad_token {
+            let pad_id = self.token_to_id(&pad_token.content);
+            if let (Some(padding), Some(pad_id)) = (self.0.padding.as_mut(), pad_id) {
+                padding.pad_token = pad_token.content;
+                padding.pad_id = pad_id;
+            }
+        }
+        Ok(())
+    }
+
+    /// Build a copy of this tokenizer without its normalizer. The added tokens are matched
+    /// against the input as is from then on.
+    pub fn clone_without_normalizer(&self) -> Tokenizer {
+        let mut tokenizer = self.clone();
+        let inner = &mut tokenizer.0;
+        inner.normalizer = None;
+        inner
+            .added_vocabulary
+            .refresh_added_tokens(&inner.model, None::<&NormalizerWrapper>);
+        tokenizer
+    }
+
+    /// Build a copy of this tokenizer without its pre-tokenizer
+    pub fn clone_without_pre_tokenizer(&self) -> Tokenizer {
+        let mut tokenizer = self.clone();
+        tokenizer.pre_tokenizer = None;
+        tokenizer
+    }
+
+    /// Build a copy of this tokenizer without its post-processor
+    pub fn clone_without_post_processor(&self) -> Tokenizer {
+        let mut tokenizer = self.clone();
+        tokenizer.post_processor = None;
+        tokenizer
+    }
+
+    /// Build a copy of this tokenizer without its decoder
+    pub fn clone_without_decoder(&self) -> Tokenizer {
+        let mut tokenizer = self.clone();
+        tokenizer.decoder = None;
+        tokenizer
+    }
+
+    /// Whether the normalizer strips the accents: it is or contains a `StripAccents`, or it is
+    /// a `BertNormalizer` set to strip them
+    pub fn get_strip_accents(&self) -> bool {
+        fn strips_accents(normalizer: &NormalizerWrapper) -> bool {
+            match normalizer {
+                NormalizerWrapper::StripAccents(_) => true,
+                NormalizerWrapper::BertNormalizer(bert) => {
+                    bert.strip_accents.unwrap_or(bert.lowercase)
+                }
+                NormalizerWrapper::Sequence(sequence) => {
+                    sequence.get_normalizers().iter().any(strips_accents)
+                }
+                _ => false,
+            }
+        }
+        self.get_normalizer().is_some_and(strips_accents)
+    }
+
+    /// Enable or disable the stripping of the accents, like `bert-base-uncased` does. A
+    /// `BertNormalizer` gets its `strip_accents` option set. Otherwise, enabling it appends a
+    /// `StripAccents` normalizer after the existing ones, and disabling it removes all the
+    /// `StripAccents` normalizers.
+    ///
+    /// `StripAccents` only removes the combining marks, so an accented char must be decomposed
+    /// by a `NFD` (or `NFKD`) normalizer before it, to get stripped. `é` stays as is otherwise,
+    /// while `e` followed by the combining acute accent becomes `e`.
+    pub fn set_strip_accents(&mut self, enabled: bool) -> &mut Self {
+        use crate::normalizers::{Sequence, StripAccents};
+
+        fn without_strip_accents(normalizer: NormalizerWrapper) -> Option<NormalizerWrapper> {
+            match normalizer {
+                NormalizerWrapper::StripAccents(_) => None,
+                NormalizerWrapper::BertNormalizer(mut bert) => {
+                    bert.strip_accents = Some(false);
+                    Some(bert.into())
+                }
+                NormalizerWrapper::Sequence(sequence) => {
+                    let normalizers = sequence
+                        .get_normalizers()
+                        .iter()
+                        .cloned()
+                        .filter_map(without_strip_accents)
+                        .collect();
+                    Some(Sequence::new(normalizers).into())
+                }
+                normalizer => Some(normalizer),
+            }
+        }
+
+        if self.get_strip_accents() == enabled {
+            return self;
+        }
+        let inner = &mut self.0;
+        let normalizer = inner.normalizer.take();
+        inner.normalizer = match (normalizer, enabled) {
+            (normalizer, false) => normalizer.and_then(without_strip_accents),
+            (Some(NormalizerWrapper::BertNormalizer(mut bert)), true) => {
+                bert.strip_accents = Some(true);
+                Some(bert.into())
+            }
+            (Some(NormalizerWrapper::Sequence(sequence)), true) => {
+                let mut normalizers = sequence.get_normalizers().to_vec();
+                normalizers.push(StripAccents.into());
+                Some(Sequence::new(normalizers).into())
+            }
+            (Some(normalizer), true) => {
+                Some(Sequence::new(vec![normalizer, StripAccents.into()]).into())
+            }
+            (None, true) => Some(StripAccents.into()),
+        };
+        inner
+            .added_vocabulary
+            .refresh_added_tokens(&inner.model, inner.normalizer.as_ref());
+        self
+    }
+
+    /// Build a copy of this tokenizer keeping only the tokens of its model that appear at least
+    /// `min_frequency` times when encoding the lines of `corpus` (without truncation, padding,
+    /// or special tokens), with new contiguous ids. The unknown token and the added tokens are
+    /// always kept, and the ids of the special tokens of the post-processor are updated. For
+    /// BPE, the tokens needed to build the kept ones by merging are kept too, along with the
+    /// merges, so that the corpus still gets the same tokens.
+    pub fn strip_vocabulary_to_frequency(
+        &self,
+        corpus: &str,
+        min_frequency: usize,
+    ) -> Result<Tokenizer> {
+        let counts = self.token_id_frequency_map(corpus)?;
+
+        let mut added_tokens: Vec<_> = self.get_added_tokens_decoder().into_iter().collect();
+        added_tokens.sort_by_key(|(id, _)| *id);
+        let mut keep: HashSet<u32> = counts
+            .into_iter()
+            .filter(|(_, count)| *count >= min_frequency)
+            .map(|(id, _)| id)
+            .collect();
+        keep.extend(added_tokens.iter().map(|(id, _)| *id));
+        let model = self.get_model().retain_tokens(&keep)?;
+
+        let mut pruned = self.clone();
+        let fallback = pruned.0.fallback_tokenizer.take();
+        pruned.added_vocabulary = AddedVocabulary::new();
+        pruned.set_encode_special_tokens(self.get_encode_special_tokens());
+        pruned.with_model(model);
+        for (_, token) in added_tokens {
+            if token.special {
+                pruned.add_special_tokens(&[token]);
+            } else {
+                pruned.add_tokens(&[token]);
+            }
+        }
+        pruned.with_fallback_tokenizer(fallback);
+        let vocab = pruned.get_vocab(true);
+        if let Some(processor) = pruned.post_processor.as_mut() {
+            processor.update_ids(&|token| vocab.get(token).copied());
+        }
+        Ok(pruned)
+    }
+
+    /// Give new ids to the added tokens that are not part of the vocabulary of the model, so
+    /// that they follow it without any gap, for example after `reset_special_tokens`. The
+    /// added tokens keep their order, and the ids of the special tokens of the post-processor
+    /// are updated. The ids of the fallback tokenizer, if any, come after them.
+    pub fn rebuild_vocabulary(&mut self) {
+        let mut added_tokens: Vec<_> = self.get_added_tokens_decoder().into_iter().collect();
+        added_tokens.sort_by_key(|(id, _)| *id);
+
+        let inner = &mut self.0;
+        let fallback = inner.fallback_tokenizer.take();
+        let encode_special_tokens = inner.added_vocabulary.get_encode_special_tokens();
+        inner.added_vocabulary = AddedVocabulary::new();
+        inner
+            .added_vocabulary
+            .set_encode_special_tokens(encode_special_tokens);
+        for (_, token) in added_tokens {
+            inner
+                .added_vocabulary
+                .add_tokens(&[token], &inner.model, inner.normalizer.as_ref());
+        }
+        inner.with_fallback_tokenizer(fallback);
+
+        let vocab = self.get_vocab(true);
+        if let Some(processor) = self.0.post_processor.as_mut() {
+            processor.update_ids(&|token| vocab.get(token).copied());
+        }
+    }
+
+    /// Add a post-processor, applied after the current one if any: the post-processors get
+    /// chained in a `Sequence`.
+    pub fn add_post_processor(&mut self, processor: PostProcessorWrapper) -> &mut Self {
+        self.0.post_processor = Some(match self.0.post_processor.take() {
+            None => processor,
+            Some(PostProcessorWrapper::Sequence(mut sequence)) => {
+                sequence.push(processor);
+                PostProcessorWrapper::Sequence(sequence)
+            }
+            Some(current) => {
+                crate::processors::sequence::Sequence::new(vec![current, processor]).into()
+            }
+        });
+        self
+    }
+
+    /// Add each of the given post-processors, in order, with `add_post_processor`
+    pub fn merge_post_processors(&mut self, processors: Vec<PostProcessorWrapper>) -> Result<()> {
+        if processors.is_empty() {
+            return Err("No post-processor to merge".into());
+        }
+        for processor in processors {
+            self.add_post_processor(processor);
+        }
+        Ok(())
+    }
+
+    /// Same as `add_tokens_from`, with a `tokenizer.json` file hosted on the Hugging Face Hub.
+    #[cfg(feature = "http")]
+    pub fn add_from_pretrained<S: AsRef<str>>(
+        &mut self,
+        identifier: S,
+        token_limit: usize,
+    ) -> Result<usize> {
+        let other = Tokenizer::from_pretrained(identifier, None)?;
+        Ok(self.add_tokens_from(&other, token_limit))
+    }
+}
+
+impl Tokenizer {
+    /// Encode the given sequence like `encode(text, false)`, also returning the trace of the
+    /// BPE merges applied, in order, as `(left_token_pos, right_token_pos, merge_rank)`.
+    /// The positions refer to the initial symbols given to the model, for all the words of
+    /// the sequence one after the other, the merged symbol taking the position of its left
+    /// part. The added tokens are not part of these symbols. Only BPE models are supported.
+    pub fn encode_with_merges(
+        &self,
+        text: &str,
+    ) -> Result<(Encoding, crate::models::bpe::MergeTrace)> {
+        let bpe = match &self.model {
+            ModelWrapper::BPE(bpe) => bpe,
+            _ => return Err("Merge traces are only supported by BPE models".into()),
+        };
+
+        let normalized = self
+            .added_vocabulary
+            .extract_and_normalize(self.normalizer.as_ref(), text);
+        let mut pre_tokenized = self.do_pre_tokenize(normalized)?;
+        let trace = std::cell::RefCell::new(vec![]);
+        let symbols = std::cell::Cell::new(0);
+        pre_tokenized.tokenize(|normalized| {
+            let (tokens, word_trace) = bpe.tokenize_with_merges(normalized.get())?;
+            // Each merge removes one of the initial symbols
+            let offset = symbols.replace(symbols.get() + tokens.len() + word_trace.len());
+            trace.borrow_mut().extend(
+                word_trace
+                    .into_iter()
+                    .map(|(left, right, rank)| (left + offset, right + offset, rank)),
+            );
+            Ok(tokens)
+        })?;
+        let encoding = pre_tokenized.into_encoding(None, 0, OffsetType::Byte)?;
+        let encoding = self.post_process(encoding, None, false)?;
+
+        Ok((encoding, trace.into_inner()))
+    }
+
+    /// Compute a hash of the complete configuration of this tokenizer, using XXH64 over its
+    /// JSON serialization. It is stable across runs and platforms, but any change to the
+    /// serialization format in a new version of this crate gives a different fingerprint.
+    pub fn fingerprint(&self) -> u64 {
+        let json = serde_json::to_vec(self).expect("Tokenizer should always serialize");
+        xxhash_rust::xxh64::xxh64(&json, 0)
+    }
+
+    /// Whether this tokenizer has the same configuration as `other`
+    pub fn fingerprint_matches(&self, other: &Tokenizer) -> bool {
+        self.fingerprint() == other.fingerprint()
+    }
+}
+
+impl Tokenizer {
+    /// Get the probability that a Unigram model gives to the unknown token, when it stands for
+    /// a character that none of its pieces covers. This is `None` for the other models, where
+    /// the unknown token has no probability, or when the Unigram model has no unknown token.
+    ///
+    /// The characters concerned are the ones left out of the alphabet during training. Our
+    /// `UnigramTrainer` keeps every character of the corpus, but SentencePiece models trained
+    /// with a `character_coverage` below `1.0` drop the rarest characters, which then all get
+    /// this probability.
+    pub fn get_unk_probability(&self) -> Option<f64> {
+        self.get_model().unk_probability()
+    }
+
+    /// Get the rank of the BPE merge that produced the given token, as a proxy of its frequency
+    /// in the training corpus: the trainer always merges the most frequent pair first, so a
+    /// lower rank means a more frequent token. The tokens that are not produced by any merge,
+    /// like the initial characters, get `0`. This is `None` for the ids that are not in the
+    /// vocabulary of the model, and for the other models.
+    pub fn get_token_frequency_ranking(&self, token_id: u32) -> Option<usize> {
+        self.get_model().merge_rank(token_id)
+    }
+
+    /// Get the merges of a BPE model, by increasing rank, with the tokens as they appear in the
+    /// vocabulary. This is `None` for the other models.
+    pub fn get_merges_list(&self) -> Option<Vec<(String, String)>> {
+        self.get_model()
+            .merges_by_rank()
+            .map(|merges| merges.into_iter().map(|(pair, _)| pair).collect())
+    }
+
+    /// Get the rank of each merge of a BPE model, like `get_merges_list`
+    pub fn get_merges_map(&self) -> Option<HashMap<(String, String), u32>> {
+        self.get_model()
+            .merges_by_rank()
+            .map(|merges| merges.into_iter().collect())
+    }
+
+    /// The longest token of the vocabulary (with the added tokens), as `(id, token,
+    /// byte_length)`. Ties are broken by the lowest id, and `None` means the vocabulary is empty.
+    pub fn longest_token_in_vocab(&self) -> Option<(u32, String, usize)> {
+        self.vocab_lengths()
+            .min_by_key(|(id, _, len)| (std::cmp::Reverse(*len), *id))
+    }
+
+    /// The shortest token of the vocabulary (with the added tokens), as `(id, token,
+    /// byte_length)`. Ties are broken by the lowest id, and `None` means the vocabulary is empty.
+    pub fn shortest_token_in_vocab(&self) -> Option<(u32, String, usize)> {
+        self.vocab_lengths().min_by_key(|(id, _, len)| (*len, *id))
+    }
+
+    /// The mean length in bytes of the tokens of the vocabulary (with the added tokens)
+    pub fn average_token_byte_length(&self) -> f64 {
+        let (count, total) = self
+            .vocab_lengths()
+            .fold((0, 0), |(count, total), (_, _, len)| {
+                (count + 1, total + len)
+            });
+        if count == 0 {
+            0.0
+        } else {
+            total as f64 / count as f64
+        }
+    }
+
+    fn vocab_lengths(&self) -> impl Iterator<Item = (u32, String, usize)> {
+        self.get_vocab(true).into_iter().map(|(token, id)| {
+            let len = token.len();
+            (id, token, len)
+        })
+    }
+
+    /// Group the consecutive tokens of `encoding` that belong to the same word, as given by
+    /// the pre-tokenizer, into word spans. The `encoding` must come from `encode` on
+    /// `original_text` (with byte offsets), and the spans that are not valid ranges of
+    /// `original_text` are skipped. The tokens without a word, like special tokens, are
+    /// not part of any span.
+    pub fn align_to_words(&self, encoding: &Encoding, original_text: &str) -> Vec<WordAlignment> {
+        encoding.word_alignments(original_text)
+    }
+
+    /// Get the substring of `original_text` covered by each token of `encoding`, rather than
+    /// the token itself, to show them highlighted in the text. The `encoding` must come from
+    /// `encode` on `original_text` alone (with byte offsets). The special tokens, including
+    /// the padding ones, get an empty string. Fails if the offsets of a token are not a valid
+    /// range of `original_text`.
+    pub fn get_token_char_spans<'a>(
+        &self,
+        encoding: &Encoding,
+        original_text: &'a str,
+    ) -> Result<Vec<&'a str>> {
+        encoding.token_char_spans(original_text)
+    }
+
+    /// Encode `text` with the special tokens and char offsets, like `encode_char_offsets`, and
+    /// project each `(char_start, char_end)` span of `spans` on the tokens: the span of tokens
+    /// `(token_start, token_end)` is the smallest one covering all the chars of the span. It
+    /// expands to the whole tokens when the span only covers a part of them. A span without
+    /// any token, like one covering only whitespaces, gives the empty span of the next token.
+    pub fn encode_and_align(
+        &self,
+        text: &str,
+        spans: &[(usize, usize)],
+    ) -> Result<(Encoding, Vec<(usize, usize)>)> {
+        let n_chars = text.chars().count();
+        if let Some((start, end)) = spans
+            .iter()
+            .find(|(start, end)| start > end || *end > n_chars)
+        {
+            return Err(format!(
+                "Invalid span ({}, {}) for a text of {} chars",
+                start, end, n_chars
+            )
+            .into());
+        }
+
+        let encoding = self.encode_char_offsets(text, true)?;
+        let token_spans = encoding.char_spans_to_token_spans(spans);
+        Ok((encoding, token_spans))
+    }
 }
 
 impl std::str::FromStr for Tokenizer {
     type Err = Box<dyn std::error::Error + Send + Sync>;
 
+    /// Deserialize a tokenizer, loading the ones of another version on a best-effort basis
+    /// with a `VersionWarning`, or failing with a `DeserializationError::VersionMismatch`
     fn from_str(s: &str) -> Result<Self> {
-        Ok(serde_json::from_str(s)?)
+        serialization::check_version(s.as_bytes(), serde_json::from_str(s))
+    }
+}
+
+impl std::convert::TryFrom<serde_json::Value> for Tokenizer {
+    type Error = Error;
+
+    /// Deserialize a tokenizer, checking its version like `Tokenizer::from_str`
+    fn try_from(value: serde_json::Value) -> Result<Self> {
+        let result = Tokenizer::deserialize(&value);
+        serialization::check_value_version(&value, result)
+    }
+}
+
+impl std::convert::TryFrom<&serde_json::Value> for Tokenizer {
+    type Error = Error;
+
+    /// Deserialize a tokenizer, checking its version like `Tokenizer::from_str`
+    fn try_from(value: &serde_json::Value) -> Result<Self> {
+        serialization::check_value_version(value, Tokenizer::deserialize(value))
     }
 }
 
@@ -479,6 +1157,8 @@ where
             added_vocabulary: t.added_vocabulary,
             padding: t.padding,
             truncation: t.truncation,
+            fertility_estimate: t.fertility_estimate,
+            fallback_tokenizer: t.fallback_tokenizer,
         })
     }
 }
@@ -507,6 +1187,69 @@ impl DerefMut for Tokenizer {
 #[error("{0}")]
 pub struct TruncationParamError(String);
 
+/// Fertility used to estimate the number of tokens before any calibration. Subword
+/// tokenizers like BPE usually produce around 0.25 token per character on English text,
+/// while character-level tokenizers are close to 1.0.
+pub const DEFAULT_FERTILITY_ESTIMATE: f64 = 0.25;
+
+/// Number of tokens of the previous context decoded by `encode_with_context`
+pub const DEFAULT_CONTEXT_WINDOW: usize = 8;
+
+/// A second `Tokenizer` used for the pre-tokens that the main one can only encode as unknown
+/// tokens, like a script it doesn't support. The `switch_token` is prepended to the tokens of
+/// each rerouted pre-token, and the ids of the fallback are shifted after the ones of the main
+/// tokenizer (added tokens included), to keep them distinct. This shift is fixed when setting
+/// the fallback, and the tokens added later get ids after the ones of the fallback.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FallbackTokenizer {
+    pub tokenizer: Arc<Tokenizer>,
+    pub switch_token: String,
+    id_offset: u32,
+}
+
+impl FallbackTokenizer {
+    pub fn new(tokenizer: Arc<Tokenizer>, switch_token: impl Into<String>) -> Self {
+        Self {
+            tokenizer,
+            switch_token: switch_token.into(),
+            id_offset: 0,
+        }
+    }
+
+    /// The shift applied to the ids of the fallback tokenizer
+    pub fn get_id_offset(&self) -> u32 {
+        self.id_offset
+    }
+
+    /// The end of the ids of the fallback tokenizer, once shifted
+    fn ids_end(&self) -> u32 {
+        self.id_offset + self.tokenizer.get_vocab_size(true) as u32
+    }
+
+    /// Encode the given pre-token with the fallback pipeline, without any post-processing
+    fn tokenize(&self, sequence: &str, switch_id: u32, id_offset: u32) -> Result<Vec<Token>> {
+        let encoding = self.tokenizer.encode_single_sequence(
+            InputSequence::from(sequence),
+            0,
+            OffsetType::Byte,
+            &EncodeOptions::default(),
+        )?;
+        let switch = Token::new(switch_id, self.switch_token.clone(), (0, 0));
+        Ok(std::iter::once(switch)
+            .chain(
+                encoding
+                    .get_ids()
+                    .iter()
+                    .zip(encoding.get_tokens())
+                    .zip(encoding.get_offsets())
+                    .map(|((id, token), offsets)| {
+                        Token::new(id + id_offset, token.clone(), *offsets)
+                    }),
+            )
+            .collect())
+    }
+}
+
 /// A `Tokenizer` is capable of encoding/decoding any text.
 #[derive(Clone, Debug)]
 pub struct TokenizerImpl<M, N, PT, PP, D> {
@@ -523,6 +1266,12 @@ pub struct TokenizerImpl<M, N, PT, PP, D> {
     // General processing parameters
     truncation: Option<TruncationParams>,
     padding: Option<PaddingParams>,
+
+    // Number of tokens per character used to estimate the length of some text
+    fertility_estimate: Option<f64>,
+
+    // Tokenizer used for the pre-tokens made only of unknown tokens
+    fallback_tokenizer: Option<FallbackTokenizer>,
 }
 
 impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
@@ -546,6 +1295,8 @@ where
 
             truncation: None,
             padding: None,
+            fertility_estimate: None,
+            fallback_tokenizer: None,
         }
     }
 
@@ -604,6 +1355,11 @@ where
         &self.model
     }
 
+    /// Get the name of the type of the model (`"BPE"`, `"WordPiece"`, ...)
+    pub fn get_model_type(&self) -> &'static str {
+        self.model.model_type()
+    }
+
     /// Set the added vocabulary.
     pub fn with_added_vocabulary(&mut self, added_vocabulary: AddedVocabulary) -> &mut Self {
         self.added_vocabulary = added_vocabulary;
@@ -643,6 +1399,11 @@ where
         self.truncation.as_mut()
     }
 
+    /// Get the currently set truncation parameters. Same as `get_truncation`
+    pub fn get_truncation_params(&self) -> Option<&TruncationParams> {
+        self.get_truncation()
+    }
+
     /// Set the padding parameters
     pub fn with_padding(&mut self, padding: Option<PaddingParams>) -> &mut Self {
         self.padding = padding;
@@ -654,11 +1415,62 @@ where
         self.padding.as_ref()
     }
 
+    /// Get the currently set padding parameters, or `None` if padding is disabled.
+    /// Same as `get_padding`
+    pub fn padding_params(&self) -> Option<&PaddingParams> {
+        self.get_padding()
+    }
+
+    /// Get the id of the padding token, if padding is enabled
+    pub fn get_padding_token_id(&self) -> Option<u32> {
+        self.padding.as_ref().map(|padding| padding.pad_id)
+    }
+
     /// Get a mutable reference to the currently set padding parameters
     pub fn get_padding_mut(&mut self) -> Option<&mut PaddingParams> {
         self.padding.as_mut()
     }
 
+    /// Set the number of tokens per character used by `estimate_tokens`, usually computed
+    /// with `calibrate_fertility`
+    pub fn set_fertility_estimate(&mut self, fertility: f64) -> &mut Self {
+        self.fertility_estimate = Some(fertility);
+        self
+    }
+
+    /// Set the tokenizer used for the pre-tokens that the model can only encode as unknown
+    /// tokens. The switch token is added as a special token if needed, and the ids of the
+    /// fallback come right after the current ones.
+    pub fn with_fallback_tokenizer(&mut self, fallback: Option<FallbackTokenizer>) -> &mut Self {
+        let mut fallback = fallback;
+        if let Some(fallback) = &mut fallback {
+            let switch_token = AddedToken::from(fallback.switch_token.clone(), true);
+            self.add_special_tokens(&[switch_token]);
+            fallback.id_offset = self.added_vocabulary.next_id(&self.model);
+            self.added_vocabulary.reserve_ids_until(fallback.ids_end());
+        }
+        self.fallback_tokenizer = fallback;
+        self
+    }
+
+    /// Get the fallback tokenizer
+    pub fn get_fallback_tokenizer(&self) -> Option<&FallbackTokenizer> {
+        self.fallback_tokenizer.as_ref()
+    }
+
+    /// Get the number of tokens per character used by `estimate_tokens`
+    pub fn get_fertility_estimate(&self) -> f64 {
+        self.fertility_estimate
+            .unwrap_or(DEFAULT_FERTILITY_ESTIMATE)
+    }
+
+    /// Estimate the number of tokens of a text with `char_count` characters, without
+    /// encoding it. This uses the fertility estimate, which defaults to
+    /// `DEFAULT_FERTILITY_ESTIMATE` until set.
+    pub fn estimate_tokens(&self, char_count: usize) -> usize {
+        (char_count as f64 * self.get_fertility_estimate()).ceil() as usize
+    }
+
     /// Get the vocabulary
     pub fn get_vocab(&self, with_added_tokens: bool) -> HashMap<String, u32> {
         let mut final_vocab = self.model.get_vocab();
@@ -681,6 +1493,81 @@ where
         self.added_vocabulary.get_added_tokens_decoder().clone()
     }
 
+    /// Get the special tokens, by increasing id: the ones `decode` skips with
+    /// `skip_special_tokens`. They are all the tokens added as special, either with
+    /// `add_special_tokens` or as an `AddedToken` with `special` set, including the ones from
+    /// a `tokenizer_config.json`. The models and post-processors don't register any special
+    /// token on their own, so the unknown token of the model, the `[CLS]` of a
+    /// `BertProcessing`, etc. are not part of them by default, only once added as special
+    /// tokens (like the `unk_token` of a `tokenizer_config.json`).
+    pub fn get_all_special_tokens(&self) -> Vec<String> {
+        self.special_tokens_by_id()
+            .into_iter()
+            .map(|(_, token)| token)
+            .collect()
+    }
+
+    /// Get the ids of the special tokens given by `get_all_special_tokens`, in the same order
+    pub fn get_all_special_token_ids(&self) -> Vec<u32> {
+        self.special_tokens_by_id()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    fn special_tokens_by_id(&self) -> Vec<(u32, String)> {
+        let mut special_tokens: Vec<_> = self
+            .added_vocabulary
+            .get_vocab()
+            .iter()
+            .filter(|(token, _)| self.added_vocabulary.is_special_token(token))
+            .map(|(token, id)| (*id, token.clone()))
+            .collect();
+        special_tokens.sort_unstable();
+        special_tokens
+    }
+
+    /// Split `text` into the spans of the special tokens, with their id, and the spans
+    /// between them, with `None`, so that each span can be handled on its own before
+    /// encoding. The spans cover the whole text, in order, and two special tokens can follow
+    /// each other without any span between them.
+    ///
+    /// At each position, the special tokens are tried by decreasing length, so the longest
+    /// one wins. They are looked for literally in `text`: their `single_word`, `lstrip`,
+    /// `rstrip` and `normalized` options are ignored, as well as the regex special tokens.
+    pub fn split_by_special_tokens<'a>(&self, text: &'a str) -> Vec<(Option<u32>, &'a str)> {
+        let decoder = self.added_vocabulary.get_added_tokens_decoder();
+        let mut special_tokens: Vec<_> = self
+            .special_tokens_by_id()
+            .into_iter()
+            .filter(|(id, _)| !decoder.get(id).is_some_and(|token| token.is_regex))
+            .collect();
+        special_tokens.sort_by_key(|(id, token)| (std::cmp::Reverse(token.len()), *id));
+
+        let mut splits = vec![];
+        let (mut start, mut pos) = (0, 0);
+        while pos < text.len() {
+            let rest = &text[pos..];
+            let found = special_tokens
+                .iter()
+                .find(|(_, token)| rest.starts_with(token.as_str()));
+            if let Some((id, token)) = found {
+                if start < pos {
+                    splits.push((None, &text[start..pos]));
+                }
+                splits.push((Some(*id), &rest[..token.len()]));
+                pos += token.len();
+                start = pos;
+            } else {
+                pos += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        if start < text.len() {
+            splits.push((None, &text[start..]));
+        }
+        splits
+    }
+
     /// Get the size of the vocabulary
     pub fn get_vocab_size(&self, with_added_tokens: bool) -> usize {
         // TODO ArthurZ THIS IS WRONG! We need to measure the length of the `set` because
@@ -704,6 +1591,16 @@ where
             .or_else(|| self.model.id_to_token(id))
     }
 
+    /// Converts each of the given tokens in the corresponding id, like `token_to_id`
+    pub fn convert_tokens_to_ids(&self, tokens: &[&str]) -> Vec<Option<u32>> {
+        tokens.iter().map(|token| self.token_to_id(token)).collect()
+    }
+
+    /// Converts each of the given ids to the corresponding token, like `id_to_token`
+    pub fn convert_ids_to_tokens(&self, ids: &[u32]) -> Vec<Option<String>> {
+        ids.iter().map(|id| self.id_to_token(*id)).collect()
+    }
+
     /// set the added bocab's splitting scheme
     pub fn set_encode_special_tokens(&mut self, value: bool) {
         self.added_vocabulary.set_encode_special_tokens(value);
@@ -720,12 +1617,13 @@ where
         sequence: InputSequence,
         type_id: u32,
         offsets_type: OffsetType,
+        options: &EncodeOptions,
     ) -> Result<Encoding> {
         let encode = |is_pre_tokenized, subseq_idx, subseq| -> Result<Encoding> {
             let normalized = self
                 .added_vocabulary
                 .extract_and_normalize(self.normalizer.as_ref(), subseq);
-            let pre_tokenized = self.do_pre_tokenize(normalized)?;
+            let pre_tokenized = self.do_pre_tokenize_with_options(normalized, options)?;
             let subseq_encoding = self.do_tokenize(
                 pre_tokenized,
                 type_id,
@@ -783,6 +1681,22 @@ where
     /// tokenizer.encode(("A complete sequence", &["And", "a", "tokenized"][..]), false);
     /// ```
     pub fn encode<'s, E>(&self, input: E, add_special_tokens: bool) -> Result<Encoding>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        self.encode_with_options(input, add_special_tokens, &EncodeOptions::default())
+    }
+
+    /// Encode the given input like `encode`, with some `EncodeOptions` overriding the
+    /// configuration of the pipeline for this call only. The options apply to every
+    /// sequence of the input (both sequences of a pair, and each word of a pre-tokenized
+    /// input), and `encode` is the same as using the default options.
+    pub fn encode_with_options<'s, E>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+        options: &EncodeOptions,
+    ) -> Result<Encoding>
     where
         E: Into<EncodeInput<'s>>,
     {
@@ -793,9 +1707,9 @@ where
         };
 
         // Encode each sequence
-        let encoding = self.encode_single_sequence(sequence, 0, OffsetType::Byte)?;
+        let encoding = self.encode_single_sequence(sequence, 0, OffsetType::Byte, options)?;
         let pair_encoding = pair
-            .map(|sequence| self.encode_single_sequence(sequence, 1, OffsetType::Byte))
+            .map(|sequence| self.encode_single_sequence(sequence, 1, OffsetType::Byte, options))
             .transpose()?;
 
         // And finally post process
@@ -836,23 +1750,293 @@ where
         };
 
         // Encode each sequence
-        let encoding = self.encode_single_sequence(sequence, 0, OffsetType::Char)?;
+        let options = EncodeOptions::default();
+        let encoding = self.encode_single_sequence(sequence, 0, OffsetType::Char, &options)?;
         let pair_encoding = pair
-            .map(|sequence| self.encode_single_sequence(sequence, 1, OffsetType::Char))
+            .map(|sequence| self.encode_single_sequence(sequence, 1, OffsetType::Char, &options))
             .transpose()?;
 
         // And finally post process
         self.post_process(encoding, pair_encoding, add_special_tokens)
     }
 
+    /// Encode the given input like `encode`, and get the ids, attention mask and type ids
+    /// as `i64`, ready to build NumPy or PyTorch arrays. They all have the length of the
+    /// encoding, including any padding.
+    pub fn encode_to_numpy_compatible<'s, E>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+    ) -> Result<EncodingArrays>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        Ok(self.encode(input, add_special_tokens)?.to_arrays())
+    }
+
+    /// Encode the given input like `encode`, along with the absolute position id of each of
+    /// its tokens, starting from `start_position`: the tokens of the encoding get the position
+    /// ids `start_position..start_position + encoding.len()`, special and padding tokens
+    /// included.
+    ///
+    /// The positions are given after truncation, so they only cover the tokens that are kept:
+    /// the truncation limits the length of the encoding, but does not take `start_position`
+    /// into account, so `start_position + max_length` must stay below the maximum position of
+    /// the model. Each overflowing encoding would start back from its own position, which is
+    /// left to the caller.
+    pub fn encode_with_position_ids<'s, E>(
+        &self,
+        input: E,
+        start_position: usize,
+        add_special_tokens: bool,
+    ) -> Result<(Encoding, Vec<u32>)>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        let encoding = self.encode(input, add_special_tokens)?;
+        let end_position = start_position
+            .checked_add(encoding.len())
+            .filter(|end| *end <= u32::MAX as usize)
+            .ok_or("The position ids do not fit in a u32")?;
+        let position_ids = (start_position as u32..end_position as u32).collect();
+        Ok((encoding, position_ids))
+    }
+
+    /// Encode the given input like `encode`, and get the encoding followed by all its
+    /// overflowing windows, which are left empty in the returned encodings
+    pub fn encode_with_overflowing_tokens<'s, E>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+    ) -> Result<Vec<Encoding>>
+    where
+        E: Into<EncodeInput<'s>>,
+    {
+        let mut encoding = self.encode(input, add_special_tokens)?;
+        let overflowing = encoding.take_overflowing();
+        Ok(std::iter::once(encoding).chain(overflowing).collect())
+    }
+
+    /// Apply the current truncation params to an already built `encoding`, without encoding
+    /// its input again. Nothing changes when no truncation is set.
+    ///
+    /// The encoding is truncated as a single sequence, and `max_length` bounds all of its
+    /// tokens: no room is kept for special tokens, and the ones already added (or any padding)
+    /// can get truncated away. This gives the same result as encoding with these params and
+    /// without special tokens, when the encoding was built without truncation nor special
+    /// tokens in the first place.
+    pub fn truncate_in_place(&self, encoding: &mut Encoding) -> Result<()> {
+        if let Some(params) = &self.truncation {
+            let (truncated, _) = truncate_encodings(std::mem::take(encoding), None, params)?;
+            *encoding = truncated;
+        }
+        Ok(())
+    }
+
+    /// Encode `text` like `encode`, and prepend the language token of `language`, as expected
+    /// by multilingual models like mBART. The language token is the special token whose content
+    /// is either `language` itself (like `en_XX`) or `language` between brackets (like
+    /// `[en_XX]`), and it must have been registered with `add_special_tokens`.
+    ///
+    /// The language token takes the type id of the first token of the encoding, with an
+    /// attention mask of `1`. It is prepended after the truncation and the padding, so the
+    /// encoding (and each of its overflowing windows) gets one token longer.
+    pub fn encode_with_language_tags(
+        &self,
+        text: &str,
+        language: &str,
+        add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        let (id, token) = vec![language.to_owned(), format!("[{}]", language)]
+            .into_iter()
+            .find_map(|token| {
+                let id = self.added_vocabulary.get_vocab().get(&token)?;
+                self.added_vocabulary
+                    .is_special_token(&token)
+                    .then_some((*id, token))
+            })
+            .ok_or_else(|| {
+                format!(
+                    "No special token registered for the language `{}`",
+                    language
+                )
+            })?;
+
+        let encoding = self.encode(text, add_special_tokens)?;
+        let type_id = encoding.get_type_ids().first().copied().unwrap_or(0);
+        let language_encoding = Encoding::new(
+            vec![id],
+            vec![type_id],
+            vec![token],
+            vec![None],
+            vec![(0, 0)],
+            vec![1],
+            vec![1],
+            vec![],
+            HashMap::new(),
+        );
+        Ok(Encoding::merge([language_encoding, encoding], false))
+    }
+
+    /// Encode `text` as the continuation of the already encoded `previous_context`, like
+    /// `encode_with_context_window` with the last `DEFAULT_CONTEXT_WINDOW` tokens of context.
+    pub fn encode_with_context(
+        &self,
+        previous_context: &[u32],
+        text: &str,
+        add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        self.encode_with_context_window(
+            previous_context,
+            text,
+            add_special_tokens,
+            DEFAULT_CONTEXT_WINDOW,
+        )
+    }
+
+    /// Encode `text` as the continuation of the already encoded `previous_context`. The last
+    /// `window` tokens of the context get decoded and prepended to `text`, so that the tokens
+    /// at the boundary are the ones we would get by encoding everything at once. Only the
+    /// tokens covering some of `text` are returned, with offsets relative to `text`: a token
+    /// that also covers the end of the context starts at `0`.
+    pub fn encode_with_context_window(
+        &self,
+        previous_context: &[u32],
+        text: &str,
+        add_special_tokens: bool,
+        window: usize,
+    ) -> Result<Encoding> {
+        let start = previous_context.len().saturating_sub(window);
+        let (_, encoding) = self.encode_after_context(&previous_context[start..], text)?;
+        self.post_process(encoding, None, add_special_tokens)
+    }
+
+    /// Encode `completion` as the continuation of a prompt whose ids are already in a KV
+    /// cache, like `encode_with_context` without special tokens. The last tokens of the
+    /// prompt must be the ones we get by encoding the prompt with the completion, so this fails
+    /// when a token would cross the boundary between them, or when the decoded prompt does not
+    /// encode back to the same ids.
+    pub fn encode_with_prompt_cache(
+        &self,
+        prompt_ids: &[u32],
+        completion: &str,
+    ) -> Result<Encoding> {
+        let start = prompt_ids.len().saturating_sub(DEFAULT_CONTEXT_WINDOW);
+        let prompt_ids = &prompt_ids[start..];
+        let (context_ids, encoding) = self.encode_after_context(prompt_ids, completion)?;
+        if context_ids != prompt_ids {
+            return Err(format!(
+                "The prompt does not encode consistently with the completion: expected {:?} but got {:?}",
+                prompt_ids, context_ids
+            )
+            .into());
+        }
+        self.post_process(encoding, None, false)
+    }
+
+    /// Decode `context_ids`, and encode it followed by `text`. Returns the ids of the tokens
+    /// covering only the context, and the encoding of the others, with offsets and words
+    /// relative to `text`.
+    fn encode_after_context(
+        &self,
+        context_ids: &[u32],
+        text: &str,
+    ) -> Result<(Vec<u32>, Encoding)> {
+        let context = self.decode(context_ids, false)?;
+        let combined = format!("{}{}", context, text);
+        let options = EncodeOptions::default();
+        let encoding = self.encode_single_sequence(
+            InputSequence::from(combined.as_str()),
+            0,
+            OffsetType::Byte,
+            &options,
+        )?;
+
+        let context_len = context.len();
+        let first = encoding
+            .get_offsets()
+            .iter()
+            .position(|(start, end)| *end > context_len || *start >= context_len)
+            .unwrap_or(encoding.len());
+        let context_ids = encoding.get_ids()[..first].to_vec();
+        let mut encoding = encoding.slice(first, encoding.len())?;
+        for (start, end) in encoding.get_offsets_mut() {
+            *start = start.saturating_sub(context_len);
+            *end = end.saturating_sub(context_len);
+        }
+        let first_word = encoding.get_word_ids().iter().flatten().min().copied();
+        if let Some(first_word) = first_word {
+            for word in encoding.get_word_ids_mut().iter_mut().flatten() {
+                *word -= first_word;
+            }
+        }
+
+        Ok((context_ids, encoding))
+    }
+
+    /// Encode the given words, that have already been split by some external tool. Unlike
+    /// encoding an `InputSequence::PreTokenized`, the pre-tokenizer is not used at all: each
+    /// word only goes through the normalizer before being given to the model. The offsets
+    /// are computed as if the words were joined with a single space.
+    pub fn encode_pretokenized(
+        &self,
+        words: &[&str],
+        add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        let mut start = 0;
+        let encoding = words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                let normalized = self
+                    .added_vocabulary
+                    .extract_and_normalize(self.normalizer.as_ref(), word);
+                let mut encoding =
+                    self.do_tokenize(normalized, 0, Some(i as u32), OffsetType::Byte)?;
+                for (offset_start, offset_end) in encoding.get_offsets_mut() {
+                    *offset_start += start;
+                    *offset_end += start;
+                }
+                start += word.len() + 1;
+                Ok(encoding)
+            })
+            .collect::<Result<Encoding>>()?;
+
+        self.post_process(encoding, None, add_special_tokens)
+    }
+
+    /// Encode the given input, and serialize the resulting `Encoding` as JSON directly to
+    /// the given writer, without allocating an intermediate `String`.
+    pub fn encode_to_writer<'s, E, W>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+        mut writer: W,
+    ) -> Result<()>
+    where
+        E: Into<EncodeInput<'s>>,
+        W: Write,
+    {
+        self.encode(input, add_special_tokens)?
+            .to_json_writer(&mut writer)
+    }
+
     /// Decode the given ids, back to a String
     pub fn decode(&self, ids: &[u32], skip_special_tokens: bool) -> Result<String> {
+        let fallback = self
+            .fallback_tokenizer
+            .as_ref()
+            .map(|fallback| (&fallback.tokenizer, fallback.id_offset));
         let tokens = ids
             .iter()
             .filter_map(|id| {
                 self.added_vocabulary
                     .simple_id_to_token(*id)
                     .or_else(|| self.model.id_to_token(*id))
+                    .or_else(|| {
+                        let (tokenizer, id_offset) = fallback?;
+                        tokenizer.id_to_token(id.checked_sub(id_offset)?)
+                    })
                     .filter(|token| {
                         !skip_special_tokens || !self.added_vocabulary.is_special_token(token)
                     })
@@ -865,6 +2049,23 @@ where
             Ok(tokens.join(" "))
         }
     }
+
+    /// Decode each of the given sequences, and write it to the given writer followed by
+    /// `separator` (usually `"\n"`), without keeping all the decoded strings in memory.
+    pub fn batch_decode_to_writer<W: Write>(
+        &self,
+        sequences: &[Vec<u32>],
+        skip_special_tokens: bool,
+        separator: &str,
+        mut writer: W,
+    ) -> Result<()> {
+        for ids in sequences {
+            let decoded = self.decode(ids, skip_special_tokens)?;
+            writer.write_all(decoded.as_bytes())?;
+            writer.write_all(separator.as_bytes())?;
+        }
+        Ok(())
+    }
 }
 
 impl<M, N, PT, PP, D> TokenizerImpl<M, N, PT, PP, D>
@@ -881,7 +2082,24 @@ where
         offsets_type: OffsetType,
     ) -> Result<Encoding> {
         let mut pretokenized: PreTokenizedString = pretokenized.into();
-        pretokenized.tokenize(|normalized| self.model.tokenize(normalized.get()))?;
+        let unk_id = self.model.get_unk_id();
+        pretokenized.tokenize(|normalized| {
+            let tokens = self.model.tokenize(normalized.get())?;
+            match (&self.fallback_tokenizer, unk_id) {
+                (Some(fallback), Some(unk_id))
+                    if !tokens.is_empty() && tokens.iter().all(|token| token.id == unk_id) =>
+                {
+                    let switch_id = self
+                        .added_vocabulary
+                        .token_to_id(&fallback.switch_token, &self.model)
+                        .ok_or(
+                            "The switch token of the fallback tokenizer is not in the vocabulary",
+                        )?;
+                    fallback.tokenize(normalized.get(), switch_id, fallback.id_offset)
+                }
+                _ => Ok(tokens),
+            }
+        })?;
         pretokenized.into_encoding(word_idx, type_id, offsets_type)
     }
 }
@@ -910,14 +2128,50 @@ where
     /// Register the given tokens as special tokens. This is especially useful for removing
     /// these special tokens while decoding
     pub fn add_special_tokens(&mut self, tokens: &[AddedToken]) -> usize {
+        let tokens = self.normalize_added_tokens(tokens);
         self.added_vocabulary
-            .add_special_tokens(tokens, &self.model, self.normalizer.as_ref())
+            .add_special_tokens(&tokens, &self.model, self.normalizer.as_ref())
     }
 
     /// Add the given tokens to the added vocabulary
     pub fn add_tokens(&mut self, tokens: &[AddedToken]) -> usize {
+        let tokens = self.normalize_added_tokens(tokens);
+        self.added_vocabulary
+            .add_tokens(&tokens, &self.model, self.normalizer.as_ref())
+    }
+
+    /// Remove all the special tokens from the added vocabulary, without affecting the
+    /// vocabulary of the model. Their ids are left unused, unless `rebuild_vocabulary` gets
+    /// called to compact the ids of the remaining added tokens. Returns the number of special
+    /// tokens removed.
+    pub fn reset_special_tokens(&mut self) -> usize {
+        self.added_vocabulary
+            .reset_special_tokens(&self.model, self.normalizer.as_ref())
+    }
+
+    /// Remove all the regular added tokens, like `reset_special_tokens` does for the special
+    /// ones. Returns the number of added tokens removed.
+    pub fn reset_added_tokens(&mut self) -> usize {
         self.added_vocabulary
-            .add_tokens(tokens, &self.model, self.normalizer.as_ref())
+            .reset_added_tokens(&self.model, self.normalizer.as_ref())
+    }
+
+    /// Normalize the content of the tokens with `normalize_when_loading`. If the normalizer
+    /// fails on some content, it is kept as is.
+    fn normalize_added_tokens(&self, tokens: &[AddedToken]) -> Vec<AddedToken> {
+        tokens
+            .iter()
+            .map(|token| {
+                let mut token = token.clone();
+                if let (true, Some(normalizer)) = (token.normalize_when_loading, &self.normalizer) {
+                    let mut content = NormalizedString::from(token.content.as_str());
+                    if normalizer.normalize(&mut content).is_ok() {
+                        token.content = content.get().to_owned();
+                    }
+                }
+                token
+            })
+            .collect()
     }
 }
 
@@ -929,10 +2183,18 @@ where
     fn do_pre_tokenize<P: Into<PreTokenizedString>>(
         &self,
         pretokenized: P,
+    ) -> Result<PreTokenizedString> {
+        self.do_pre_tokenize_with_options(pretokenized, &EncodeOptions::default())
+    }
+
+    fn do_pre_tokenize_with_options<P: Into<PreTokenizedString>>(
+        &self,
+        pretokenized: P,
+        options: &EncodeOptions,
     ) -> Result<PreTokenizedString> {
         let mut pretokenized: PreTokenizedString = pretokenized.into();
         if let Some(ref pretok) = self.pre_tokenizer {
-            pretok.pre_tokenize(&mut pretokenized)?;
+            pretok.pre_tokenize_with_options(&mut pretokenized, options)?;
         }
 
         Ok(pretokenized)
@@ -1024,10 +2286,72 @@ where
     where
         E: Into<EncodeInput<'s>> + Send,
     {
-        let mut encodings = inputs
-            .into_maybe_par_iter()
-            .map(|input| self.encode(input, add_special_tokens))
-            .collect::<Result<Vec<Encoding>>>()?;
+        self.encode_batch_with_options(inputs, add_special_tokens, &EncodeOptions::default())
+    }
+
+    /// Encode all the pairs of sequences in parallel, like `encode_batch` does with
+    /// `EncodeInput::Dual` inputs. This is the common case of QA or NLI tasks.
+    pub fn encode_pairs_batch(
+        &self,
+        pairs: &[(&str, &str)],
+        add_special_tokens: bool,
+    ) -> Result<Vec<Encoding>> {
+        let inputs: Vec<EncodeInput> = pairs.iter().map(|pair| (*pair).into()).collect();
+        self.encode_batch(inputs, add_special_tokens)
+    }
+
+    /// Encode all the sentences in parallel like `encode_batch`, and take the overflowing
+    /// windows out of the encodings: the `i`-th element of the second vec holds the windows of
+    /// the `i`-th encoding, in order, and is empty when the input did not overflow.
+    pub fn encode_batch_with_return_overflowing_encodings<'s, E>(
+        &self,
+        inputs: Vec<E>,
+        add_special_tokens: bool,
+    ) -> Result<(Vec<Encoding>, Vec<Vec<Encoding>>)>
+    where
+        E: Into<EncodeInput<'s>> + Send,
+    {
+        let mut encodings = self.encode_batch(inputs, add_special_tokens)?;
+        let overflowing = encodings
+            .iter_mut()
+            .map(|encoding| encoding.take_overflowing())
+            .collect();
+        Ok((encodings, overflowing))
+    }
+
+    /// Encode all the sentences in parallel like `encode_batch`, with some `EncodeOptions`
+    /// overriding the configuration of the pipeline for this call only
+    pub fn encode_batch_with_options<'s, E>(
+        &self,
+        inputs: Vec<E>,
+        add_special_tokens: bool,
+        options: &EncodeOptions,
+    ) -> Result<Vec<Encoding>>
+    where
+        E: Into<EncodeInput<'s>> + Send,
+    {
+        let encode =
+            |input: EncodeInput<'s>| self.encode_with_options(input, add_special_tokens, options);
+        let mut encodings = if options.sort_for_parallel {
+            let mut inputs: Vec<(usize, EncodeInput)> =
+                inputs.into_iter().map(Into::into).enumerate().collect();
+            inputs.sort_by_cached_key(|(_, input)| std::cmp::Reverse(input.char_len()));
+            let mut encodings = inputs
+                .into_maybe_par_iter()
+                .map(|(i, input)| Ok((i, encode(input)?)))
+                .collect::<Result<Vec<_>>>()?;
+            // Restore the order of the inputs
+            encodings.sort_unstable_by_key(|(i, _)| *i);
+            encodings
+                .into_iter()
+                .map(|(_, encoding)| encoding)
+                .collect()
+        } else {
+            inputs
+                .into_maybe_par_iter()
+                .map(|input| encode(input.into()))
+                .collect::<Result<Vec<Encoding>>>()?
+        };
 
         if let Some(params) = &self.padding {
             // We do the padding here to make sure we handle the batch padding
@@ -1037,6 +2361,46 @@ where
         Ok(encodings)
     }
 
+    /// Lazily encode the lines read from `reader`, by batches of `batch_size` lines (at least
+    /// one). Each batch is encoded with `encode_batch`, and the last one may be smaller. The
+    /// iteration stops after the first I/O error, which is yielded.
+    pub fn encode_batch_stream<'a, R>(
+        &'a self,
+        reader: R,
+        batch_size: usize,
+        add_special_tokens: bool,
+    ) -> impl Iterator<Item = Result<Vec<Encoding>>> + 'a
+    where
+        R: BufRead + 'a,
+    {
+        let mut lines = reader.lines();
+        let mut done = false;
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let mut batch = Vec::with_capacity(batch_size.max(1));
+            while batch.len() < batch_size.max(1) {
+                match lines.next() {
+                    Some(Ok(line)) => batch.push(line),
+                    Some(Err(e)) => {
+                        done = true;
+                        return Some(Err(e.into()));
+                    }
+                    None => {
+                        done = true;
+                        break;
+                    }
+                }
+            }
+            if batch.is_empty() {
+                None
+            } else {
+                Some(self.encode_batch(batch, add_special_tokens))
+            }
+        })
+    }
+
     /// Encode all the sentences in parallel, using multiple threads.
     /// The offsets on each `Encoding` will be relative to chars instead of bytes.
     pub fn encode_batch_char_offsets<'s, E>(
@@ -1075,6 +2439,28 @@ where
             .collect()
     }
 
+    /// Normalize and pre-tokenize a sequence, to get the words fed to a trainer
+    fn words_for_training(&self, sequence: &str) -> Result<Vec<String>> {
+        let normalized = self.do_normalize(sequence)?;
+        let pre_tokenized = self.do_pre_tokenize(normalized)?;
+        Ok(pre_tokenized
+            .get_splits(OffsetReferential::Original, OffsetType::Byte)
+            .into_iter()
+            .map(|(s, _, _)| s.to_owned())
+            .collect())
+    }
+
+    /// Register the decoder provided by the trainer, unless we already have one. This only
+    /// applies when it can be converted with `Decoder::from_trainer_decoder`.
+    fn register_trainer_decoder<T>(&mut self, trainer: &T)
+    where
+        T: Trainer<Model = M>,
+    {
+        if self.decoder.is_none() {
+            self.decoder = trainer.decoder().and_then(D::from_trainer_decoder);
+        }
+    }
+
     /// Train our Model from files
     pub fn train_from_files<T>(&mut self, trainer: &mut T, files: Vec<String>) -> Result<&mut Self>
     where
@@ -1124,15 +2510,7 @@ where
                         }
                         s
                     }),
-                    |seq| {
-                        let normalized = self.do_normalize(seq.as_ref())?;
-                        let pre_tokenized = self.do_pre_tokenize(normalized)?;
-                        Ok(pre_tokenized
-                            .get_splits(OffsetReferential::Original, OffsetType::Byte)
-                            .into_iter()
-                            .map(|(s, _, _)| s.to_owned())
-                            .collect())
-                    },
+                    |seq| self.words_for_training(seq),
                 )?;
 
                 if let Some(pbar) = progress {
@@ -1140,6 +2518,7 @@ where
                 }
                 let special_tokens = trainer.train(&mut self.model)?;
                 self.add_special_tokens(&special_tokens);
+                self.register_trainer_decoder(trainer);
 
                 Ok(())
             },
@@ -1176,15 +2555,7 @@ where
                 }
                 s
             }),
-            |seq| {
-                let normalized = self.do_normalize(seq.as_ref())?;
-                let pre_tokenized = self.do_pre_tokenize(normalized)?;
-                Ok(pre_tokenized
-                    .get_splits(OffsetReferential::Original, OffsetType::Byte)
-                    .into_iter()
-                    .map(|(s, _, _)| s.to_owned())
-                    .collect())
-            },
+            |seq| self.words_for_training(seq),
         )?;
         if let Some(pbar) = progress {
             pbar.finish();
@@ -1192,6 +2563,31 @@ where
 
         let special_tokens = trainer.train(&mut self.model)?;
         self.add_special_tokens(&special_tokens);
+        self.register_trainer_decoder(trainer);
+
+        Ok(self)
+    }
+
+    /// Train our Model, using the given Trainer and an iterator of `(sequence, weight)`. The
+    /// word counts of each sequence get multiplied by its weight, so that some sources can be
+    /// up or down-weighted. A weight of 0.0 ignores the sequence, and a negative or
+    /// non-finite weight is an error. Only the trainers supporting `Trainer::feed_weighted`
+    /// can be used.
+    pub fn train_from_weighted_iterator<T, I, S>(
+        &mut self,
+        trainer: &mut T,
+        sequences: I,
+    ) -> Result<&mut Self>
+    where
+        T: Trainer<Model = M> + Sync,
+        I: Iterator<Item = (S, f64)> + Send,
+        S: AsRef<str> + Send,
+    {
+        trainer.feed_weighted(sequences, |seq| self.words_for_training(seq))?;
+
+        let special_tokens = trainer.train(&mut self.model)?;
+        self.add_special_tokens(&special_tokens);
+        self.register_trainer_decoder(trainer);
 
         Ok(self)
     }
@@ -1207,8 +2603,10 @@ where
 {
     type Err = Error;
 
+    /// Deserialize a tokenizer, loading the ones of another version on a best-effort basis
+    /// with a `VersionWarning`, or failing with a `DeserializationError::VersionMismatch`
     fn from_str(s: &str) -> Result<Self> {
-        Ok(serde_json::from_str(s)?)
+        serialization::check_version(s.as_bytes(), serde_json::from_str(s))
     }
 }
 
@@ -1223,8 +2621,7 @@ where
     /// Instantiate a new Tokenizer from the given file
     pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Self> {
         let content = read_to_string(file)?;
-        let tokenizer = serde_json::from_str(&content)?;
-        Ok(tokenizer)
+        content.parse()
     }
 }
 
@@ -1238,8 +2635,8 @@ where
 {
     /// Instantiate a new Tokenizer from bytes
     pub fn from_bytes<P: AsRef<[u8]>>(bytes: P) -> Result<Self> {
-        let tokenizer = serde_json::from_slice(bytes.as_ref())?;
-        Ok(tokenizer)
+        let bytes = bytes.as_ref();
+        serialization::check_version(bytes, serde_json::from_slice(bytes))
     }
 }
 
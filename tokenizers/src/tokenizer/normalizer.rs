@@ -125,6 +125,54 @@ impl NormalizedString {
             original_shift,
         }
     }
+    /// Build a NormalizedString from alignments computed elsewhere: `alignments` gives,
+    /// for each char of `normalized`, the `(start, end)` byte offsets of the corresponding
+    /// part of `original`.
+    pub fn from_parts(
+        original: &str,
+        normalized: &str,
+        alignments: Vec<(usize, usize)>,
+    ) -> Result<Self> {
+        let n_chars = normalized.chars().count();
+        if alignments.len() != n_chars {
+            return Err(format!(
+                "Expected one alignment for each of the {} chars of the normalized string, got {}",
+                n_chars,
+                alignments.len()
+            )
+            .into());
+        }
+
+        let mut byte_alignments = Vec::with_capacity(normalized.len());
+        for (i, (c, (start, end))) in normalized.chars().zip(alignments).enumerate() {
+            if start > end || end > original.len() {
+                return Err(format!(
+                    "Invalid alignment ({}, {}) for char {}: the original string has {} bytes",
+                    start,
+                    end,
+                    i,
+                    original.len()
+                )
+                .into());
+            }
+            if !original.is_char_boundary(start) || !original.is_char_boundary(end) {
+                return Err(format!(
+                    "Invalid alignment ({}, {}) for char {}: not on a char boundary of the original string",
+                    start, end, i
+                )
+                .into());
+            }
+            byte_alignments.extend((0..c.len_utf8()).map(|_| (start, end)));
+        }
+
+        Ok(Self {
+            original: original.to_owned(),
+            normalized: normalized.to_owned(),
+            alignments: byte_alignments,
+            original_shift: 0,
+        })
+    }
+
     /// Return the normalized string
     pub fn get(&self) -> &str {
         &self.normalized
@@ -511,6 +559,33 @@ impl NormalizedString {
         self
     }
 
+    /// Insert the given string at the byte position `pos` of the normalized string. The new
+    /// chars are aligned with the char right before `pos`, or with the first one when
+    /// inserting at the start. Does nothing if `pos` is not a char boundary.
+    pub fn insert(&mut self, pos: usize, s: &str) -> &mut Self {
+        if pos == 0 {
+            return self.prepend(s);
+        }
+        if !self.normalized.is_char_boundary(pos) {
+            return self;
+        }
+        if let Some((b, prev)) = self.normalized[..pos].char_indices().last() {
+            let transformations = std::iter::once((prev, 0)).chain(s.chars().map(|c| (c, 1)));
+            self.transform_range(Range::Normalized(b..pos), transformations, 0);
+        }
+        self
+    }
+
+    /// Remove the given byte range of the normalized string. Does nothing if the range is not
+    /// valid.
+    pub fn delete_range(&mut self, range: std::ops::Range<usize>) -> &mut Self {
+        if let Some(removed) = self.normalized.get(range.clone()) {
+            let removed = removed.chars().count();
+            self.transform_range(Range::Normalized(range), std::iter::empty(), removed);
+        }
+        self
+    }
+
     /// Map our characters
     pub fn map<F: Fn(char) -> char>(&mut self, map: F) -> &mut Self {
         let transformations = self
@@ -552,6 +627,18 @@ impl NormalizedString {
         self
     }
 
+    /// Replace the given byte range of the normalized string with `content`, with the same
+    /// alignments as `replace` would give for a match on this range. Does nothing if the range
+    /// is not valid.
+    pub fn replace_range(&mut self, range: std::ops::Range<usize>, content: &str) -> &mut Self {
+        if let Some(removed) = self.normalized.get(range.clone()) {
+            let removed = removed.chars().count();
+            let dest = content.chars().map(|c| (c, 1));
+            self.transform_range(Range::Normalized(range), dest, removed);
+        }
+        self
+    }
+
     /// Replace anything that matches the pattern with the given content.
     pub fn replace<P: Pattern>(&mut self, pattern: P, content: &str) -> Result<()> {
         let mut new_normalized = String::with_capacity(self.normalized.len()); // Initially allocate for the input size
@@ -666,6 +753,21 @@ impl NormalizedString {
         len
     }
 
+    /// Discard all the transformations applied so far, and reset the normalized string to the
+    /// original one, with identity alignments. All subsequent normalizations then apply to
+    /// the original text.
+    pub fn revert(&mut self) -> &mut Self {
+        self.normalized.clear();
+        self.normalized.push_str(&self.original);
+        self.alignments.clear();
+        self.alignments
+            .extend(self.original.char_indices().flat_map(|(b, c)| {
+                let len = c.len_utf8();
+                (0..len).map(move |_| (b, b + len))
+            }));
+        self
+    }
+
     /// Split the current string in many subparts. Specify what to do with the
     /// delimiter.
     ///
@@ -1163,6 +1265,30 @@ mod tests {
         assert_eq!(n.convert_offsets(Range::Normalized(10..)), None);
     }
 
+    #[test]
+    fn from_parts() {
+        // Built by hand to match the one lowercasing and removing the whitespaces gives
+        let mut n = NormalizedString::from("  Héllo  ");
+        n.filter(|c| !c.is_whitespace()).lowercase();
+        let alignments = vec![(2, 3), (3, 5), (5, 6), (6, 7), (7, 8)];
+        let built = NormalizedString::from_parts("  Héllo  ", "héllo", alignments).unwrap();
+        assert_eq!(built, n);
+        assert_eq!(built.convert_offsets(Range::Normalized(1..3)), Some(3..5));
+        assert_eq!(
+            built.get_range_original(Range::Normalized(0..6)),
+            Some("Héllo")
+        );
+
+        // Several chars can come from the same part of the original string
+        let built = NormalizedString::from_parts("ﬁ", "fi", vec![(0, 3), (0, 3)]).unwrap();
+        assert_eq!(built.get_range_original(Range::Normalized(1..2)), Some("ﬁ"));
+
+        assert!(NormalizedString::from_parts("abc", "abc", vec![(0, 1), (1, 2)]).is_err());
+        assert!(NormalizedString::from_parts("abc", "ab", vec![(0, 1), (1, 4)]).is_err());
+        assert!(NormalizedString::from_parts("abc", "ab", vec![(0, 1), (2, 1)]).is_err());
+        assert!(NormalizedString::from_parts("é", "e", vec![(0, 1)]).is_err());
+    }
+
     #[test]
     fn original_range() {
         let mut n = NormalizedString::from("Hello_______ World!");
@@ -1388,6 +1514,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn insert() {
+        let mut n = NormalizedString::from("Hey there");
+        n.insert(3, ",");
+        n.insert(n.len(), "!");
+        n.insert(0, "¡");
+        assert_eq!(n.get(), "¡Hey, there!");
+        assert_eq!(n.get_range_original(Range::Normalized(5..6)), Some("y"));
+        assert_eq!(n.get_range_original(Range::Normalized(0..2)), Some("H"));
+        assert_eq!(n.get_range(Range::Original(3..9)), Some(" there!"));
+
+        // Not a char boundary
+        n.insert(1, "x");
+        assert_eq!(n.get(), "¡Hey, there!");
+    }
+
+    #[test]
+    fn replace_range() {
+        let mut n = NormalizedString::from("I'm the best");
+        n.replace_range(4..7, "a");
+        let mut expected = NormalizedString::from("I'm the best");
+        expected.replace("the", "a").unwrap();
+        assert_eq!(n, expected);
+        assert_eq!(n.get(), "I'm a best");
+        assert_eq!(n.get_range(Range::Original(8..12)), Some("best"));
+
+        n.replace_range(0..3, "I am");
+        expected.replace("I'm", "I am").unwrap();
+        assert_eq!(n, expected);
+        assert_eq!(n.get(), "I am a best");
+        n.replace_range(3..20, "x");
+        assert_eq!(n.get(), "I am a best");
+    }
+
+    #[test]
+    fn delete_range() {
+        let mut n = NormalizedString::from("a (bracket) b");
+        n.delete_range(1..11);
+        assert_eq!(n.get(), "a b");
+        assert_eq!(n.get_range(Range::Original(12..13)), Some("b"));
+        assert_eq!(n.get_range(Range::Original(3..10)), Some(""));
+        n.delete_range(0..2);
+        assert_eq!(n.get(), "b");
+        assert_eq!(n.get_range_original(Range::Normalized(0..1)), Some("b"));
+
+        // Invalid range
+        n.delete_range(0..3);
+        assert_eq!(n.get(), "b");
+    }
+
     #[test]
     fn get_range() {
         let s = String::from("Hello my name is John 👋");
@@ -2260,4 +2436,26 @@ mod tests {
         s.lowercase();
         assert_eq!(s.get(), "a...");
     }
+
+    #[test]
+    fn revert() {
+        use crate::normalizers::utils::Lowercase;
+        use crate::Normalizer;
+
+        let mut s = NormalizedString::from("Héllo WÖRLD");
+        Lowercase.normalize(&mut s).unwrap();
+        s.filter(|c| c != ' ');
+        assert_eq!(s.get(), "héllowörld");
+
+        s.revert();
+        assert_eq!(s, NormalizedString::from("Héllo WÖRLD"));
+
+        s.uppercase();
+        assert_eq!(s.get(), "HÉLLO WÖRLD");
+        assert_eq!(s.get_original(), "Héllo WÖRLD");
+        assert_eq!(
+            s.alignments,
+            NormalizedString::from("Héllo WÖRLD").alignments
+        );
+    }
 }
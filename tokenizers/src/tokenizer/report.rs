@@ -0,0 +1,626 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::tokenizer::{Encoding, Model, Offsets, PostProcessor, Result, Tokenizer};
+use crate::utils::padding::PaddingStrategy;
+
+/// Aggregate statistics about the tokenization of some input, useful to detect a domain
+/// mismatch between a tokenizer and the text it processes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenizationReport {
+    pub tokens: Vec<String>,
+    pub ids: Vec<u32>,
+    /// Number of tokens per character
+    pub fertility: f64,
+    /// Fraction of the characters covered by a token that is not the unknown token
+    pub char_coverage: f64,
+    /// Number of unknown tokens
+    pub unk_count: usize,
+    /// Fraction of the tokens that are the unknown token
+    pub unk_rate: f64,
+    /// Fraction of the bytes covered by a token that is not the unknown token
+    pub byte_coverage: f64,
+}
+
+/// The differences between the segmentations of the same text by two tokenizers, at the
+/// byte-offset level. Each span is the offsets of a token in `text`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentationComparison {
+    pub text: String,
+    /// The spans produced by both tokenizers
+    pub shared_spans: Vec<Offsets>,
+    /// The spans produced only by the tokenizer being compared
+    pub self_only_spans: Vec<Offsets>,
+    /// The spans produced only by the other tokenizer
+    pub other_only_spans: Vec<Offsets>,
+}
+
+impl std::fmt::Display for SegmentationComparison {
+    /// Shows one span per line, in the order of the text, with a `-` for the spans only
+    /// produced by the first tokenizer, and a `+` for the ones only produced by the other
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut spans: Vec<_> = self
+            .shared_spans
+            .iter()
+            .map(|span| (span, ' '))
+            .chain(self.self_only_spans.iter().map(|span| (span, '-')))
+            .chain(self.other_only_spans.iter().map(|span| (span, '+')))
+            .collect();
+        spans.sort_by_key(|((start, end), marker)| (*start, *end, *marker));
+        for ((start, end), marker) in spans {
+            let content = self.text.get(*start..*end).unwrap_or("");
+            writeln!(f, "{} {:?} ({}, {})", marker, content, start, end)?;
+        }
+        Ok(())
+    }
+}
+
+/// The overlap between the vocabularies (with the added tokens) of two tokenizers, based on
+/// the content of their tokens, whatever their ids
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedVocabReport {
+    /// Number of tokens in both vocabularies
+    pub shared_count: usize,
+    /// Fraction of the vocabulary of the tokenizer being compared that is shared
+    pub shared_fraction_self: f64,
+    /// Fraction of the vocabulary of the other tokenizer that is shared
+    pub shared_fraction_other: f64,
+    /// The tokens only in the vocabulary of the tokenizer being compared, sorted
+    pub only_in_self: Vec<String>,
+    /// The tokens only in the vocabulary of the other tokenizer, sorted
+    pub only_in_other: Vec<String>,
+}
+
+/// How well the vocabulary of a tokenizer covers a corpus, at the word level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    /// Number of words given by the pre-tokenizer
+    pub total_words: usize,
+    /// Number of words with at least one unknown token
+    pub unk_words: usize,
+    /// Fraction of the words with at least one unknown token
+    pub unk_fraction: f64,
+    /// Some distinct words with unknown tokens, in the order of the corpus
+    pub unk_examples: Vec<String>,
+}
+
+/// A summary of the configuration of a tokenizer, meant for model cards and logs. The
+/// components are given by the `"type"` of their serialization.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenizerDescription {
+    pub model_type: String,
+    /// Size of the vocabulary of the model, without the added tokens
+    pub vocab_size: usize,
+    pub num_added_tokens: usize,
+    /// Number of added tokens that are special tokens
+    pub num_special_tokens: usize,
+    pub normalizer: Option<String>,
+    pub pre_tokenizer: Option<String>,
+    pub post_processor: Option<String>,
+    pub decoder: Option<String>,
+    pub padding: Option<PaddingDescription>,
+    pub truncation: Option<TruncationDescription>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaddingDescription {
+    /// The fixed length of the padding, `None` meaning the longest sequence of each batch
+    pub length: Option<usize>,
+    pub pad_to_multiple_of: Option<usize>,
+    pub direction: String,
+    pub pad_token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TruncationDescription {
+    pub max_length: usize,
+    pub stride: usize,
+    pub strategy: String,
+    pub direction: String,
+}
+
+/// The heap bytes used by `estimate_max_batch_memory` for each byte of an input, while
+/// encoding it: the input and its normalized version (up to twice larger, like with a byte-level
+/// pre-tokenizer) with the alignment of each of its bytes, kept by both the normalized input
+/// and its splits, doubled for the growth of the vectors
+pub const WORKING_BYTES_PER_INPUT_BYTE: usize =
+    2 * 2 * (1 + 2 * (1 + std::mem::size_of::<(usize, usize)>()));
+
+/// The `"type"` of the serialization of a component
+fn component_type<T: Serialize>(component: &T) -> Option<String> {
+    let value = serde_json::to_value(component).ok()?;
+    value.get("type")?.as_str().map(|t| t.to_owned())
+}
+
+fn ratio(num: usize, den: usize) -> f64 {
+    if den == 0 {
+        0.0
+    } else {
+        num as f64 / den as f64
+    }
+}
+
+/// Sort the counts of a `token_id_frequency_map` by decreasing frequency, and by increasing
+/// id for the same frequency
+pub fn sort_by_frequency(map: &HashMap<u32, usize>) -> Vec<(u32, usize)> {
+    let mut counts: Vec<_> = map.iter().map(|(id, count)| (*id, *count)).collect();
+    counts.sort_unstable_by(|(ia, ca), (ib, cb)| cb.cmp(ca).then(ia.cmp(ib)));
+    counts
+}
+
+impl Tokenizer {
+    /// Count the occurrences of each token id when encoding the lines of `corpus`, without
+    /// truncation, padding, or special tokens
+    pub fn token_id_frequency_map(&self, corpus: &str) -> Result<HashMap<u32, usize>> {
+        let mut counter = self.clone();
+        counter.with_truncation(None)?.with_padding(None);
+        let encodings = counter.encode_batch(corpus.lines().collect(), false)?;
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for id in encodings.iter().flat_map(|encoding| encoding.get_ids()) {
+            *counts.entry(*id).or_default() += 1;
+        }
+        Ok(counts)
+    }
+
+    /// Summarize the configuration of this tokenizer
+    pub fn describe(&self) -> TokenizerDescription {
+        let added_tokens = self.get_added_tokens_decoder();
+        TokenizerDescription {
+            model_type: self.get_model_type().to_owned(),
+            vocab_size: self.get_vocab_size(false),
+            num_added_tokens: added_tokens.len(),
+            num_special_tokens: added_tokens.values().filter(|t| t.special).count(),
+            normalizer: self.get_normalizer().and_then(component_type),
+            pre_tokenizer: self.get_pre_tokenizer().and_then(component_type),
+            post_processor: self.get_post_processor().and_then(component_type),
+            decoder: self.get_decoder().and_then(component_type),
+            padding: self.get_padding().map(|params| PaddingDescription {
+                length: match params.strategy {
+                    PaddingStrategy::Fixed(length) => Some(length),
+                    PaddingStrategy::BatchLongest => None,
+                },
+                pad_to_multiple_of: params.pad_to_multiple_of,
+                direction: params.direction.as_ref().to_owned(),
+                pad_token: params.pad_token.clone(),
+            }),
+            truncation: self.get_truncation().map(|params| TruncationDescription {
+                max_length: params.max_length,
+                stride: params.stride,
+                strategy: params.strategy.as_ref().to_owned(),
+                direction: params.direction.as_ref().to_owned(),
+            }),
+        }
+    }
+
+    /// Encode the given text without special tokens, and compute a `TokenizationReport`
+    pub fn tokenization_report(&self, text: &str) -> Result<TokenizationReport> {
+        let encoding = self.encode(text, false)?;
+        let unk_id = self.get_model().get_unk_id();
+
+        // Mark each byte of the text covered by a known token
+        let mut covered = vec![false; text.len()];
+        let mut unk_count = 0;
+        for (id, (start, end)) in encoding.get_ids().iter().zip(encoding.get_offsets()) {
+            if Some(*id) == unk_id {
+                unk_count += 1;
+            } else if let Some(bytes) = covered.get_mut(*start..*end) {
+                bytes.iter_mut().for_each(|b| *b = true);
+            }
+        }
+
+        let n_chars = text.chars().count();
+        let covered_chars = text
+            .char_indices()
+            .filter(|(start, _)| covered[*start])
+            .count();
+        let covered_bytes = covered.iter().filter(|b| **b).count();
+        let n_tokens = encoding.len();
+
+        Ok(TokenizationReport {
+            tokens: encoding.get_tokens().to_vec(),
+            ids: encoding.get_ids().to_vec(),
+            fertility: ratio(n_tokens, n_chars),
+            char_coverage: ratio(covered_chars, n_chars),
+            unk_count,
+            unk_rate: ratio(unk_count, n_tokens),
+            byte_coverage: ratio(covered_bytes, text.len()),
+        })
+    }
+
+    /// Compute the mean number of tokens per character over the given texts, encoded without
+    /// special tokens. Empty texts are ignored. The result can be given to
+    /// `set_fertility_estimate`.
+    pub fn calibrate_fertility(&self, sample_texts: &[&str]) -> Result<f64> {
+        let fertilities = sample_texts
+            .iter()
+            .filter(|text| !text.is_empty())
+            .map(|text| {
+                let encoding = self.encode(*text, false)?;
+                Ok(ratio(encoding.len(), text.chars().count()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ratio(1, fertilities.len()) * fertilities.iter().sum::<f64>())
+    }
+
+    /// Encode the given corpus without special tokens, and count the words (as given by the
+    /// pre-tokenizer) that produce at least one unknown token. Up to `unk_limit_examples` of
+    /// these words are kept as examples.
+    pub fn coverage(&self, test_corpus: &str, unk_limit_examples: usize) -> Result<CoverageReport> {
+        let encoding = self.encode(test_corpus, false)?;
+        let unk_id = self.get_model().get_unk_id();
+
+        // The span of each word, and whether it has an unknown token
+        let mut words: Vec<(Offsets, bool)> = vec![];
+        let mut last_word = None;
+        for ((word, id), (start, end)) in encoding
+            .get_word_ids()
+            .iter()
+            .zip(encoding.get_ids())
+            .zip(encoding.get_offsets())
+        {
+            if word.is_none() {
+                continue;
+            }
+            if *word != last_word {
+                words.push(((*start, *end), false));
+                last_word = *word;
+            }
+            if let Some(((_, word_end), has_unk)) = words.last_mut() {
+                *word_end = *end;
+                *has_unk |= Some(*id) == unk_id;
+            }
+        }
+
+        let unk_spans: Vec<_> = words
+            .iter()
+            .filter(|(_, has_unk)| *has_unk)
+            .map(|(span, _)| *span)
+            .collect();
+        let mut unk_examples: Vec<String> = vec![];
+        for (start, end) in &unk_spans {
+            if unk_examples.len() >= unk_limit_examples {
+                break;
+            }
+            let example = test_corpus.get(*start..*end).unwrap_or("");
+            if !unk_examples.iter().any(|e| e == example) {
+                unk_examples.push(example.to_owned());
+            }
+        }
+
+        Ok(CoverageReport {
+            total_words: words.len(),
+            unk_words: unk_spans.len(),
+            unk_fraction: ratio(unk_spans.len(), words.len()),
+            unk_examples,
+        })
+    }
+
+    /// Estimate the peak heap allocation, in bytes, of `encode_batch` on `batch_size` inputs of
+    /// up to `max_seq_len` characters, to pre-allocate buffers. This is meant as an upper
+    /// bound, for the worst case where each byte of the input (up to 4 per character) becomes
+    /// its own token, as with a byte-level BPE. The estimate is the sum of:
+    ///  - for each input, the tokens of its encoding and of its overflowing windows, each
+    ///    taking its share of the fields of the `Encoding` plus the bytes of the longest token
+    ///    of the vocabulary, doubled for the growth of the vectors,
+    ///  - for each input, `WORKING_BYTES_PER_INPUT_BYTE` per byte, for the strings and the
+    ///    alignments built while encoding,
+    ///  - the vocabulary lookup tables, with both directions of each entry.
+    ///
+    /// The number of tokens accounts for the special tokens of the post-processor, the
+    /// windows of the truncation and the padding. The inputs can only go beyond this bound
+    /// with a normalizer expanding them more than twice, like a decomposition of ligatures.
+    pub fn estimate_max_batch_memory(&self, batch_size: usize, max_seq_len: usize) -> usize {
+        let input_bytes = 4 * max_seq_len;
+        let n_added_tokens = self
+            .get_post_processor()
+            .map_or(0, |processor| processor.added_tokens(false));
+
+        // Each window holds up to `max_length` tokens, and moves by `max_length - stride`
+        // tokens of the input, the first one excepted
+        let mut window_len = input_bytes + n_added_tokens;
+        let mut windows = 1;
+        if let Some(trunc) = self.get_truncation() {
+            let step = trunc
+                .max_length
+                .saturating_sub(n_added_tokens)
+                .saturating_sub(trunc.stride);
+            if step > 0 && trunc.max_length < window_len {
+                window_len = trunc.max_length;
+                windows += input_bytes.div_ceil(step);
+            }
+        }
+        if let Some(padding) = self.get_padding() {
+            if let PaddingStrategy::Fixed(length) = padding.strategy {
+                window_len = window_len.max(length);
+            }
+            if let Some(multiple) = padding.pad_to_multiple_of.filter(|m| *m > 0) {
+                window_len = window_len.div_ceil(multiple) * multiple;
+            }
+        }
+
+        let max_token_len = self
+            .longest_token_in_vocab()
+            .map_or(0, |(_, _, len)| len)
+            .max(
+                self.get_padding()
+                    .map_or(0, |padding| padding.pad_token.len()),
+            );
+        let token_bytes = 4 * std::mem::size_of::<u32>()
+            + std::mem::size_of::<String>()
+            + std::mem::size_of::<Option<u32>>()
+            + std::mem::size_of::<Offsets>()
+            + max_token_len;
+        let encoding_bytes =
+            windows * (std::mem::size_of::<Encoding>() + 2 * window_len * token_bytes);
+        let working_bytes = input_bytes * WORKING_BYTES_PER_INPUT_BYTE;
+
+        let vocab_bytes: usize = self
+            .vocab_lengths()
+            .map(|(_, _, len)| {
+                2 * (len + std::mem::size_of::<String>() + std::mem::size_of::<u32>())
+            })
+            .sum();
+
+        batch_size * (encoding_bytes + working_bytes) + vocab_bytes
+    }
+
+    /// Compare the segmentation of `text` by this tokenizer and by `other`. Both encode the
+    /// text without special tokens.
+    pub fn compare_segmentations(
+        &self,
+        text: &str,
+        other: &Tokenizer,
+    ) -> Result<SegmentationComparison> {
+        let spans = |tokenizer: &Tokenizer| -> Result<BTreeSet<Offsets>> {
+            let encoding = tokenizer.encode(text, false)?;
+            Ok(encoding.get_offsets().iter().copied().collect())
+        };
+        let (self_spans, other_spans) = (spans(self)?, spans(other)?);
+
+        Ok(SegmentationComparison {
+            text: text.to_owned(),
+            shared_spans: self_spans.intersection(&other_spans).copied().collect(),
+            self_only_spans: self_spans.difference(&other_spans).copied().collect(),
+            other_only_spans: other_spans.difference(&self_spans).copied().collect(),
+        })
+    }
+
+    /// Compare the vocabulary of this tokenizer with the one of `other`, both with their
+    /// added tokens
+    pub fn shared_vocabulary_with(&self, other: &Tokenizer) -> SharedVocabReport {
+        let tokens = |tokenizer: &Tokenizer| -> BTreeSet<String> {
+            tokenizer.get_vocab(true).into_keys().collect()
+        };
+        let (self_tokens, other_tokens) = (tokens(self), tokens(other));
+        let shared_count = self_tokens.intersection(&other_tokens).count();
+
+        SharedVocabReport {
+            shared_count,
+            shared_fraction_self: ratio(shared_count, self_tokens.len()),
+            shared_fraction_other: ratio(shared_count, other_tokens.len()),
+            only_in_self: self_tokens.difference(&other_tokens).cloned().collect(),
+            only_in_other: other_tokens.difference(&self_tokens).cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::wordlevel::WordLevel;
+    use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+    use crate::tokenizer::test_utils::word_level_tokenizer;
+
+    #[test]
+    fn describe() {
+        use crate::decoders::wordpiece::WordPiece as WordPieceDecoder;
+        use crate::models::wordpiece::WordPiece;
+        use crate::normalizers::bert::BertNormalizer;
+        use crate::pre_tokenizers::bert::BertPreTokenizer;
+        use crate::processors::bert::BertProcessing;
+        use crate::{AddedToken, PaddingParams, TruncationParams};
+
+        let vocab: HashMap<String, u32> = ["[UNK]", "[CLS]", "[SEP]", "[PAD]", "hello", "##s"]
+            .iter()
+            .enumerate()
+            .map(|(id, token)| (token.to_string(), id as u32))
+            .collect();
+        let mut tokenizer = Tokenizer::new(WordPiece::builder().vocab(vocab).build().unwrap());
+        tokenizer
+            .with_normalizer(BertNormalizer::default())
+            .with_pre_tokenizer(BertPreTokenizer)
+            .with_post_processor(BertProcessing::new(
+                ("[SEP]".into(), 2),
+                ("[CLS]".into(), 1),
+            ))
+            .with_decoder(WordPieceDecoder::default())
+            .with_padding(Some(PaddingParams::default()))
+            .with_truncation(Some(TruncationParams {
+                max_length: 512,
+                ..Default::default()
+            }))
+            .unwrap();
+        tokenizer.add_special_tokens(&[
+            AddedToken::from("[CLS]", true),
+            AddedToken::from("[SEP]", true),
+            AddedToken::from("[PAD]", true),
+        ]);
+        tokenizer.add_tokens(&[AddedToken::from("world", false)]);
+
+        let description = tokenizer.describe();
+        assert_eq!(
+            description,
+            TokenizerDescription {
+                model_type: "WordPiece".into(),
+                vocab_size: 6,
+                num_added_tokens: 4,
+                num_special_tokens: 3,
+                normalizer: Some("BertNormalizer".into()),
+                pre_tokenizer: Some("BertPreTokenizer".into()),
+                post_processor: Some("BertProcessing".into()),
+                decoder: Some("WordPiece".into()),
+                padding: Some(PaddingDescription {
+                    length: None,
+                    pad_to_multiple_of: None,
+                    direction: "right".into(),
+                    pad_token: "[PAD]".into(),
+                }),
+                truncation: Some(TruncationDescription {
+                    max_length: 512,
+                    stride: 0,
+                    strategy: "longest_first".into(),
+                    direction: "right".into(),
+                }),
+            }
+        );
+        let json = serde_json::to_value(&description).unwrap();
+        assert_eq!(json["model_type"], "WordPiece");
+        assert_eq!(json["truncation"]["max_length"], 512);
+
+        let description = word_level_tokenizer().describe();
+        assert_eq!(description.normalizer, None);
+        assert_eq!(description.padding, None);
+        assert_eq!(description.num_added_tokens, 0);
+    }
+
+    #[test]
+    fn report() {
+        let report = word_level_tokenizer()
+            .tokenization_report("hello wörld !")
+            .unwrap();
+        assert_eq!(report.tokens, vec!["hello", "[UNK]", "!"]);
+        assert_eq!(report.ids, vec![1, 0, 3]);
+        assert_eq!(report.unk_count, 1);
+        assert_eq!(report.unk_rate, 1.0 / 3.0);
+        assert_eq!(report.fertility, 3.0 / 13.0);
+        // Whitespace and the unknown word are not covered
+        assert_eq!(report.char_coverage, 6.0 / 13.0);
+        assert_eq!(report.byte_coverage, 6.0 / 14.0);
+    }
+
+    #[test]
+    fn report_without_unknown() {
+        let report = word_level_tokenizer()
+            .tokenization_report("hello!")
+            .unwrap();
+        assert_eq!(report.unk_count, 0);
+        assert_eq!(report.unk_rate, 0.0);
+        assert_eq!(report.char_coverage, 1.0);
+        assert_eq!(report.byte_coverage, 1.0);
+
+        let empty = word_level_tokenizer().tokenization_report("").unwrap();
+        assert!(empty.tokens.is_empty());
+        assert_eq!(empty.fertility, 0.0);
+        assert_eq!(empty.char_coverage, 0.0);
+    }
+
+    #[test]
+    fn token_id_frequency_map() {
+        let corpus = "hello world !\nhello hello\n\nfoo world";
+        let counts = word_level_tokenizer()
+            .token_id_frequency_map(corpus)
+            .unwrap();
+        let expected: HashMap<u32, usize> =
+            vec![(0, 1), (1, 3), (2, 2), (3, 1)].into_iter().collect();
+        assert_eq!(counts, expected);
+        assert_eq!(
+            sort_by_frequency(&counts),
+            vec![(1, 3), (2, 2), (0, 1), (3, 1)]
+        );
+
+        assert!(word_level_tokenizer()
+            .token_id_frequency_map("")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn coverage() {
+        let corpus = "hello wörld ! hello wörld fôo";
+        let coverage = word_level_tokenizer().coverage(corpus, 5).unwrap();
+        assert_eq!(coverage.total_words, 6);
+        assert_eq!(coverage.unk_words, 3);
+        assert_eq!(coverage.unk_fraction, 0.5);
+        assert_eq!(coverage.unk_examples, vec!["wörld", "fôo"]);
+
+        let coverage = word_level_tokenizer().coverage(corpus, 1).unwrap();
+        assert_eq!(coverage.unk_examples, vec!["wörld"]);
+
+        let coverage = word_level_tokenizer().coverage("hello world", 5).unwrap();
+        assert_eq!(coverage.unk_words, 0);
+        assert_eq!(coverage.unk_fraction, 0.0);
+        assert!(coverage.unk_examples.is_empty());
+    }
+
+    #[test]
+    fn compare_segmentations() {
+        let mut other = word_level_tokenizer();
+        other.with_pre_tokenizer(WhitespaceSplit);
+
+        let comparison = word_level_tokenizer()
+            .compare_segmentations("hello world!", &other)
+            .unwrap();
+        assert_eq!(comparison.shared_spans, vec![(0, 5)]);
+        assert_eq!(comparison.self_only_spans, vec![(6, 11), (11, 12)]);
+        assert_eq!(comparison.other_only_spans, vec![(6, 12)]);
+        assert_eq!(
+            comparison.to_string(),
+            "  \"hello\" (0, 5)\n\
+             - \"world\" (6, 11)\n\
+             + \"world!\" (6, 12)\n\
+             - \"!\" (11, 12)\n"
+        );
+
+        let same = word_level_tokenizer()
+            .compare_segmentations("hello world!", &word_level_tokenizer())
+            .unwrap();
+        assert_eq!(same.shared_spans.len(), 3);
+        assert!(same.self_only_spans.is_empty() && same.other_only_spans.is_empty());
+    }
+
+    #[test]
+    fn shared_vocabulary_with() {
+        let same = word_level_tokenizer().shared_vocabulary_with(&word_level_tokenizer());
+        assert_eq!(same.shared_count, 4);
+        assert_eq!(same.shared_fraction_self, 1.0);
+        assert_eq!(same.shared_fraction_other, 1.0);
+        assert!(same.only_in_self.is_empty() && same.only_in_other.is_empty());
+
+        let vocab: HashMap<String, u32> = [("<unk>", 0), ("bonjour", 1)]
+            .iter()
+            .map(|(token, id)| (token.to_string(), *id))
+            .collect();
+        let disjoint = Tokenizer::new(WordLevel::builder().vocab(vocab).build().unwrap());
+        let report = word_level_tokenizer().shared_vocabulary_with(&disjoint);
+        assert_eq!(report.shared_count, 0);
+        assert_eq!(report.shared_fraction_self, 0.0);
+        assert_eq!(report.shared_fraction_other, 0.0);
+        assert_eq!(report.only_in_self, vec!["!", "[UNK]", "hello", "world"]);
+        assert_eq!(report.only_in_other, vec!["<unk>", "bonjour"]);
+
+        // The added tokens are part of the vocabulary
+        let mut other = word_level_tokenizer();
+        other.add_tokens(&[crate::AddedToken::from("planet", false)]);
+        let report = word_level_tokenizer().shared_vocabulary_with(&other);
+        assert_eq!(report.shared_count, 4);
+        assert_eq!(report.shared_fraction_self, 1.0);
+        assert_eq!(report.shared_fraction_other, 0.8);
+        assert_eq!(report.only_in_other, vec!["planet"]);
+    }
+
+    #[test]
+    fn fertility_estimate() {
+        let mut tokenizer = word_level_tokenizer();
+        assert_eq!(tokenizer.estimate_tokens(100), 25);
+
+        // 2 tokens for 11 chars, and 3 tokens for 12 chars
+        let fertility = tokenizer
+            .calibrate_fertility(&["hello world", "hello world!", ""])
+            .unwrap();
+        assert_eq!(fertility, (2.0 / 11.0 + 3.0 / 12.0) / 2.0);
+        tokenizer.set_fertility_estimate(fertility);
+        assert_eq!(tokenizer.estimate_tokens(12), 3);
+        assert_eq!(tokenizer.estimate_tokens(0), 0);
+        assert_eq!(tokenizer.calibrate_fertility(&[]).unwrap(), 0.0);
+    }
+}
@@ -8,10 +8,93 @@ use serde::{
 };
 
 use super::{added_vocabulary::AddedTokenWithId, TokenizerImpl};
-use crate::{Decoder, Model, Normalizer, PostProcessor, PreTokenizer, TokenizerBuilder};
+use crate::{
+    Decoder, FallbackTokenizer, Model, Normalizer, PostProcessor, PreTokenizer, TokenizerBuilder,
+};
 
 static SERIALIZATION_VERSION: &str = "1.0";
 
+/// Emitted as a warning when deserializing a tokenizer of another version than the supported
+/// one. Its fields get loaded on a best-effort basis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionWarning {
+    pub found_version: String,
+    pub supported_version: String,
+}
+
+impl std::fmt::Display for VersionWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Loading a tokenizer of version '{}' while the supported version is '{}', some of its \
+             parts may be ignored or fail to load",
+            self.found_version, self.supported_version
+        )
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DeserializationError {
+    /// A tokenizer of another version than the supported one could not be loaded
+    #[error("Could not load the tokenizer of version '{found_version}', the supported version is '{supported_version}': {source}")]
+    VersionMismatch {
+        found_version: String,
+        supported_version: String,
+        source: serde_json::Error,
+    },
+}
+
+/// Turn the error of deserializing the tokenizer in `json` into a `VersionMismatch` when
+/// `json` is of an unsupported version
+pub(crate) fn check_version<T>(json: &[u8], result: serde_json::Result<T>) -> crate::Result<T> {
+    #[derive(Deserialize)]
+    struct Versioned {
+        version: Option<String>,
+    }
+
+    check_found_version(
+        || {
+            serde_json::from_slice::<Versioned>(json)
+                .ok()
+                .and_then(|versioned| versioned.version)
+        },
+        result,
+    )
+}
+
+/// Same as `check_version`, for a tokenizer deserialized from a `serde_json::Value`
+pub(crate) fn check_value_version<T>(
+    value: &serde_json::Value,
+    result: serde_json::Result<T>,
+) -> crate::Result<T> {
+    check_found_version(
+        || {
+            value
+                .get("version")
+                .and_then(|version| version.as_str())
+                .map(str::to_owned)
+        },
+        result,
+    )
+}
+
+fn check_found_version<T, F>(found_version: F, result: serde_json::Result<T>) -> crate::Result<T>
+where
+    F: FnOnce() -> Option<String>,
+{
+    result.map_err(|source| match found_version() {
+        Some(found_version) if found_version != SERIALIZATION_VERSION => {
+            DeserializationError::VersionMismatch {
+                found_version,
+                supported_version: SERIALIZATION_VERSION.to_owned(),
+                source,
+            }
+            .into()
+        }
+        _ => source.into(),
+    })
+}
+
 impl<M, N, PT, PP, D> Serialize for TokenizerImpl<M, N, PT, PP, D>
 where
     M: Serialize,
@@ -24,7 +107,8 @@ where
     where
         S: Serializer,
     {
-        let mut tokenizer = serializer.serialize_struct("Tokenizer", 9)?;
+        let len = 9 + self.fallback_tokenizer.is_some() as usize;
+        let mut tokenizer = serializer.serialize_struct("Tokenizer", len)?;
 
         // Start by adding the current version
         tokenizer.serialize_field("version", SERIALIZATION_VERSION)?;
@@ -43,10 +127,20 @@ where
         tokenizer.serialize_field("decoder", &self.decoder)?;
         tokenizer.serialize_field("model", &self.model)?;
 
+        // The fallback tokenizer is only there when set, like in the files of older versions
+        match &self.fallback_tokenizer {
+            Some(fallback) => tokenizer.serialize_field("fallback_tokenizer", fallback)?,
+            None => tokenizer.skip_field("fallback_tokenizer")?,
+        }
+
         tokenizer.end()
     }
 }
 
+/// Tokenizers of another version are loaded on a best-effort basis with a `VersionWarning`. When
+/// deserialized inside of another type, their errors can't become a `VersionMismatch`: only the
+/// constructors like `Tokenizer::from_str`, `Tokenizer::from_bytes` or
+/// `Tokenizer::try_from(serde_json::Value)` check the version of a tokenizer that fails to load.
 impl<'de, M, N, PT, PP, D> Deserialize<'de> for TokenizerImpl<M, N, PT, PP, D>
 where
     M: Deserialize<'de> + Model,
@@ -71,6 +165,7 @@ where
                 "post_processor",
                 "decoder",
                 "model",
+                "fallback_tokenizer",
             ],
             TokenizerVisitor(
                 PhantomData,
@@ -111,12 +206,17 @@ where
     {
         let mut builder = TokenizerBuilder::new();
         let mut tokens: Vec<AddedTokenWithId> = vec![];
+        let mut fallback: Option<FallbackTokenizer> = None;
         while let Some(key) = map.next_key::<String>()? {
             match key.as_ref() {
                 "version" => {
                     let v: String = map.next_value()?;
-                    if &v != "1.0" {
-                        return Err(Error::custom(format!("Unknown tokenizer version '{}'", v)));
+                    if v != SERIALIZATION_VERSION {
+                        let warning = VersionWarning {
+                            found_version: v,
+                            supported_version: SERIALIZATION_VERSION.to_owned(),
+                        };
+                        warn!("{}", warning);
                     }
                 }
                 "truncation" => {
@@ -143,6 +243,9 @@ where
                 "post_processor" => {
                     builder = builder.with_post_processor(map.next_value()?);
                 }
+                "fallback_tokenizer" => {
+                    fallback = map.next_value()?;
+                }
                 _ => {}
             };
         }
@@ -166,8 +269,21 @@ where
                 }
             }
         }
-        let added_tokens: Vec<_> = tokens.into_iter().map(|token| token.token).collect();
+        // The ids of the fallback are reserved, so the added tokens need their saved ids
+        let added_tokens: Vec<_> = tokens
+            .into_iter()
+            .map(|token| match &fallback {
+                Some(_) => token.token.id(Some(token.id)),
+                None => token.token,
+            })
+            .collect();
+        if let Some(fallback) = &fallback {
+            tokenizer
+                .added_vocabulary
+                .reserve_ids_until(fallback.ids_end());
+        }
         tokenizer.add_tokens(&added_tokens[..]);
+        tokenizer.fallback_tokenizer = fallback;
 
         Ok(tokenizer)
     }
@@ -233,6 +349,109 @@ mod tests {
         assert_eq!(tok_str, tok_json);
     }
 
+    #[test]
+    fn other_versions() {
+        use crate::tokenizer::DeserializationError;
+
+        let tokenizer = |version: &str, model_type: &str| {
+            format!(
+                r#"{{"version":"{}","truncation":null,"padding":null,"added_tokens":[],"normalizer":null,"pre_tokenizer":null,"post_processor":null,"decoder":null,"model":{{"type":"{}","vocab":{{"[UNK]":0,"hello":1}},"unk_token":"[UNK]"}}}}"#,
+                version, model_type
+            )
+        };
+
+        for version in ["0.99", "2.0"] {
+            // Loaded on a best-effort basis
+            let loaded = Tokenizer::from_str(&tokenizer(version, "WordLevel")).unwrap();
+            assert_eq!(loaded.token_to_id("hello"), Some(1));
+            let loaded = Tokenizer::from_bytes(tokenizer(version, "WordLevel")).unwrap();
+            assert_eq!(loaded.token_to_id("hello"), Some(1));
+
+            let err = Tokenizer::from_str(&tokenizer(version, "FutureModel")).unwrap_err();
+            match err.downcast_ref::<DeserializationError>() {
+                Some(DeserializationError::VersionMismatch {
+                    found_version,
+                    supported_version,
+                    ..
+                }) => {
+                    assert_eq!(found_version, version);
+                    assert_eq!(supported_version, "1.0");
+                }
+                _ => panic!("Unexpected error {}", err),
+            }
+        }
+
+        // The errors of the supported version are left as they are
+        let err = Tokenizer::from_str(&tokenizer("1.0", "FutureModel")).unwrap_err();
+        assert!(err.downcast_ref::<serde_json::Error>().is_some());
+
+        // Same when loading from a `serde_json::Value`
+        use std::convert::TryFrom;
+        let value = |version: &str, model_type: &str| -> serde_json::Value {
+            serde_json::from_str(&tokenizer(version, model_type)).unwrap()
+        };
+        let loaded = Tokenizer::try_from(value("2.0", "WordLevel")).unwrap();
+        assert_eq!(loaded.token_to_id("hello"), Some(1));
+        let err = Tokenizer::try_from(value("2.0", "FutureModel")).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<DeserializationError>(),
+            Some(DeserializationError::VersionMismatch { found_version, .. }) if found_version == "2.0"
+        ));
+        let err = Tokenizer::try_from(&value("0.99", "FutureModel")).unwrap_err();
+        assert!(err.downcast_ref::<DeserializationError>().is_some());
+        let err = Tokenizer::try_from(&value("1.0", "FutureModel")).unwrap_err();
+        assert!(err.downcast_ref::<serde_json::Error>().is_some());
+    }
+
+    #[test]
+    fn test_embedded_in_config() {
+        use crate::models::wordlevel::WordLevel;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+        use crate::AddedToken;
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize)]
+        struct Config {
+            name: String,
+            tokenizer: Tokenizer,
+        }
+
+        let vocab = [("[UNK]", 0), ("hello", 1), ("world", 2)]
+            .iter()
+            .map(|(token, id)| (token.to_string(), *id))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Whitespace {});
+        tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true)]);
+
+        let config = Config {
+            name: "config".into(),
+            tokenizer,
+        };
+        let serialized = serde_json::to_value(&config).unwrap();
+        // The tokenizer is inlined as an object, in the same format as its own files
+        let expected: serde_json::Value =
+            serde_json::from_str(&config.tokenizer.to_string(false).unwrap()).unwrap();
+        assert_eq!(serialized["tokenizer"], expected);
+
+        let deserialized: Config = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized.name, "config");
+        assert_eq!(
+            deserialized.tokenizer.to_string(false).unwrap(),
+            config.tokenizer.to_string(false).unwrap()
+        );
+        let encoding = deserialized
+            .tokenizer
+            .encode("hello [CLS] planet", false)
+            .unwrap();
+        assert_eq!(encoding.get_ids(), &[1, 3, 0]);
+    }
+
     #[cfg(feature = "http")]
     #[test]
     fn test_from_pretrained() {
@@ -0,0 +1,21 @@
+//! Fixtures shared by the unit tests of the tokenizer modules.
+use crate::models::wordlevel::WordLevel;
+use crate::pre_tokenizers::whitespace::Whitespace;
+use crate::Tokenizer;
+use std::collections::HashMap;
+
+/// A WordLevel tokenizer knowing `[UNK]`, `hello`, `world` and `!`, split on whitespaces
+pub(crate) fn word_level_tokenizer() -> Tokenizer {
+    let vocab: HashMap<String, u32> = [("[UNK]", 0), ("hello", 1), ("world", 2), ("!", 3)]
+        .iter()
+        .map(|(token, id)| (token.to_string(), *id))
+        .collect();
+    let model = WordLevel::builder()
+        .vocab(vocab)
+        .unk_token("[UNK]".into())
+        .build()
+        .unwrap();
+    let mut tokenizer = Tokenizer::new(model);
+    tokenizer.with_pre_tokenizer(Whitespace {});
+    tokenizer
+}
@@ -0,0 +1,109 @@
+use crate::{AddedToken, Result};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// An added token read from one of the sidecar files, with the id it expects if any
+pub(crate) type SidecarToken = (Option<u32>, AddedToken);
+
+fn read_json(path: &Path) -> Result<Option<Value>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&read_to_string(path)?)?))
+}
+
+/// Parse a token given either as a plain string or as a dict of `AddedToken` attributes
+pub(crate) fn parse_token(value: &Value, special: bool) -> Option<AddedToken> {
+    match value {
+        Value::String(content) => Some(AddedToken::from(content.as_str(), special)),
+        Value::Object(attributes) => {
+            let content = attributes.get("content")?.as_str()?;
+            let special = attributes
+                .get("special")
+                .and_then(Value::as_bool)
+                .unwrap_or(special);
+            let mut token = AddedToken::from(content, special);
+            let flag = |name: &str, default: bool| {
+                attributes
+                    .get(name)
+                    .and_then(Value::as_bool)
+                    .unwrap_or(default)
+            };
+            token.single_word = flag("single_word", token.single_word);
+            token.lstrip = flag("lstrip", token.lstrip);
+            token.rstrip = flag("rstrip", token.rstrip);
+            token.normalized = flag("normalized", token.normalized);
+            Some(token)
+        }
+        _ => None,
+    }
+}
+
+/// The attributes of a `tokenizer_config.json` holding special tokens
+const SPECIAL_TOKENS_ATTRIBUTES: [&str; 8] = [
+    "bos_token",
+    "eos_token",
+    "unk_token",
+    "sep_token",
+    "pad_token",
+    "cls_token",
+    "mask_token",
+    "additional_special_tokens",
+];
+
+/// Parse the special tokens of a `tokenizer_config.json`, in the order of
+/// `SPECIAL_TOKENS_ATTRIBUTES`
+pub(crate) fn config_special_tokens(config: &Value) -> Vec<AddedToken> {
+    SPECIAL_TOKENS_ATTRIBUTES
+        .iter()
+        .filter_map(|attribute| config.get(attribute))
+        .flat_map(|value| match value {
+            Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        })
+        .filter_map(|value| parse_token(value, true))
+        .collect()
+}
+
+/// Read the added tokens from the optional `tokenizer_config.json`, `special_tokens_map.json`
+/// and `added_tokens.json` files of `dir`, in this order.
+pub(crate) fn sidecar_tokens(dir: &Path) -> Result<Vec<SidecarToken>> {
+    let mut tokens = vec![];
+
+    if let Some(config) = read_json(&dir.join("tokenizer_config.json"))? {
+        if let Some(Value::Object(decoder)) = config.get("added_tokens_decoder") {
+            for (id, value) in decoder {
+                if let (Ok(id), Some(token)) = (id.parse::<u32>(), parse_token(value, false)) {
+                    tokens.push((Some(id), token));
+                }
+            }
+        }
+    }
+
+    let mut special_contents = HashSet::new();
+    if let Some(Value::Object(map)) = read_json(&dir.join("special_tokens_map.json"))? {
+        for value in map.values() {
+            let values = match value {
+                Value::Array(values) => values.iter().collect(),
+                value => vec![value],
+            };
+            for token in values.into_iter().filter_map(|v| parse_token(v, true)) {
+                special_contents.insert(token.content.clone());
+                tokens.push((None, token));
+            }
+        }
+    }
+
+    if let Some(Value::Object(map)) = read_json(&dir.join("added_tokens.json"))? {
+        for (content, id) in map {
+            if let Some(id) = id.as_u64() {
+                let special = special_contents.contains(&content);
+                tokens.push((Some(id as u32), AddedToken::from(content, special)));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
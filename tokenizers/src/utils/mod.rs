@@ -1,4 +1,5 @@
 pub(crate) mod cache;
+pub(crate) mod from_dir;
 #[cfg(feature = "http")]
 pub(crate) mod from_pretrained;
 
@@ -15,6 +16,8 @@ pub mod iter;
 pub mod padding;
 pub mod parallelism;
 pub(crate) mod progress;
+pub(crate) mod sentencepiece;
+pub(crate) mod tiktoken;
 pub mod truncation;
 
 use serde::{Serialize, Serializer};
@@ -0,0 +1,422 @@
+//! A minimal reader of the SentencePiece `ModelProto` protobuf, limited to the fields needed to
+//! build an equivalent `Tokenizer`.
+use crate::decoders::byte_fallback::ByteFallback;
+use crate::decoders::sequence::Sequence as DecoderSequence;
+use crate::decoders::DecoderWrapper;
+use crate::models::bpe::BPE;
+use crate::models::unigram::Unigram;
+use crate::normalizers::precompiled::Precompiled;
+use crate::normalizers::{NormalizerWrapper, RegexReplace, Sequence, Strip};
+use crate::pre_tokenizers::metaspace::{Metaspace, PrependScheme};
+use crate::{AddedToken, Result, Tokenizer};
+use std::collections::HashMap;
+
+/// The value of a protobuf field, by wire type
+enum Value<'a> {
+    Varint(u64),
+    Fixed64,
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+/// Iterates over the `(field_number, value)` of a protobuf message
+struct Fields<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (byte, rest) = self
+                .buf
+                .split_first()
+                .ok_or("Truncated varint in SentencePiece model")?;
+            self.buf = rest;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Invalid varint in SentencePiece model".into())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.buf.len() < len {
+            return Err("Truncated field in SentencePiece model".into());
+        }
+        let (bytes, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    fn field(&mut self) -> Result<(u64, Value<'a>)> {
+        let key = self.varint()?;
+        let value = match key & 0x7 {
+            0 => Value::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                Value::Fixed64
+            }
+            2 => {
+                let len = self.varint()? as usize;
+                Value::Bytes(self.take(len)?)
+            }
+            5 => {
+                let bytes = self.take(4)?;
+                Value::Fixed32(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            wire_type => {
+                return Err(
+                    format!("Unsupported wire type {} in SentencePiece model", wire_type).into(),
+                )
+            }
+        };
+        Ok((key >> 3, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u64, Value<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            None
+        } else {
+            Some(self.field())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PieceType {
+    Normal,
+    Unknown,
+    Control,
+    UserDefined,
+    Unused,
+    Byte,
+}
+
+struct Piece {
+    piece: String,
+    score: f32,
+    piece_type: PieceType,
+}
+
+/// The parts of a `ModelProto` we use, with the defaults of SentencePiece
+struct ModelProto {
+    pieces: Vec<Piece>,
+    /// `1` for Unigram, `2` for BPE
+    model_type: u64,
+    unk_id: usize,
+    byte_fallback: bool,
+    precompiled_charsmap: Vec<u8>,
+    add_dummy_prefix: bool,
+    remove_extra_whitespaces: bool,
+}
+
+fn parse_piece(bytes: &[u8]) -> Result<Piece> {
+    let mut piece = Piece {
+        piece: String::new(),
+        score: 0.0,
+        piece_type: PieceType::Normal,
+    };
+    for field in Fields::new(bytes) {
+        match field? {
+            (1, Value::Bytes(bytes)) => piece.piece = String::from_utf8(bytes.to_vec())?,
+            (2, Value::Fixed32(bits)) => piece.score = f32::from_bits(bits),
+            (3, Value::Varint(t)) => {
+                piece.piece_type = match t {
+                    2 => PieceType::Unknown,
+                    3 => PieceType::Control,
+                    4 => PieceType::UserDefined,
+                    5 => PieceType::Unused,
+                    6 => PieceType::Byte,
+                    _ => PieceType::Normal,
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(piece)
+}
+
+fn parse_model(bytes: &[u8]) -> Result<ModelProto> {
+    let mut model = ModelProto {
+        pieces: vec![],
+        model_type: 1,
+        unk_id: 0,
+        byte_fallback: false,
+        precompiled_charsmap: vec![],
+        add_dummy_prefix: true,
+        remove_extra_whitespaces: true,
+    };
+    for field in Fields::new(bytes) {
+        match field? {
+            (1, Value::Bytes(bytes)) => model.pieces.push(parse_piece(bytes)?),
+            // TrainerSpec
+            (2, Value::Bytes(bytes)) => {
+                for field in Fields::new(bytes) {
+                    match field? {
+                        (3, Value::Varint(model_type)) => model.model_type = model_type,
+                        (35, Value::Varint(byte_fallback)) => {
+                            model.byte_fallback = byte_fallback != 0
+                        }
+                        (40, Value::Varint(unk_id)) => model.unk_id = unk_id as usize,
+                        _ => {}
+                    }
+                }
+            }
+            // NormalizerSpec
+            (3, Value::Bytes(bytes)) => {
+                for field in Fields::new(bytes) {
+                    match field? {
+                        (2, Value::Bytes(charsmap)) => {
+                            model.precompiled_charsmap = charsmap.to_vec()
+                        }
+                        (3, Value::Varint(v)) => model.add_dummy_prefix = v != 0,
+                        (4, Value::Varint(v)) => model.remove_extra_whitespaces = v != 0,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if model.unk_id >= model.pieces.len() {
+        return Err("The unknown piece of the SentencePiece model is missing".into());
+    }
+    Ok(model)
+}
+
+/// SentencePiece BPE models don't store their merges, so we rebuild them from the vocabulary:
+/// each piece is the merge of any two pieces that can be concatenated into it, ordered by the
+/// id of the piece first.
+fn bpe_merges(vocab: &HashMap<String, u32>) -> Vec<(String, String)> {
+    let mut merges: Vec<_> = vocab
+        .iter()
+        .flat_map(|(piece, id)| {
+            piece.char_indices().skip(1).filter_map(move |(i, _)| {
+                let (left, right) = piece.split_at(i);
+                Some(((*id, *vocab.get(left)?, *vocab.get(right)?), (left, right)))
+            })
+        })
+        .collect();
+    merges.sort_unstable();
+    merges
+        .into_iter()
+        .map(|(_, (left, right))| (left.to_owned(), right.to_owned()))
+        .collect()
+}
+
+/// Build a `Tokenizer` equivalent to the given SentencePiece model, which must be a `"bpe"` or
+/// `"unigram"` one.
+pub(crate) fn from_model(bytes: &[u8], model_type: &str) -> Result<Tokenizer> {
+    let model = parse_model(bytes)?;
+    let expected_type = match model_type {
+        "unigram" => 1,
+        "bpe" => 2,
+        _ => {
+            return Err(format!(
+                "Unsupported SentencePiece model type `{}`, expected `bpe` or `unigram`",
+                model_type
+            )
+            .into())
+        }
+    };
+    if model.model_type != expected_type {
+        return Err(format!("This SentencePiece model is not a `{}` one", model_type).into());
+    }
+
+    let mut tokenizer = if model_type == "unigram" {
+        let vocab = model
+            .pieces
+            .iter()
+            .map(|p| (p.piece.clone(), f64::from(p.score)))
+            .collect();
+        Tokenizer::new(Unigram::from(
+            vocab,
+            Some(model.unk_id),
+            model.byte_fallback,
+        )?)
+    } else {
+        let vocab: HashMap<String, u32> = model
+            .pieces
+            .iter()
+            .enumerate()
+            .map(|(id, p)| (p.piece.clone(), id as u32))
+            .collect();
+        let merges = bpe_merges(&vocab);
+        Tokenizer::new(
+            BPE::builder()
+                .vocab_and_merges(vocab, merges)
+                .unk_token(model.pieces[model.unk_id].piece.clone())
+                .fuse_unk(true)
+                .byte_fallback(model.byte_fallback)
+                .build()?,
+        )
+    };
+
+    let mut normalizers: Vec<NormalizerWrapper> = vec![];
+    if !model.precompiled_charsmap.is_empty() {
+        normalizers.push(Precompiled::from(&model.precompiled_charsmap)?.into());
+    }
+    if model.remove_extra_whitespaces {
+        normalizers.push(Strip::new(true, true).into());
+        normalizers.push(RegexReplace::new(" {2,}", " ")?.into());
+    }
+    if !normalizers.is_empty() {
+        tokenizer.with_normalizer(Sequence::new(normalizers));
+    }
+
+    let prepend_scheme = if model.add_dummy_prefix {
+        PrependScheme::Always
+    } else {
+        PrependScheme::Never
+    };
+    let metaspace = Metaspace::new('▁', prepend_scheme, true);
+    tokenizer.with_pre_tokenizer(metaspace.clone());
+    if model.byte_fallback {
+        tokenizer.with_decoder(DecoderSequence::new(vec![
+            DecoderWrapper::ByteFallback(ByteFallback::new()),
+            DecoderWrapper::Metaspace(metaspace),
+        ]));
+    } else {
+        tokenizer.with_decoder(metaspace);
+    }
+
+    let special_tokens: Vec<_> = model
+        .pieces
+        .iter()
+        .filter(|p| matches!(p.piece_type, PieceType::Control | PieceType::Unknown))
+        .map(|p| AddedToken::from(p.piece.clone(), true))
+        .collect();
+    tokenizer.add_special_tokens(&special_tokens);
+    let user_defined: Vec<_> = model
+        .pieces
+        .iter()
+        .filter(|p| p.piece_type == PieceType::UserDefined)
+        .map(|p| AddedToken::from(p.piece.clone(), false))
+        .collect();
+    tokenizer.add_tokens(&user_defined);
+
+    Ok(tokenizer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn bytes_field(number: u64, bytes: &[u8], out: &mut Vec<u8>) {
+        varint(number << 3 | 2, out);
+        varint(bytes.len() as u64, out);
+        out.extend(bytes);
+    }
+
+    fn varint_field(number: u64, value: u64, out: &mut Vec<u8>) {
+        varint(number << 3, out);
+        varint(value, out);
+    }
+
+    fn model(pieces: &[(&str, f32, u64)], model_type: u64) -> Vec<u8> {
+        let mut out = vec![];
+        for (piece, score, piece_type) in pieces {
+            let mut p = vec![];
+            bytes_field(1, piece.as_bytes(), &mut p);
+            varint(2 << 3 | 5, &mut p);
+            p.extend(score.to_le_bytes());
+            varint_field(3, *piece_type, &mut p);
+            bytes_field(1, &p, &mut out);
+        }
+        let mut trainer_spec = vec![];
+        varint_field(3, model_type, &mut trainer_spec);
+        varint_field(40, 0, &mut trainer_spec);
+        bytes_field(2, &trainer_spec, &mut out);
+        out
+    }
+
+    #[test]
+    fn unigram() {
+        let bytes = model(
+            &[
+                ("<unk>", 0.0, 2),
+                ("</s>", 0.0, 3),
+                ("▁hello", -1.0, 1),
+                ("▁world", -2.0, 1),
+                ("▁", -3.0, 1),
+                ("o", -4.0, 1),
+            ],
+            1,
+        );
+        let tokenizer = from_model(&bytes, "unigram").unwrap();
+        assert_eq!(tokenizer.get_model_type(), "Unigram");
+
+        let encoding = tokenizer.encode("hello   world</s>", false).unwrap();
+        assert_eq!(encoding.get_tokens(), ["▁hello", "▁world", "</s>"]);
+        assert_eq!(encoding.get_ids(), [2, 3, 1]);
+        assert_eq!(tokenizer.decode(&[2, 3, 5], true).unwrap(), "hello worldo");
+
+        assert!(from_model(&bytes, "bpe").is_err());
+        assert!(from_model(&bytes, "word").is_err());
+        assert!(from_model(&bytes[..bytes.len() - 1], "unigram").is_err());
+    }
+
+    #[test]
+    fn bpe() {
+        let pieces: Vec<(&str, f32, u64)> = [
+            "<unk>", "he", "ll", "llo", "hello", "▁hello", "▁", "h", "e", "l", "o",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, piece)| (*piece, -(i as f32), if i == 0 { 2 } else { 1 }))
+        .collect();
+        let tokenizer = from_model(&model(&pieces, 2), "bpe").unwrap();
+        assert_eq!(tokenizer.get_model_type(), "BPE");
+
+        let encoding = tokenizer.encode("hello hell", false).unwrap();
+        assert_eq!(encoding.get_tokens(), ["▁hello", "▁", "he", "ll"]);
+        assert_eq!(
+            tokenizer.decode(encoding.get_ids(), true).unwrap(),
+            "hello hell"
+        );
+    }
+
+    #[test]
+    fn save_and_reload() {
+        let pieces: Vec<(&str, f32, u64)> = ["<unk>", "he", "ll", "▁he", "▁", "h", "e", "l"]
+            .iter()
+            .enumerate()
+            .map(|(i, piece)| (*piece, -(i as f32), if i == 0 { 2 } else { 1 }))
+            .collect();
+        let tokenizer = from_model(&model(&pieces, 2), "bpe").unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokenizer.json");
+        tokenizer.save(&path, false).unwrap();
+        let reloaded = Tokenizer::from_file(&path).unwrap();
+
+        for input in ["  hell   he ", "héll"] {
+            let expected = tokenizer.encode(input, false).unwrap();
+            let encoding = reloaded.encode(input, false).unwrap();
+            assert_eq!(encoding.get_tokens(), expected.get_tokens());
+            assert_eq!(encoding.get_offsets(), expected.get_offsets());
+        }
+        assert_eq!(
+            reloaded.encode("  hell   he ", false).unwrap().get_tokens(),
+            ["▁he", "ll", "▁he"]
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Builds the `Tokenizer` of the OpenAI encodings from their tiktoken rank files.
+use crate::models::bpe::BPE;
+use crate::normalizer::SplitDelimiterBehavior;
+use crate::pre_tokenizers::byte_level::{bytes_char, ByteLevel};
+use crate::pre_tokenizers::sequence::Sequence;
+use crate::pre_tokenizers::split::{Split, SplitPattern};
+use crate::{AddedToken, Result, Tokenizer};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use std::collections::HashMap;
+
+#[derive(thiserror::Error, Debug)]
+pub enum TiktokenError {
+    /// The encoding is not one of the built-in ones
+    #[error("Unknown tiktoken encoding `{0}`, expected one of `cl100k_base`, `p50k_base` or `r50k_base`")]
+    UnknownTiktokenEncoding(String),
+    /// When the rank file is in the wrong format. This error holds the line number of the line
+    /// that caused the error.
+    #[error("Tiktoken rank file invalid at line {0}")]
+    BadRankFile(usize),
+}
+
+/// What an encoding needs besides its ranks
+struct EncodingSpec {
+    pattern: &'static str,
+    special_tokens: &'static [(&'static str, u32)],
+}
+
+const GPT2_PATTERN: &str =
+    r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+";
+
+fn encoding_spec(encoding_name: &str) -> Option<EncodingSpec> {
+    match encoding_name {
+        "cl100k_base" => Some(EncodingSpec {
+            pattern: r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+",
+            special_tokens: &[
+                ("<|endoftext|>", 100257),
+                ("<|fim_prefix|>", 100258),
+                ("<|fim_middle|>", 100259),
+                ("<|fim_suffix|>", 100260),
+                ("<|endofprompt|>", 100276),
+            ],
+        }),
+        "p50k_base" | "r50k_base" => Some(EncodingSpec {
+            pattern: GPT2_PATTERN,
+            special_tokens: &[("<|endoftext|>", 50256)],
+        }),
+        _ => None,
+    }
+}
+
+/// Parse the lines `{base64 token} {rank}` of a rank file
+fn parse_ranks(ranks: &[u8]) -> Result<Vec<(Vec<u8>, u32)>> {
+    let content = std::str::from_utf8(ranks)?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            let bad_line = || TiktokenError::BadRankFile(i + 1);
+            let (token, rank) = line.split_once(' ').ok_or_else(bad_line)?;
+            let token = STANDARD.decode(token).map_err(|_| bad_line())?;
+            let rank = rank.parse().map_err(|_| bad_line())?;
+            Ok((token, rank))
+        })
+        .collect()
+}
+
+/// The two parts merged into `token` by tiktoken, which merges the pair giving the lowest rank
+/// first: running these merges with the ranks lower than the one of `token` must leave exactly
+/// two parts. The tokens that can't be built this way are only reached as whole words.
+fn merge_parts<'a>(
+    token: &'a [u8],
+    rank: u32,
+    ranks: &HashMap<&[u8], u32>,
+) -> Option<(&'a [u8], &'a [u8])> {
+    let mut parts: Vec<&[u8]> = token.chunks(1).collect();
+    loop {
+        let best = (0..parts.len().saturating_sub(1))
+            .filter_map(|i| {
+                let merged = &token
+                    [offset(token, parts[i])..offset(token, parts[i + 1]) + parts[i + 1].len()];
+                ranks
+                    .get(merged)
+                    .filter(|merged_rank| **merged_rank < rank)
+                    .map(|merged_rank| (*merged_rank, i, merged))
+            })
+            .min();
+        match best {
+            Some((_, i, merged)) => {
+                parts[i] = merged;
+                parts.remove(i + 1);
+            }
+            None => break,
+        }
+    }
+    match parts[..] {
+        [a, b] => Some((a, b)),
+        _ => None,
+    }
+}
+
+/// The position of `part` in `token`, of which it is a slice
+fn offset(token: &[u8], part: &[u8]) -> usize {
+    part.as_ptr() as usize - token.as_ptr() as usize
+}
+
+/// Build the `Tokenizer` of the given encoding from the content of its `.tiktoken` rank file
+pub(crate) fn from_ranks(encoding_name: &str, ranks: &[u8]) -> Result<Tokenizer> {
+    let spec = encoding_spec(encoding_name)
+        .ok_or_else(|| TiktokenError::UnknownTiktokenEncoding(encoding_name.to_owned()))?;
+    let ranks = parse_ranks(ranks)?;
+    let by_bytes: HashMap<&[u8], u32> = ranks
+        .iter()
+        .map(|(token, rank)| (token.as_slice(), *rank))
+        .collect();
+
+    // The tokens are the byte-level representations of the bytes
+    let byte_chars = bytes_char();
+    let to_string = |bytes: &[u8]| -> String { bytes.iter().map(|b| byte_chars[b]).collect() };
+    let vocab: HashMap<String, u32> = ranks
+        .iter()
+        .map(|(token, rank)| (to_string(token), *rank))
+        .collect();
+    let mut sorted: Vec<_> = ranks.iter().filter(|(token, _)| token.len() > 1).collect();
+    sorted.sort_by_key(|(_, rank)| *rank);
+    let merges = sorted
+        .into_iter()
+        .filter_map(|(token, rank)| merge_parts(token, *rank, &by_bytes))
+        .map(|(a, b)| (to_string(a), to_string(b)))
+        .collect();
+
+    // Like tiktoken, the words that are tokens don't go through the merges
+    let mut tokenizer = Tokenizer::new(
+        BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .ignore_merges(true)
+            .build()?,
+    );
+    tokenizer.with_pre_tokenizer(Sequence::new(vec![
+        Split::new(
+            SplitPattern::Regex(spec.pattern.to_owned()),
+            SplitDelimiterBehavior::Isolated,
+            false,
+        )?
+        .into(),
+        ByteLevel::new(false, true, false).into(),
+    ]));
+    tokenizer.with_decoder(ByteLevel::default());
+    tokenizer.with_post_processor(ByteLevel::new(false, false, false));
+
+    let special_tokens: Vec<_> = spec
+        .special_tokens
+        .iter()
+        .map(|(token, id)| AddedToken::from(*token, true).id(Some(*id)))
+        .collect();
+    tokenizer.add_special_tokens(&special_tokens);
+    Ok(tokenizer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A rank file with all the bytes, and a few merges
+    fn ranks() -> String {
+        let mut lines: Vec<String> = (0..=255u8)
+            .map(|b| format!("{} {}", STANDARD.encode([b]), b))
+            .collect();
+        for (token, rank) in [
+            ("he", 256),
+            ("ll", 257),
+            ("hell", 258),
+            ("hello", 259),
+            (" w", 260),
+        ] {
+            lines.push(format!("{} {}", STANDARD.encode(token), rank));
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn from_inline_ranks() {
+        let tokenizer = from_ranks("r50k_base", ranks().as_bytes()).unwrap();
+        assert_eq!(tokenizer.get_vocab_size(false), 261);
+        assert_eq!(tokenizer.token_to_id("a"), Some(97));
+        assert_eq!(tokenizer.token_to_id("Ġw"), Some(260));
+
+        // A word of the vocabulary is a single token, the other words are merged by rank
+        let encoding = tokenizer.encode("hello world", false).unwrap();
+        assert_eq!(encoding.get_ids(), [259, 260, 111, 114, 108, 100]);
+        assert_eq!(encoding.get_tokens(), ["hello", "Ġw", "o", "r", "l", "d"]);
+        let encoding = tokenizer.encode("hellhe", false).unwrap();
+        assert_eq!(encoding.get_tokens(), ["hell", "he"]);
+
+        let encoding = tokenizer.encode("hello<|endoftext|>", false).unwrap();
+        assert_eq!(encoding.get_ids(), [259, 50256]);
+        assert_eq!(
+            tokenizer.decode(&[259, 260, 111, 50256], false).unwrap(),
+            "hello wo<|endoftext|>"
+        );
+        assert_eq!(tokenizer.decode(&[259, 50256], true).unwrap(), "hello");
+
+        // The cl100k_base pattern splits the digits by groups of 3
+        let tokenizer = from_ranks("cl100k_base", ranks().as_bytes()).unwrap();
+        let encoding = tokenizer.encode("12345<|endofprompt|>", false).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            ["1", "2", "3", "4", "5", "<|endofprompt|>"]
+        );
+        assert_eq!(
+            encoding.get_word_ids(),
+            [Some(0), Some(0), Some(0), Some(1), Some(1), Some(2)]
+        );
+        assert_eq!(encoding.get_ids()[5], 100276);
+    }
+
+    #[test]
+    fn merges_follow_the_ranks() {
+        let ranks = parse_ranks(ranks().as_bytes()).unwrap();
+        let by_bytes: HashMap<&[u8], u32> = ranks
+            .iter()
+            .map(|(token, rank)| (token.as_slice(), *rank))
+            .collect();
+        let parts = |token: &'static str, rank| {
+            merge_parts(token.as_bytes(), rank, &by_bytes).map(|(a, b)| {
+                (
+                    std::str::from_utf8(a).unwrap(),
+                    std::str::from_utf8(b).unwrap(),
+                )
+            })
+        };
+        assert_eq!(parts("he", 256), Some(("h", "e")));
+        assert_eq!(parts("hell", 258), Some(("he", "ll")));
+        assert_eq!(parts("hello", 259), Some(("hell", "o")));
+        // Its parts would come after it
+        assert_eq!(parts("hello", 257), None);
+    }
+
+    #[test]
+    fn errors() {
+        let err = from_ranks("o200k_base", ranks().as_bytes()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TiktokenError>(),
+            Some(TiktokenError::UnknownTiktokenEncoding(name)) if name == "o200k_base"
+        ));
+
+        for bad in ["aGU= 256\naGU=", "aGU= x", "a*GU= 1"] {
+            let err = from_ranks("r50k_base", bad.as_bytes()).unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<TiktokenError>(),
+                Some(TiktokenError::BadRankFile(_))
+            ));
+        }
+        let err = from_ranks("r50k_base", b"aGU= 256\naGU=").unwrap_err();
+        assert_eq!(err.to_string(), "Tiktoken rank file invalid at line 2");
+    }
+}
@@ -1,7 +1,10 @@
 mod common;
 
 use common::*;
+use std::collections::HashMap;
+use tokenizers::normalizers::utils::Lowercase;
 use tokenizers::tokenizer::AddedToken;
+use tokenizers::Tokenizer;
 
 #[test]
 fn add_tokens() {
@@ -26,6 +29,16 @@ fn add_tokens() {
     );
     assert_eq!(tokenizer.token_to_id("hello"), Some(2));
     assert_eq!(tokenizer.token_to_id("world"), Some(3));
+
+    // All the fields are public, so tokens can be built from a struct literal
+    let token = AddedToken {
+        content: "<pad>".into(),
+        special: true,
+        id: Some(10),
+        ..Default::default()
+    };
+    assert_eq!(tokenizer.add_special_tokens(&[token]), 1);
+    assert_eq!(tokenizer.token_to_id("<pad>"), Some(10));
 }
 
 #[test]
@@ -125,3 +138,190 @@ fn overlapping_tokens() {
     // assert_eq!(output.get_tokens(), &["I", "Ġlike", "Ġda", "nci", "ng"]);
     assert_eq!(output.get_tokens(), &["I", "Ġl", "ike", "Ġ", "danc", "ing"]);
 }
+
+#[test]
+fn get_all_special_tokens() {
+    let mut tokenizer = get_word_level();
+    tokenizer.with_post_processor(get_bert_processing());
+    // Neither the unknown token of the model nor the tokens of the post-processor
+    assert!(tokenizer.get_all_special_tokens().is_empty());
+    assert!(tokenizer.get_all_special_token_ids().is_empty());
+
+    tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true)]);
+    tokenizer.add_tokens(&[
+        AddedToken::from("[SEP]", false).special(true),
+        AddedToken::from("planet", false),
+    ]);
+    tokenizer
+        .load_config_from_json(r#"{"mask_token": "[MASK]", "unk_token": "[UNK]"}"#)
+        .unwrap();
+
+    assert_eq!(
+        tokenizer.get_all_special_tokens(),
+        vec!["[UNK]", "[CLS]", "[SEP]", "[MASK]"]
+    );
+    assert_eq!(tokenizer.get_all_special_token_ids(), vec![0, 4, 5, 7]);
+    // These are the tokens skipped when decoding
+    let ids = tokenizer
+        .encode("[CLS] hello planet [MASK] foo [SEP]", false)
+        .unwrap()
+        .get_ids()
+        .to_vec();
+    assert_eq!(tokenizer.decode(&ids, true).unwrap(), "hello planet");
+
+    tokenizer.reset_special_tokens();
+    assert!(tokenizer.get_all_special_tokens().is_empty());
+}
+
+#[test]
+fn split_by_special_tokens() {
+    let mut tokenizer = get_word_level();
+    tokenizer.add_special_tokens(&[
+        AddedToken::from("<s>", true),
+        AddedToken::from("</s>", true),
+        AddedToken::from("<s><s>", true),
+        AddedToken::from("[IMG]", true),
+    ]);
+    // Neither the regular added tokens nor the regex special tokens
+    tokenizer.add_tokens(&[AddedToken::from("hello", false)]);
+    tokenizer.add_special_tokens(&[AddedToken::from_regex(r"\d+", None).unwrap().special(true)]);
+
+    assert!(tokenizer.split_by_special_tokens("").is_empty());
+    assert_eq!(
+        tokenizer.split_by_special_tokens("hello 42"),
+        vec![(None, "hello 42")]
+    );
+
+    // At the start and at the end, and adjacent
+    assert_eq!(
+        tokenizer.split_by_special_tokens("</s>hi [IMG]</s>[IMG]"),
+        vec![
+            (Some(5), "</s>"),
+            (None, "hi "),
+            (Some(7), "[IMG]"),
+            (Some(5), "</s>"),
+            (Some(7), "[IMG]"),
+        ]
+    );
+
+    // The longest pattern wins over the ones it starts with
+    assert_eq!(
+        tokenizer.split_by_special_tokens("é<s><s><s>é"),
+        vec![
+            (None, "é"),
+            (Some(6), "<s><s>"),
+            (Some(4), "<s>"),
+            (None, "é"),
+        ]
+    );
+    assert_eq!(
+        tokenizer.split_by_special_tokens("<s</s>>"),
+        vec![(None, "<s"), (Some(5), "</s>"), (None, ">")]
+    );
+}
+
+#[test]
+fn convert_ids_and_tokens() {
+    let mut tokenizer = get_word_level();
+    tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true)]);
+    tokenizer.add_tokens(&[AddedToken::from("planet", false)]);
+
+    assert_eq!(
+        tokenizer.convert_tokens_to_ids(&["hello", "[CLS]", "planet", "moon", "[UNK]"]),
+        vec![Some(1), Some(4), Some(5), None, Some(0)]
+    );
+    assert_eq!(
+        tokenizer.convert_ids_to_tokens(&[2, 4, 5, 6]),
+        vec![
+            Some("world".to_string()),
+            Some("[CLS]".to_string()),
+            Some("planet".to_string()),
+            None
+        ]
+    );
+    assert!(tokenizer.convert_ids_to_tokens(&[]).is_empty());
+}
+
+#[test]
+fn reset_added_and_special_tokens() {
+    let mut tokenizer = get_word_level();
+    tokenizer.add_special_tokens(&[
+        AddedToken::from("[CLS]", true),
+        AddedToken::from("[SEP]", true),
+    ]);
+    tokenizer.add_tokens(&[AddedToken::from("hey", false)]);
+    tokenizer.with_post_processor(get_bert_processing());
+    assert_eq!(tokenizer.token_to_id("hey"), Some(6));
+
+    assert_eq!(tokenizer.reset_special_tokens(), 2);
+    assert_eq!(tokenizer.token_to_id("[CLS]"), None);
+    assert_eq!(tokenizer.get_vocab_size(false), 4);
+    let encoding = tokenizer.encode("[CLS] hey", false).unwrap();
+    assert_eq!(encoding.get_tokens(), ["[UNK]", "[UNK]", "[UNK]", "hey"]);
+    assert_eq!(encoding.get_ids(), [0, 0, 0, 6]);
+
+    // The ids of the removed tokens are reused once rebuilt
+    tokenizer.add_special_tokens(&[AddedToken::from("[SEP]", true)]);
+    assert_eq!(tokenizer.token_to_id("[SEP]"), Some(7));
+    tokenizer.rebuild_vocabulary();
+    assert_eq!(tokenizer.token_to_id("hey"), Some(4));
+    assert_eq!(tokenizer.token_to_id("[SEP]"), Some(5));
+    assert!(tokenizer.get_added_tokens_decoder()[&5].special);
+    // The post-processor uses the new id of `[SEP]`
+    let encoding = tokenizer.encode("hello", true).unwrap();
+    assert_eq!(encoding.get_ids().last(), Some(&5));
+
+    assert_eq!(tokenizer.reset_added_tokens(), 1);
+    assert_eq!(tokenizer.token_to_id("hey"), None);
+    assert_eq!(tokenizer.token_to_id("[SEP]"), Some(5));
+    assert_eq!(tokenizer.token_to_id("hello"), Some(1));
+}
+
+#[test]
+fn add_tokens_from() {
+    use tokenizers::models::bpe::BPE;
+
+    let vocab: HashMap<String, u32> = [("a", 0), ("b", 1), ("c", 2), ("ab", 3), ("bc", 4)]
+        .iter()
+        .chain(&[("abc", 5), ("hello", 6)])
+        .map(|(token, id)| (token.to_string(), *id))
+        .collect();
+    let merges = [("b", "c"), ("a", "b"), ("a", "bc")]
+        .iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect();
+    let other = Tokenizer::new(
+        BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .build()
+            .unwrap(),
+    );
+
+    // The merged tokens come first, by rank
+    let mut tokenizer = get_word_level();
+    assert_eq!(tokenizer.add_tokens_from(&other, 2), 2);
+    assert_eq!(tokenizer.token_to_id("bc"), Some(4));
+    assert_eq!(tokenizer.token_to_id("ab"), Some(5));
+    assert_eq!(tokenizer.token_to_id("abc"), None);
+
+    // Known tokens are skipped
+    assert_eq!(tokenizer.add_tokens_from(&other, 10), 4);
+    assert_eq!(tokenizer.get_vocab_size(true), 10);
+    assert_eq!(tokenizer.add_tokens_from(&other, 10), 0);
+}
+
+#[test]
+fn added_token_normalize_when_loading() {
+    let mut tokenizer = get_word_level();
+    tokenizer.with_normalizer(Lowercase);
+    tokenizer.add_tokens(&[
+        AddedToken::from("[MASK]", false).normalize_when_loading(true),
+        AddedToken::from("[CLS]", false),
+    ]);
+    assert_eq!(tokenizer.token_to_id("[mask]"), Some(4));
+    assert_eq!(tokenizer.token_to_id("[MASK]"), None);
+    assert_eq!(tokenizer.token_to_id("[CLS]"), Some(5));
+
+    let encoding = tokenizer.encode("Hello [MASK]", false).unwrap();
+    assert_eq!(encoding.get_tokens(), &["hello", "[mask]"]);
+}
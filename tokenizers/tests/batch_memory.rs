@@ -0,0 +1,49 @@
+use tokenizers::models::bpe::BPE;
+use tokenizers::pre_tokenizers::byte_level::ByteLevel;
+use tokenizers::processors::bert::BertProcessing;
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer};
+
+#[global_allocator]
+static ALLOCATOR: dhat::Alloc = dhat::Alloc;
+
+// This is the only test of this file, so no other test allocates while profiling
+#[test]
+fn estimate_is_above_measured_peak() {
+    // Without any merge, a byte-level BPE gives a token for each byte of the input
+    let vocab = ByteLevel::alphabet()
+        .into_iter()
+        .enumerate()
+        .map(|(id, c)| (c.to_string(), id as u32))
+        .chain([("[CLS]".to_string(), 256), ("[SEP]".to_string(), 257)])
+        .collect();
+    let mut tokenizer = Tokenizer::new(BPE::new(vocab, vec![]));
+    tokenizer.with_pre_tokenizer(ByteLevel::default());
+    tokenizer.with_post_processor(BertProcessing::new(
+        ("[SEP]".into(), 257),
+        ("[CLS]".into(), 256),
+    ));
+    tokenizer.with_padding(Some(PaddingParams {
+        strategy: PaddingStrategy::Fixed(512),
+        ..Default::default()
+    }));
+
+    let (batch_size, max_seq_len) = (16, 100);
+    let batch: Vec<String> = (0..batch_size)
+        .map(|_| "𝄞 é".repeat(max_seq_len / 3))
+        .collect();
+    let estimate = tokenizer.estimate_max_batch_memory(batch_size, max_seq_len);
+
+    // Only the allocations made while profiling are counted
+    let profiler = dhat::Profiler::builder().testing().build();
+    let encodings = tokenizer.encode_batch(batch, true).unwrap();
+    let measured = dhat::HeapStats::get().max_bytes;
+    drop(profiler);
+
+    assert_eq!(encodings[0].len(), 512);
+    assert!(
+        estimate >= measured,
+        "estimate {} below the measured peak {}",
+        estimate,
+        measured
+    );
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "benchmarking")]
+use std::collections::HashMap;
+use tokenizers::models::wordlevel::WordLevel;
+use tokenizers::pre_tokenizers::whitespace::Whitespace;
+use tokenizers::{Result, Tokenizer};
+
+fn tokenizer() -> Tokenizer {
+    let vocab: HashMap<String, u32> = [("[UNK]", 0), ("hello", 1), ("world", 2)]
+        .iter()
+        .map(|(token, id)| (token.to_string(), *id))
+        .collect();
+    let model = WordLevel::builder()
+        .vocab(vocab)
+        .unk_token("[UNK]".into())
+        .build()
+        .unwrap();
+    let mut tokenizer = Tokenizer::new(model);
+    tokenizer.with_pre_tokenizer(Whitespace {});
+    tokenizer
+}
+
+#[test]
+fn benchmark_encode() -> Result<()> {
+    let tokenizer = tokenizer();
+    let result = tokenizer.benchmark_encode(&["hello world", "hello there world !"], 50)?;
+
+    assert!(result.mean_ns > 0.0);
+    assert!(result.p50_ns <= result.p95_ns);
+    assert!(result.p95_ns <= result.p99_ns);
+    assert!(result.throughput_chars_per_sec > 0.0);
+    assert!(result.throughput_tokens_per_sec > 0.0);
+    // 15 chars for 3 tokens per pair of samples on average
+    let chars_per_token = result.throughput_chars_per_sec / result.throughput_tokens_per_sec;
+    assert!((chars_per_token - 30.0 / 6.0).abs() < 1e-9);
+    Ok(())
+}
+
+#[test]
+fn benchmark_encode_nothing() -> Result<()> {
+    let tokenizer = tokenizer();
+    let result = tokenizer.benchmark_encode(&[], 10)?;
+    assert_eq!(result.mean_ns, 0.0);
+    assert_eq!(result.p99_ns, 0.0);
+    assert_eq!(result.throughput_tokens_per_sec, 0.0);
+    Ok(())
+}
@@ -1,9 +1,11 @@
 use tokenizers::decoders::wordpiece::WordPiece as WordPieceDecoder;
 use tokenizers::models::bpe::BPE;
+use tokenizers::models::wordlevel::WordLevel;
 use tokenizers::models::wordpiece::WordPiece;
 use tokenizers::normalizers::bert::BertNormalizer;
 use tokenizers::pre_tokenizers::bert::BertPreTokenizer;
 use tokenizers::pre_tokenizers::byte_level::ByteLevel;
+use tokenizers::pre_tokenizers::whitespace::Whitespace;
 use tokenizers::processors::bert::BertProcessing;
 use tokenizers::tokenizer::{Model, Tokenizer};
 
@@ -53,3 +55,27 @@ pub fn get_bert() -> Tokenizer {
 
     tokenizer
 }
+
+/// A WordLevel tokenizer knowing `[UNK]`, `hello`, `world` and `!`, split on whitespaces
+#[allow(dead_code)]
+pub fn get_word_level() -> Tokenizer {
+    let vocab = [("[UNK]", 0), ("hello", 1), ("world", 2), ("!", 3)]
+        .iter()
+        .map(|(token, id)| (token.to_string(), *id))
+        .collect();
+    let model = WordLevel::builder()
+        .vocab(vocab)
+        .unk_token("[UNK]".into())
+        .build()
+        .unwrap();
+    let mut tokenizer = Tokenizer::new(model);
+    tokenizer.with_pre_tokenizer(Whitespace {});
+    tokenizer
+}
+
+/// The post-processor adding `[CLS]` and `[SEP]` to the tokens of `get_word_level`, with the
+/// ids they get once added after its vocabulary
+#[allow(dead_code)]
+pub fn get_bert_processing() -> BertProcessing {
+    BertProcessing::new(("[SEP]".into(), 5), ("[CLS]".into(), 4))
+}
@@ -0,0 +1,416 @@
+mod common;
+
+use common::*;
+use std::collections::HashMap;
+use tokenizers::normalizers::utils::Lowercase;
+use tokenizers::{AddedToken, Encoding, Tokenizer, TruncationParams};
+
+#[test]
+fn encode_batch_stream() {
+    use std::fs::File;
+    use std::io::{BufReader, Write};
+
+    let tokenizer = get_word_level();
+    let lines: Vec<String> = (0..10_000)
+        .map(|i| ["hello", "hello world", "world !"][i % 3].to_string())
+        .collect();
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "{}", lines.join("\n")).unwrap();
+
+    let reader = BufReader::new(File::open(file.path()).unwrap());
+    let batches = tokenizer
+        .encode_batch_stream(reader, 100, false)
+        .collect::<tokenizers::Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(batches.len(), 100);
+    assert!(batches.iter().all(|batch| batch.len() == 100));
+
+    let n_tokens = |encodings: &[Encoding]| encodings.iter().map(|e| e.len()).sum::<usize>();
+    let streamed: usize = batches.iter().map(|batch| n_tokens(batch)).sum();
+    let expected = n_tokens(&tokenizer.encode_batch(lines, false).unwrap());
+    assert_eq!(streamed, expected);
+
+    // The last batch may be partial
+    let reader = "hello\nworld\n!".as_bytes();
+    let sizes: Vec<_> = tokenizer
+        .encode_batch_stream(reader, 2, false)
+        .map(|batch| batch.unwrap().len())
+        .collect();
+    assert_eq!(sizes, vec![2, 1]);
+}
+
+#[test]
+fn encode_batch_sorted_for_parallel() {
+    let tokenizer = get_word_level();
+    let inputs: Vec<String> = (0..100)
+        .map(|i| vec!["hello world"; i % 7].join(" !"))
+        .collect();
+    let expected = tokenizer.encode_batch(inputs.clone(), false).unwrap();
+
+    let options = tokenizers::EncodeOptions {
+        sort_for_parallel: true,
+        ..Default::default()
+    };
+    let encodings = tokenizer
+        .encode_batch_with_options(inputs, false, &options)
+        .unwrap();
+    assert_eq!(encodings, expected);
+}
+
+#[test]
+fn encode_pairs_batch() {
+    let tokenizer = get_word_level();
+    let pairs = [("hello", "world !"), ("world", "hello")];
+    let encodings = tokenizer.encode_pairs_batch(&pairs, false).unwrap();
+    assert_eq!(encodings.len(), 2);
+    for (encoding, pair) in encodings.iter().zip(pairs) {
+        assert_eq!(encoding, &tokenizer.encode(pair, false).unwrap());
+    }
+    assert_eq!(encodings[0].get_type_ids(), [0, 1, 1]);
+    assert!(tokenizer.encode_pairs_batch(&[], false).unwrap().is_empty());
+}
+
+#[test]
+fn encode_pretokenized() {
+    let mut tokenizer = get_word_level();
+    tokenizer.with_normalizer(Lowercase);
+    tokenizer.with_post_processor(get_bert_processing());
+
+    let words = ["Hello", "world", "!", "unknown"];
+    for add_special_tokens in [false, true] {
+        let expected = tokenizer
+            .encode(words.join(" "), add_special_tokens)
+            .unwrap();
+        let encoding = tokenizer
+            .encode_pretokenized(&words, add_special_tokens)
+            .unwrap();
+        assert_eq!(encoding, expected);
+    }
+
+    // The pre-tokenizer is skipped, so a word is never split
+    let encoding = tokenizer
+        .encode_pretokenized(&["hello world"], false)
+        .unwrap();
+    assert_eq!(encoding.get_tokens(), &["[UNK]"]);
+    assert_eq!(encoding.get_offsets(), &[(0, 11)]);
+}
+
+#[test]
+fn encode_with_context() {
+    use tokenizers::decoders::fuse::Fuse;
+    use tokenizers::models::bpe::BPE;
+
+    let vocab: HashMap<String, u32> = [("a", 0), ("b", 1), ("c", 2), ("ab", 3), ("bc", 4)]
+        .iter()
+        .map(|(token, id)| (token.to_string(), *id))
+        .collect();
+    let merges = vec![("a".into(), "b".into()), ("b".into(), "c".into())];
+    let bpe = BPE::builder()
+        .vocab_and_merges(vocab, merges)
+        .build()
+        .unwrap();
+    let mut tokenizer = Tokenizer::new(bpe);
+    tokenizer.with_decoder(Fuse::new());
+    assert_eq!(tokenizer.encode("bc", false).unwrap().get_tokens(), ["bc"]);
+
+    // After an `a`, the `b` gets merged with it
+    let encoding = tokenizer.encode_with_context(&[0], "bc", false).unwrap();
+    assert_eq!(encoding.get_tokens(), ["ab", "c"]);
+    assert_eq!(encoding.get_offsets(), [(0, 1), (1, 2)]);
+
+    // The tokens of the context are not returned
+    let encoding = tokenizer.encode_with_context(&[2, 3], "c", false).unwrap();
+    assert_eq!(encoding.get_tokens(), ["c"]);
+    assert_eq!(encoding.get_offsets(), [(0, 1)]);
+
+    // Without any context, this is a regular encoding
+    let encoding = tokenizer
+        .encode_with_context_window(&[0], "bc", false, 0)
+        .unwrap();
+    assert_eq!(encoding.get_tokens(), ["bc"]);
+    let encoding = tokenizer.encode_with_context(&[], "abc", false).unwrap();
+    assert_eq!(
+        encoding.get_ids(),
+        tokenizer.encode("abc", false).unwrap().get_ids()
+    );
+}
+
+#[test]
+fn encode_with_prompt_cache() {
+    use tokenizers::decoders::fuse::Fuse;
+    use tokenizers::models::bpe::BPE;
+
+    let vocab: HashMap<String, u32> = [("a", 0), ("b", 1), ("c", 2), ("ab", 3), ("bc", 4)]
+        .iter()
+        .map(|(token, id)| (token.to_string(), *id))
+        .collect();
+    let merges = vec![("a".into(), "b".into()), ("b".into(), "c".into())];
+    let bpe = BPE::builder()
+        .vocab_and_merges(vocab, merges)
+        .build()
+        .unwrap();
+    let mut tokenizer = Tokenizer::new(bpe);
+    tokenizer.with_decoder(Fuse::new());
+
+    let encoding = tokenizer.encode_with_prompt_cache(&[2, 3], "c").unwrap();
+    assert_eq!(encoding.get_tokens(), ["c"]);
+    assert_eq!(encoding.get_offsets(), [(0, 1)]);
+
+    // The `b` of the completion would be merged with the `a` of the prompt
+    assert!(tokenizer.encode_with_prompt_cache(&[2, 0], "bc").is_err());
+    // `a` followed by `b` is never encoded like this
+    assert!(tokenizer.encode_with_prompt_cache(&[0, 1], "c").is_err());
+}
+
+#[test]
+fn encode_with_language_tags() {
+    let mut tokenizer = get_word_level();
+    tokenizer.with_post_processor(get_bert_processing());
+    tokenizer.add_special_tokens(&[
+        AddedToken::from("[CLS]", true),
+        AddedToken::from("[SEP]", true),
+        AddedToken::from("en_XX", true),
+        AddedToken::from("[fr_XX]", true),
+    ]);
+    tokenizer.add_tokens(&[AddedToken::from("de_DE", false)]);
+
+    let encoding = tokenizer
+        .encode_with_language_tags("hello world", "en_XX", true)
+        .unwrap();
+    assert_eq!(encoding.get_ids(), &[6, 4, 1, 2, 5]);
+    assert_eq!(encoding.get_tokens()[0], "en_XX");
+    assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 0, 0]);
+    assert_eq!(encoding.get_attention_mask(), &[1, 1, 1, 1, 1]);
+    assert_eq!(encoding.get_special_tokens_mask(), &[1, 1, 0, 0, 1]);
+    assert_eq!(encoding.get_word_ids()[0], None);
+
+    let encoding = tokenizer
+        .encode_with_language_tags("world", "fr_XX", false)
+        .unwrap();
+    assert_eq!(encoding.get_ids(), &[7, 2]);
+
+    // Each overflowing window gets its language token too
+    tokenizer
+        .with_truncation(Some(TruncationParams {
+            max_length: 3,
+            ..Default::default()
+        }))
+        .unwrap();
+    let encoding = tokenizer
+        .encode_with_language_tags("hello world", "en_XX", true)
+        .unwrap();
+    assert_eq!(encoding.get_ids(), &[6, 4, 1, 5]);
+    assert_eq!(encoding.get_overflowing()[0].get_ids(), &[6, 4, 2, 5]);
+
+    // Neither unknown languages nor regular added tokens
+    assert!(tokenizer
+        .encode_with_language_tags("hello", "es_XX", true)
+        .is_err());
+    assert!(tokenizer
+        .encode_with_language_tags("hello", "de_DE", true)
+        .is_err());
+}
+
+#[test]
+fn post_process_add_special_tokens() {
+    use tokenizers::processors::template::TemplateProcessing;
+
+    let template = TemplateProcessing::builder()
+        .try_single("[CLS] $A [SEP]")
+        .unwrap()
+        .try_pair("[CLS] $A [SEP] $B:1 [SEP]:1")
+        .unwrap()
+        .special_tokens(vec![("[CLS]", 4), ("[SEP]", 5)])
+        .build()
+        .unwrap();
+    let mut with_template = get_word_level();
+    with_template.with_post_processor(template);
+    let without_template = get_word_level();
+
+    let single = |tokenizer: &Tokenizer, add_special_tokens| {
+        tokenizer.encode("hello world", add_special_tokens).unwrap()
+    };
+    let pair = |tokenizer: &Tokenizer, add_special_tokens| {
+        tokenizer
+            .encode(("hello world", "!"), add_special_tokens)
+            .unwrap()
+    };
+
+    for add_special_tokens in [true, false] {
+        let encoding = single(&without_template, add_special_tokens);
+        assert_eq!(encoding.get_ids(), [1, 2]);
+        assert_eq!(encoding.get_type_ids(), [0, 0]);
+        let encoding = pair(&without_template, add_special_tokens);
+        assert_eq!(encoding.get_ids(), [1, 2, 3]);
+        assert_eq!(encoding.get_type_ids(), [0, 0, 1]);
+        assert_eq!(encoding.get_sequence_ids(), [Some(0), Some(0), Some(1)]);
+    }
+
+    let encoding = single(&with_template, true);
+    assert_eq!(encoding.get_ids(), [4, 1, 2, 5]);
+    assert_eq!(encoding.get_special_tokens_mask(), [1, 0, 0, 1]);
+    let encoding = single(&with_template, false);
+    assert_eq!(encoding.get_ids(), [1, 2]);
+    assert_eq!(encoding.get_special_tokens_mask(), [0, 0]);
+
+    // The template still gives the type ids and sequence ids of the pair
+    let encoding = pair(&with_template, true);
+    assert_eq!(encoding.get_ids(), [4, 1, 2, 5, 3, 5]);
+    assert_eq!(encoding.get_type_ids(), [0, 0, 0, 0, 1, 1]);
+    let encoding = pair(&with_template, false);
+    assert_eq!(encoding.get_ids(), [1, 2, 3]);
+    assert_eq!(encoding.get_type_ids(), [0, 0, 1]);
+    assert_eq!(encoding.get_sequence_ids(), [Some(0), Some(0), Some(1)]);
+
+    // The special tokens only count against the truncation when they are added
+    with_template
+        .with_truncation(Some(TruncationParams {
+            max_length: 3,
+            ..Default::default()
+        }))
+        .unwrap();
+    assert_eq!(single(&with_template, true).get_ids(), [4, 1, 5]);
+    assert_eq!(single(&with_template, false).get_ids(), [1, 2]);
+    assert_eq!(pair(&with_template, false).get_ids(), [1, 2, 3]);
+}
+
+#[test]
+fn fallback_tokenizer() {
+    use std::sync::Arc;
+    use tokenizers::models::wordpiece::WordPiece;
+    use tokenizers::FallbackTokenizer;
+
+    let mut tokenizer = get_word_level();
+    let encoding = tokenizer.encode("hello 日本語 world", false).unwrap();
+    assert_eq!(encoding.get_tokens(), ["hello", "[UNK]", "world"]);
+
+    // A fallback that knows Japanese, but not English
+    let vocab: HashMap<String, u32> = [("[UNK]", 0), ("日本", 1), ("##語", 2)]
+        .iter()
+        .map(|(token, id)| (token.to_string(), *id))
+        .collect();
+    let japanese = Tokenizer::new(WordPiece::builder().vocab(vocab).build().unwrap());
+    tokenizer.with_fallback_tokenizer(Some(FallbackTokenizer::new(Arc::new(japanese), "<ja>")));
+    assert_eq!(tokenizer.token_to_id("<ja>"), Some(4));
+
+    let encoding = tokenizer.encode("hello 日本語 world !", false).unwrap();
+    assert_eq!(
+        encoding.get_tokens(),
+        ["hello", "<ja>", "日本", "##語", "world", "!"]
+    );
+    // The ids of the fallback come after the 5 tokens of the main vocabulary
+    assert_eq!(encoding.get_ids(), [1, 4, 6, 7, 2, 3]);
+    assert_eq!(
+        encoding.get_offsets(),
+        [(0, 5), (6, 6), (6, 12), (12, 15), (16, 21), (22, 23)]
+    );
+    assert_eq!(encoding.get_word_ids()[1..4], [Some(1); 3]);
+
+    assert_eq!(
+        tokenizer.decode(encoding.get_ids(), true).unwrap(),
+        "hello 日本 ##語 world !"
+    );
+    assert_eq!(
+        tokenizer.decode(encoding.get_ids(), false).unwrap(),
+        "hello <ja> 日本 ##語 world !"
+    );
+
+    // Pre-tokens with known tokens are never rerouted
+    let encoding = tokenizer.encode("hello", false).unwrap();
+    assert_eq!(encoding.get_tokens(), ["hello"]);
+
+    // The ids of the fallback don't change when adding tokens afterwards
+    let ids = tokenizer
+        .encode("日本語", false)
+        .unwrap()
+        .get_ids()
+        .to_vec();
+    tokenizer.add_tokens(&[AddedToken::from("hey", false)]);
+    tokenizer.add_special_tokens(&[AddedToken::from("[SEP]", true)]);
+    assert_eq!(tokenizer.token_to_id("hey"), Some(8));
+    assert_eq!(tokenizer.token_to_id("[SEP]"), Some(9));
+    assert_eq!(tokenizer.decode(&ids, false).unwrap(), "<ja> 日本 ##語");
+    let encoding = tokenizer.encode("日本語 hey [SEP]", false).unwrap();
+    assert_eq!(encoding.get_ids(), [4, 6, 7, 8, 9]);
+
+    // Neither when saving and reloading the tokenizer
+    let reloaded = tokenizer
+        .to_string(false)
+        .unwrap()
+        .parse::<Tokenizer>()
+        .unwrap();
+    let fallback = reloaded.get_fallback_tokenizer().unwrap();
+    assert_eq!(fallback.get_id_offset(), 5);
+    assert_eq!(fallback.switch_token, "<ja>");
+    let reloaded_encoding = reloaded.encode("日本語 hey [SEP]", false).unwrap();
+    assert_eq!(reloaded_encoding.get_ids(), encoding.get_ids());
+    assert_eq!(
+        reloaded.decode(encoding.get_ids(), false).unwrap(),
+        "<ja> 日本 ##語 hey [SEP]"
+    );
+
+    tokenizer.with_fallback_tokenizer(None);
+    let encoding = tokenizer.encode("日本語", false).unwrap();
+    assert_eq!(encoding.get_tokens(), ["[UNK]"]);
+}
+
+#[test]
+fn encode_with_merges() {
+    use tokenizers::models::bpe::BPE;
+    use tokenizers::pre_tokenizers::whitespace::Whitespace;
+
+    let vocab: HashMap<String, u32> = [("a", 0), ("b", 1), ("ab", 2)]
+        .iter()
+        .map(|(token, id)| (token.to_string(), *id))
+        .collect();
+    let bpe = BPE::builder()
+        .vocab_and_merges(vocab, vec![("a".into(), "b".into())])
+        .build()
+        .unwrap();
+    let mut tokenizer = Tokenizer::new(bpe);
+    tokenizer.with_pre_tokenizer(Whitespace {});
+
+    // The positions of the symbols of each word follow the ones of the previous words
+    let (encoding, trace) = tokenizer.encode_with_merges("ab ab").unwrap();
+    assert_eq!(encoding.get_tokens(), ["ab", "ab"]);
+    assert_eq!(trace, [(0, 1, 0), (2, 3, 0)]);
+
+    assert!(get_word_level().encode_with_merges("hello").is_err());
+}
+
+#[test]
+fn encode_to_writer() {
+    let tokenizer = get_word_level();
+
+    let mut buffer = vec![];
+    tokenizer
+        .encode_to_writer("hello world !", false, &mut buffer)
+        .unwrap();
+    let encoding = tokenizer.encode("hello world !", false).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        encoding.to_json_string().unwrap()
+    );
+}
+
+#[test]
+fn batch_decode_to_writer() {
+    let mut tokenizer = get_word_level();
+    tokenizer.add_special_tokens(&[AddedToken::from("[SEP]", true)]);
+    let sequences = vec![vec![1, 2, 4], vec![], vec![2, 3]];
+
+    let mut buffer = vec![];
+    tokenizer
+        .batch_decode_to_writer(&sequences, true, "\n", &mut buffer)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "hello world\n\nworld !\n"
+    );
+
+    let mut buffer = vec![];
+    tokenizer
+        .batch_decode_to_writer(&sequences[..1], false, " | ", &mut buffer)
+        .unwrap();
+    assert_eq!(String::from_utf8(buffer).unwrap(), "hello world [SEP] | ");
+}
@@ -0,0 +1,28 @@
+use std::fs::{read, read_to_string};
+use tokenizers::Tokenizer;
+
+#[test]
+fn bpe_merges_match_the_reference_conversion() {
+    // The Llama SentencePiece BPE model, and its reference conversion that kept the merges
+    // SentencePiece applies
+    let bytes = read("data/llama-tokenizer.model").unwrap();
+    let tokenizer = Tokenizer::from_sentencepiece_model(&bytes, "bpe").unwrap();
+    let reference = Tokenizer::from_file("data/llama-tokenizer.json").unwrap();
+
+    // The ids given by SentencePiece itself
+    let encoding = tokenizer.encode("Hello world", false).unwrap();
+    assert_eq!(encoding.get_ids(), [15043, 3186]);
+
+    // The reference conversion doesn't split on whitespaces, so we skip their runs
+    let content = read_to_string("data/small.txt").unwrap();
+    for line in content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.contains("  "))
+    {
+        let expected = reference.encode(line, false).unwrap();
+        let encoding = tokenizer.encode(line, false).unwrap();
+        assert_eq!(encoding.get_tokens(), expected.get_tokens(), "{}", line);
+        assert_eq!(encoding.get_ids(), expected.get_ids(), "{}", line);
+    }
+}
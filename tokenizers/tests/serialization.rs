@@ -1,5 +1,6 @@
 mod common;
 
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use common::*;
 use tokenizers::decoders::byte_level::ByteLevel;
 use tokenizers::decoders::DecoderWrapper;
@@ -9,6 +10,7 @@ use tokenizers::models::wordpiece::WordPiece;
 use tokenizers::models::ModelWrapper;
 use tokenizers::normalizers::bert::BertNormalizer;
 use tokenizers::normalizers::unicode::{NFC, NFKC};
+use tokenizers::normalizers::utils::Lowercase;
 use tokenizers::normalizers::NormalizerWrapper;
 use tokenizers::pre_tokenizers::bert::BertPreTokenizer;
 use tokenizers::pre_tokenizers::delimiter::CharDelimiterSplit;
@@ -17,7 +19,7 @@ use tokenizers::pre_tokenizers::whitespace::Whitespace;
 use tokenizers::pre_tokenizers::PreTokenizerWrapper;
 use tokenizers::processors::bert::BertProcessing;
 use tokenizers::processors::PostProcessorWrapper;
-use tokenizers::{SplitDelimiterBehavior, Tokenizer, TokenizerImpl};
+use tokenizers::{AddedToken, PaddingDirection, SplitDelimiterBehavior, Tokenizer, TokenizerImpl};
 
 #[test]
 fn bpe_serde() {
@@ -248,3 +250,174 @@ fn bpe_with_dropout_serde() {
 fn test_deserialize_long_file() {
     let _tokenizer = Tokenizer::from_file("data/albert-base-v1-tokenizer.json").unwrap();
 }
+
+#[test]
+fn base64_roundtrip() {
+    let mut tokenizer = get_word_level();
+    tokenizer.with_normalizer(Lowercase);
+    tokenizer.with_post_processor(get_bert_processing());
+    tokenizer.add_special_tokens(&[
+        AddedToken::from("[CLS]", true),
+        AddedToken::from("[SEP]", true),
+    ]);
+
+    let encoded = tokenizer.serialize_to_base64().unwrap();
+    assert!(encoded
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+
+    let decoded = Tokenizer::from_base64(&encoded).unwrap();
+    assert_eq!(
+        decoded.to_string(false).unwrap(),
+        tokenizer.to_string(false).unwrap()
+    );
+    let encoding = decoded.encode("Hello World !", true).unwrap();
+    assert_eq!(encoding.get_ids(), &[4, 1, 2, 3, 5]);
+    assert_eq!(
+        encoding.get_ids(),
+        tokenizer.encode("Hello World !", true).unwrap().get_ids()
+    );
+
+    assert!(Tokenizer::from_base64("not base64!").is_err());
+    assert!(Tokenizer::from_base64(&URL_SAFE_NO_PAD.encode("{}")).is_err());
+}
+
+#[test]
+fn try_from_json_value() {
+    use std::convert::TryFrom;
+
+    let tokenizer = get_word_level();
+    let value = serde_json::to_value(&tokenizer).unwrap();
+
+    let from_ref = Tokenizer::try_from(&value).unwrap();
+    let from_owned = Tokenizer::try_from(value).unwrap();
+    let expected = tokenizer.encode("hello world !", false).unwrap();
+    for tok in [from_ref, from_owned] {
+        let encoding = tok.encode("hello world !", false).unwrap();
+        assert_eq!(encoding.get_ids(), expected.get_ids());
+        assert_eq!(encoding.get_tokens(), expected.get_tokens());
+    }
+
+    let invalid = serde_json::json!({ "version": "1.0" });
+    assert!(Tokenizer::try_from(&invalid).is_err());
+}
+
+#[test]
+fn from_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path();
+    get_word_level()
+        .save(path.join("tokenizer.json"), false)
+        .unwrap();
+    std::fs::write(
+        path.join("tokenizer_config.json"),
+        r#"{"added_tokens_decoder": {
+            "4": {"content": "<pad>", "lstrip": false, "normalized": false, "rstrip": false, "single_word": false, "special": true},
+            "1": {"content": "<conflict>", "special": true}
+        }}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        path.join("special_tokens_map.json"),
+        r#"{"bos_token": "<s>", "eos_token": {"content": "</s>", "lstrip": true}, "additional_special_tokens": ["world"]}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        path.join("added_tokens.json"),
+        r#"{"<pad>": 4, "<tok>": 5, "hello": 6}"#,
+    )
+    .unwrap();
+
+    let tokenizer = Tokenizer::from_dir(path).unwrap();
+    assert_eq!(tokenizer.token_to_id("<pad>"), Some(4));
+    assert_eq!(tokenizer.token_to_id("<tok>"), Some(5));
+    // tokenizer.json wins for conflicting ids and contents
+    assert_eq!(tokenizer.token_to_id("<conflict>"), None);
+    assert_eq!(tokenizer.token_to_id("hello"), Some(1));
+    assert_eq!(tokenizer.token_to_id("world"), Some(2));
+    // Tokens without an expected id come last
+    assert_eq!(tokenizer.token_to_id("<s>"), Some(6));
+    assert_eq!(tokenizer.token_to_id("</s>"), Some(7));
+
+    let added = tokenizer.get_added_tokens_decoder();
+    assert!(added[&4].special);
+    assert!(!added[&5].special);
+    assert!(added[&7].lstrip);
+
+    let encoding = tokenizer.encode("hello <tok> </s>", false).unwrap();
+    assert_eq!(encoding.get_ids(), &[1, 5, 7]);
+
+    let empty = tempfile::tempdir().unwrap();
+    assert!(Tokenizer::from_dir(empty.path()).is_err());
+}
+
+#[test]
+fn fingerprint() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tokenizer.json");
+    get_word_level().save(&path, false).unwrap();
+
+    let first = Tokenizer::from_file(&path).unwrap();
+    let mut second = Tokenizer::from_file(&path).unwrap();
+    assert_eq!(first.fingerprint(), second.fingerprint());
+    assert!(first.fingerprint_matches(&second));
+    assert!(first.fingerprint_matches(&get_word_level()));
+
+    second.add_tokens(&[AddedToken::from("new", false)]);
+    assert_ne!(first.fingerprint(), second.fingerprint());
+    assert!(!first.fingerprint_matches(&second));
+}
+
+#[test]
+fn load_config_from_json() {
+    let bert_config = r#"{
+        "do_lower_case": true,
+        "model_max_length": 6,
+        "padding_side": "left",
+        "truncation_side": "left",
+        "cls_token": "[CLS]",
+        "sep_token": "[SEP]",
+        "pad_token": {"content": "[PAD]", "lstrip": false},
+        "unk_token": "[UNK]",
+        "tokenizer_class": "BertTokenizer"
+    }"#;
+    let mut tokenizer = get_word_level();
+    tokenizer.load_config_from_json(bert_config).unwrap();
+    for token in ["[UNK]", "[CLS]", "[SEP]", "[PAD]"] {
+        let id = tokenizer.token_to_id(token).unwrap();
+        assert!(tokenizer.get_added_tokens_decoder()[&id].special);
+    }
+    assert!(tokenizer.token_to_id("BertTokenizer").is_none());
+    let truncation = tokenizer.get_truncation().unwrap();
+    assert_eq!(truncation.max_length, 6);
+    assert_eq!(truncation.direction, tokenizers::TruncationDirection::Left);
+    let padding = tokenizer.get_padding().unwrap();
+    assert!(matches!(padding.direction, PaddingDirection::Left));
+    assert_eq!(padding.pad_token, "[PAD]");
+    assert_eq!(padding.pad_id, tokenizer.token_to_id("[PAD]").unwrap());
+
+    let encoding = tokenizer
+        .encode("hello world ! hello world ! hello", false)
+        .unwrap();
+    assert_eq!(encoding.get_ids(), &[2, 3, 1, 2, 3, 1]);
+
+    // Keys missing or set to the value of the models without limit are ignored
+    let gpt2_config = r#"{
+        "add_prefix_space": false,
+        "bos_token": "<|endoftext|>",
+        "eos_token": "<|endoftext|>",
+        "model_max_length": 1000000000000000019884624838656,
+        "tokenizer_class": "GPT2Tokenizer"
+    }"#;
+    let mut tokenizer = get_word_level();
+    tokenizer.load_config_from_json(gpt2_config).unwrap();
+    assert_eq!(tokenizer.get_added_tokens_decoder().len(), 1);
+    assert_eq!(tokenizer.token_to_id("<|endoftext|>"), Some(4));
+    assert!(tokenizer.get_truncation().is_none());
+    assert!(tokenizer.get_padding().is_none());
+
+    assert!(tokenizer.load_config_from_json("[]").is_err());
+    assert!(tokenizer
+        .load_config_from_json(r#"{"padding_side": "top"}"#)
+        .is_err());
+}
@@ -0,0 +1,29 @@
+use std::fs::read;
+use tokenizers::Tokenizer;
+
+#[test]
+fn cl100k_base_matches_tiktoken() {
+    let ranks = read("data/cl100k_base.tiktoken").unwrap();
+    let tokenizer = Tokenizer::from_tiktoken_ranks("cl100k_base", &ranks).unwrap();
+    assert_eq!(tokenizer.get_vocab_size(true), 100_261);
+
+    // The ids given by tiktoken itself
+    for (text, expected) in [
+        ("hello world", &[15339, 1917][..]),
+        ("tiktoken is great!", &[83, 1609, 5963, 374, 2294, 0]),
+        ("2 + 2 = 4", &[17, 489, 220, 17, 284, 220, 19]),
+        (
+            "antidisestablishmentarianism",
+            &[519, 85342, 34500, 479, 8997, 2191],
+        ),
+        (
+            "お誕生日おめでとう",
+            &[33334, 45918, 243, 21990, 9080, 33334, 62004, 16556, 78699],
+        ),
+        ("hello<|endoftext|>", &[15339, 100257]),
+    ] {
+        let encoding = tokenizer.encode(text, false).unwrap();
+        assert_eq!(encoding.get_ids(), expected, "{}", text);
+        assert_eq!(tokenizer.decode(expected, false).unwrap(), text);
+    }
+}
@@ -0,0 +1,287 @@
+mod common;
+
+use common::*;
+use std::collections::HashMap;
+use tokenizers::normalizers::utils::Lowercase;
+use tokenizers::pre_tokenizers::whitespace::Whitespace;
+use tokenizers::{AddedToken, Tokenizer};
+
+#[test]
+fn mock() {
+    let tokenizer = Tokenizer::mock(5);
+    assert_eq!(tokenizer.get_vocab_size(true), 5);
+    assert!(tokenizer.get_normalizer().is_none());
+    assert!(tokenizer.get_post_processor().is_none());
+
+    let encoding = tokenizer.encode("token_4 token_1, token_5", true).unwrap();
+    assert_eq!(encoding.get_ids(), &[4, 1, 0, 0]);
+    assert_eq!(
+        encoding.get_tokens(),
+        &["token_4", "token_1", "[UNK]", "[UNK]"]
+    );
+
+    assert_eq!(Tokenizer::mock(0).get_vocab_size(true), 1);
+}
+
+#[test]
+fn vocab_lengths() {
+    let mut tokenizer = get_word_level();
+    assert_eq!(
+        tokenizer.longest_token_in_vocab(),
+        Some((0, "[UNK]".to_string(), 5))
+    );
+    assert_eq!(
+        tokenizer.shortest_token_in_vocab(),
+        Some((3, "!".to_string(), 1))
+    );
+    assert_eq!(tokenizer.average_token_byte_length(), 4.0);
+
+    // With the added tokens
+    tokenizer.add_tokens(&[tokenizers::AddedToken::from("wörld", false)]);
+    assert_eq!(
+        tokenizer.longest_token_in_vocab(),
+        Some((4, "wörld".to_string(), 6))
+    );
+    assert_eq!(tokenizer.average_token_byte_length(), 22.0 / 5.0);
+
+    let empty = Tokenizer::new(tokenizers::models::bpe::BPE::default());
+    assert_eq!(empty.longest_token_in_vocab(), None);
+    assert_eq!(empty.shortest_token_in_vocab(), None);
+    assert_eq!(empty.average_token_byte_length(), 0.0);
+}
+
+#[test]
+fn merge_post_processors() {
+    use tokenizers::processors::roberta::RobertaProcessing;
+    use tokenizers::processors::sequence::Sequence;
+    use tokenizers::{PostProcessor, PostProcessorWrapper};
+
+    let mut tokenizer = get_word_level();
+    assert!(tokenizer.merge_post_processors(vec![]).is_err());
+
+    let bert = get_bert_processing();
+    tokenizer.add_post_processor(bert.clone().into());
+    assert!(matches!(
+        tokenizer.get_post_processor(),
+        Some(PostProcessorWrapper::Bert(_))
+    ));
+
+    let roberta = RobertaProcessing::new(("[SEP]".into(), 5), ("[CLS]".into(), 4))
+        .add_prefix_space(false)
+        .trim_offsets(false);
+    tokenizer
+        .merge_post_processors(vec![roberta.clone().into()])
+        .unwrap();
+    match tokenizer.get_post_processor() {
+        Some(PostProcessorWrapper::Sequence(sequence)) => {
+            assert_eq!(
+                sequence,
+                &Sequence::new(vec![bert.clone().into(), roberta.into()])
+            )
+        }
+        other => panic!("Expected a sequence, got {:?}", other),
+    }
+
+    // Adding more extends the existing sequence
+    tokenizer.add_post_processor(bert.into());
+    match tokenizer.get_post_processor() {
+        Some(PostProcessorWrapper::Sequence(sequence)) => {
+            assert_eq!(sequence.added_tokens(false), 6)
+        }
+        other => panic!("Expected a sequence, got {:?}", other),
+    }
+}
+
+#[test]
+fn strip_accents() {
+    use tokenizers::normalizers::{BertNormalizer, Sequence, NFD};
+    use tokenizers::NormalizerWrapper;
+
+    let normalize = |tokenizer: &Tokenizer, s: &str| {
+        let mut normalized = tokenizers::NormalizedString::from(s);
+        if let Some(normalizer) = tokenizer.get_normalizer() {
+            tokenizers::Normalizer::normalize(normalizer, &mut normalized).unwrap();
+        }
+        normalized.get().to_owned()
+    };
+
+    let mut tokenizer = get_word_level();
+    assert!(!tokenizer.get_strip_accents());
+    tokenizer.set_strip_accents(true);
+    assert!(tokenizer.get_strip_accents());
+    assert!(matches!(
+        tokenizer.get_normalizer(),
+        Some(NormalizerWrapper::StripAccents(_))
+    ));
+    // Without any decomposition first, only the combining marks are removed
+    assert_eq!(normalize(&tokenizer, "héllo he\u{301}llo"), "héllo hello");
+    tokenizer.set_strip_accents(false);
+    assert!(tokenizer.get_normalizer().is_none());
+
+    tokenizer.with_normalizer(Sequence::new(vec![NFD.into(), Lowercase.into()]));
+    tokenizer.set_strip_accents(true).set_strip_accents(true);
+    assert!(tokenizer.get_strip_accents());
+    assert_eq!(normalize(&tokenizer, "Héllo"), "hello");
+    match tokenizer.get_normalizer() {
+        Some(NormalizerWrapper::Sequence(sequence)) => {
+            assert_eq!(sequence.get_normalizers().len(), 3)
+        }
+        other => panic!("Expected a sequence, got {:?}", other),
+    }
+    tokenizer.set_strip_accents(false);
+    assert!(!tokenizer.get_strip_accents());
+    assert_eq!(normalize(&tokenizer, "Héllo"), "he\u{301}llo");
+
+    // The BertNormalizer strips the accents by default when lowercasing
+    tokenizer.with_normalizer(BertNormalizer::default());
+    assert!(tokenizer.get_strip_accents());
+    tokenizer.set_strip_accents(false);
+    assert!(!tokenizer.get_strip_accents());
+    assert_eq!(normalize(&tokenizer, "Héllo"), "héllo");
+}
+
+#[test]
+fn clone_without_components() {
+    use tokenizers::decoders::fuse::Fuse;
+
+    let mut tokenizer = get_word_level();
+    tokenizer.add_special_tokens(&[
+        AddedToken::from("[CLS]", true),
+        AddedToken::from("[SEP]", true),
+    ]);
+    tokenizer
+        .with_normalizer(Lowercase)
+        .with_post_processor(get_bert_processing())
+        .with_decoder(Fuse::new());
+    let tokens = |tokenizer: &Tokenizer, text| {
+        let encoding = tokenizer.encode(text, true).unwrap();
+        encoding.get_tokens().to_vec()
+    };
+    assert_eq!(
+        tokens(&tokenizer, "Hello world"),
+        ["[CLS]", "hello", "world", "[SEP]"]
+    );
+    assert_eq!(tokenizer.decode(&[1, 2], false).unwrap(), "helloworld");
+
+    let stripped = tokenizer.clone_without_normalizer();
+    assert!(stripped.get_normalizer().is_none());
+    assert_eq!(
+        tokens(&stripped, "Hello world"),
+        ["[CLS]", "[UNK]", "world", "[SEP]"]
+    );
+
+    let stripped = tokenizer.clone_without_pre_tokenizer();
+    assert!(stripped.get_pre_tokenizer().is_none());
+    assert_eq!(
+        tokens(&stripped, "Hello world"),
+        ["[CLS]", "[UNK]", "[SEP]"]
+    );
+
+    let stripped = tokenizer.clone_without_post_processor();
+    assert!(stripped.get_post_processor().is_none());
+    assert_eq!(tokens(&stripped, "Hello world"), ["hello", "world"]);
+
+    let stripped = tokenizer.clone_without_decoder();
+    assert!(stripped.get_decoder().is_none());
+    assert_eq!(stripped.decode(&[1, 2], false).unwrap(), "hello world");
+
+    // The vocabulary is the same, and the original is left untouched
+    assert_eq!(stripped.get_vocab(true), tokenizer.get_vocab(true));
+    assert!(tokenizer.get_decoder().is_some());
+}
+
+#[test]
+fn strip_vocabulary_to_frequency() {
+    let mut tokenizer = get_word_level();
+    tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true)]);
+    tokenizer.add_special_tokens(&[AddedToken::from("[SEP]", true)]);
+    tokenizer.with_post_processor(get_bert_processing());
+
+    let corpus = "hello world\nhello";
+    let pruned = tokenizer.strip_vocabulary_to_frequency(corpus, 2).unwrap();
+    assert_eq!(pruned.get_vocab_size(false), 2);
+    assert_eq!(pruned.token_to_id("[UNK]"), Some(0));
+    assert_eq!(pruned.token_to_id("hello"), Some(1));
+    assert_eq!(pruned.token_to_id("world"), None);
+    // The added tokens and their ids in the post-processor follow
+    assert_eq!(pruned.token_to_id("[CLS]"), Some(2));
+    assert_eq!(
+        pruned.encode("hello world", true).unwrap().get_ids(),
+        [2, 1, 0, 3]
+    );
+
+    // Every token but the unknown one appears in the corpus
+    let pruned = tokenizer.strip_vocabulary_to_frequency(corpus, 1).unwrap();
+    let encoding = pruned.encode(corpus, false).unwrap();
+    for (token, id) in pruned.get_vocab(false) {
+        assert!(token == "[UNK]" || encoding.get_ids().contains(&id));
+    }
+    assert_eq!(pruned.get_vocab_size(false), 3);
+}
+
+#[test]
+fn strip_vocabulary_to_frequency_bpe() {
+    use tokenizers::models::bpe::BPE;
+
+    let vocab: HashMap<String, u32> = ["a", "b", "c", "d", "ab", "abc", "cd"]
+        .iter()
+        .enumerate()
+        .map(|(id, token)| (token.to_string(), id as u32))
+        .collect();
+    let merges = [("a", "b"), ("ab", "c"), ("c", "d")]
+        .iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect();
+    let mut tokenizer = Tokenizer::new(
+        BPE::builder()
+            .vocab_and_merges(vocab, merges)
+            .build()
+            .unwrap(),
+    );
+    tokenizer.with_pre_tokenizer(Whitespace {});
+
+    let corpus = "abc abc ab";
+    let pruned = tokenizer.strip_vocabulary_to_frequency(corpus, 1).unwrap();
+    let tokens = |tokenizer: &Tokenizer| {
+        tokenizer
+            .encode(corpus, false)
+            .unwrap()
+            .get_tokens()
+            .to_vec()
+    };
+    assert_eq!(tokens(&pruned), tokens(&tokenizer));
+    assert_eq!(pruned.token_to_id("d"), None);
+    assert_eq!(pruned.token_to_id("cd"), None);
+    // `a`, `b` and `c` build the kept tokens
+    assert_eq!(pruned.get_vocab_size(false), 5);
+    if let tokenizers::ModelWrapper::BPE(bpe) = pruned.get_model() {
+        assert_eq!(bpe.num_merges(), 2);
+        assert!(bpe.verify_vocabulary_consistency().is_ok());
+    }
+}
+
+#[test]
+fn merges_list_and_map() {
+    use tokenizers::models::bpe::BPE;
+
+    assert_eq!(get_word_level().get_merges_list(), None);
+    assert_eq!(get_word_level().get_merges_map(), None);
+
+    let vocab: HashMap<String, u32> = [("a", 0), ("b", 1), ("c", 2), ("ab", 3), ("cab", 4)]
+        .iter()
+        .map(|(token, id)| (token.to_string(), *id))
+        .collect();
+    let merges = vec![("a".into(), "b".into()), ("c".into(), "ab".into())];
+    let bpe = BPE::builder()
+        .vocab_and_merges(vocab, merges)
+        .build()
+        .unwrap();
+    let tokenizer = Tokenizer::new(bpe);
+
+    let expected: Vec<(String, String)> = vec![("a".into(), "b".into()), ("c".into(), "ab".into())];
+    assert_eq!(tokenizer.get_merges_list(), Some(expected));
+    let map = tokenizer.get_merges_map().unwrap();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map[&("a".to_string(), "b".to_string())], 0);
+    assert_eq!(map[&("c".to_string(), "ab".to_string())], 1);
+}
@@ -0,0 +1,231 @@
+mod common;
+
+use common::*;
+use tokenizers::{Encoding, PaddingDirection, PaddingParams, Tokenizer, TruncationParams};
+
+#[test]
+fn encode_with_overflowing_tokens() {
+    let mut tokenizer = get_word_level();
+    tokenizer.with_post_processor(get_bert_processing());
+    tokenizer
+        .with_truncation(Some(TruncationParams {
+            max_length: 4,
+            ..Default::default()
+        }))
+        .unwrap();
+    let windows = |text: &str| {
+        let encodings = tokenizer
+            .encode_with_overflowing_tokens(text, true)
+            .unwrap();
+        assert!(encodings.iter().all(|e| e.get_overflowing().is_empty()));
+        encodings
+            .into_iter()
+            .map(|e| e.get_ids().to_vec())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(windows("hello world"), vec![vec![4, 1, 2, 5]]);
+    assert_eq!(
+        windows("hello world !"),
+        vec![vec![4, 1, 2, 5], vec![4, 3, 5]]
+    );
+    assert_eq!(
+        windows("hello world ! hello world !"),
+        vec![vec![4, 1, 2, 5], vec![4, 3, 1, 5], vec![4, 2, 3, 5],]
+    );
+    assert_eq!(
+        windows("hello world ! hello world ! hello"),
+        vec![
+            vec![4, 1, 2, 5],
+            vec![4, 3, 1, 5],
+            vec![4, 2, 3, 5],
+            vec![4, 1, 5],
+        ]
+    );
+}
+
+#[test]
+fn truncate_in_place() {
+    let mut tokenizer = get_word_level();
+    let text = "hello world ! hello world";
+    let mut encoding = tokenizer.encode(text, false).unwrap();
+
+    // Without truncation, nothing changes
+    let untouched = encoding.clone();
+    tokenizer.truncate_in_place(&mut encoding).unwrap();
+    assert_eq!(encoding, untouched);
+
+    for params in [
+        TruncationParams {
+            max_length: 3,
+            ..Default::default()
+        },
+        TruncationParams {
+            max_length: 2,
+            stride: 1,
+            direction: tokenizers::TruncationDirection::Left,
+            ..Default::default()
+        },
+        TruncationParams {
+            max_length: 0,
+            ..Default::default()
+        },
+    ] {
+        tokenizer.with_truncation(Some(params)).unwrap();
+        let mut truncated = untouched.clone();
+        tokenizer.truncate_in_place(&mut truncated).unwrap();
+        assert_eq!(truncated, tokenizer.encode(text, false).unwrap());
+    }
+}
+
+#[test]
+fn truncation_stride_overflowing() {
+    let mut tokenizer = get_word_level();
+    let windows = |tokenizer: &mut Tokenizer, stride| {
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: 3,
+                stride,
+                ..Default::default()
+            }))
+            .unwrap();
+        let encoding = tokenizer
+            .encode("hello world ! hello world", false)
+            .unwrap();
+        std::iter::once(&encoding)
+            .chain(encoding.get_overflowing())
+            .map(|e| (e.get_tokens().to_vec(), e.get_offsets().to_vec()))
+            .collect::<Vec<_>>()
+    };
+
+    // Without stride, the windows don't overlap
+    assert_eq!(
+        windows(&mut tokenizer, 0),
+        vec![
+            (
+                vec!["hello".into(), "world".into(), "!".into()],
+                vec![(0, 5), (6, 11), (12, 13)]
+            ),
+            (
+                vec!["hello".into(), "world".into()],
+                vec![(14, 19), (20, 25)]
+            ),
+        ]
+    );
+
+    // Each window starts with the last `stride` tokens of the previous one
+    assert_eq!(
+        windows(&mut tokenizer, 1),
+        vec![
+            (
+                vec!["hello".into(), "world".into(), "!".into()],
+                vec![(0, 5), (6, 11), (12, 13)]
+            ),
+            (
+                vec!["!".into(), "hello".into(), "world".into()],
+                vec![(12, 13), (14, 19), (20, 25)]
+            ),
+        ]
+    );
+    let overlapping = windows(&mut tokenizer, 2);
+    assert_eq!(overlapping.len(), 3);
+    assert_eq!(overlapping[1].1, vec![(6, 11), (12, 13), (14, 19)]);
+    assert_eq!(overlapping[2].1, vec![(12, 13), (14, 19), (20, 25)]);
+}
+
+#[test]
+fn encode_batch_with_return_overflowing_encodings() {
+    let mut tokenizer = get_word_level();
+    tokenizer
+        .with_truncation(Some(TruncationParams {
+            max_length: 2,
+            stride: 1,
+            ..Default::default()
+        }))
+        .unwrap();
+
+    let (encodings, overflowing) = tokenizer
+        .encode_batch_with_return_overflowing_encodings(
+            vec!["hello world ! hello", "world", "hello world !"],
+            false,
+        )
+        .unwrap();
+    let ids = |encodings: &[Encoding]| {
+        encodings
+            .iter()
+            .map(|e| e.get_ids().to_vec())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&encodings), vec![vec![1, 2], vec![2], vec![1, 2]]);
+    assert!(encodings.iter().all(|e| e.get_overflowing().is_empty()));
+    assert_eq!(overflowing.len(), 3);
+    assert_eq!(ids(&overflowing[0]), vec![vec![2, 3], vec![3, 1]]);
+    assert!(overflowing[1].is_empty());
+    assert_eq!(ids(&overflowing[2]), vec![vec![2, 3]]);
+
+    let (encodings, overflowing) = tokenizer
+        .encode_batch_with_return_overflowing_encodings(Vec::<&str>::new(), false)
+        .unwrap();
+    assert!(encodings.is_empty() && overflowing.is_empty());
+}
+
+#[test]
+fn padding_and_truncation_params() {
+    let mut tokenizer = get_word_level();
+    assert!(tokenizer.padding_params().is_none());
+    assert_eq!(tokenizer.get_padding_token_id(), None);
+    assert!(tokenizer.get_truncation_params().is_none());
+
+    tokenizer.with_padding(Some(PaddingParams {
+        pad_id: 3,
+        pad_token: "!".into(),
+        direction: PaddingDirection::Left,
+        ..Default::default()
+    }));
+    tokenizer
+        .with_truncation(Some(TruncationParams {
+            max_length: 8,
+            ..Default::default()
+        }))
+        .unwrap();
+    assert_eq!(tokenizer.get_padding_token_id(), Some(3));
+    assert!(matches!(
+        tokenizer.padding_params().unwrap().direction,
+        PaddingDirection::Left
+    ));
+    assert_eq!(tokenizer.get_truncation_params().unwrap().max_length, 8);
+}
+
+#[test]
+fn encode_with_position_ids() {
+    let mut tokenizer = get_word_level();
+    tokenizer.with_post_processor(get_bert_processing());
+
+    let (encoding, position_ids) = tokenizer
+        .encode_with_position_ids("hello world", 0, true)
+        .unwrap();
+    assert_eq!(encoding.get_ids(), &[4, 1, 2, 5]);
+    assert_eq!(position_ids, vec![0, 1, 2, 3]);
+
+    let (encoding, position_ids) = tokenizer
+        .encode_with_position_ids("world !", 10, false)
+        .unwrap();
+    assert_eq!(encoding.len(), 2);
+    assert_eq!(position_ids, vec![10, 11]);
+
+    // Only the tokens kept by the truncation get a position
+    tokenizer
+        .with_truncation(Some(TruncationParams {
+            max_length: 3,
+            ..Default::default()
+        }))
+        .unwrap();
+    let (_, position_ids) = tokenizer
+        .encode_with_position_ids("hello world !", 5, true)
+        .unwrap();
+    assert_eq!(position_ids, vec![5, 6, 7]);
+
+    assert!(tokenizer
+        .encode_with_position_ids("hello", u32::MAX as usize, false)
+        .is_err());
+}